@@ -0,0 +1,185 @@
+//! generates typed accessors from a ZON schema, so rust services and
+//! typescript frontends read the same data without hand-written offset math.
+//!
+//! ```text
+//! let schema = Schema::parse(&source)?;
+//! let rust = zon_codegen::rust(&schema)?;
+//! let ts = zon_codegen::typescript(&schema, "@zon-lib/zon")?;
+//! ```
+
+mod model;
+mod rust;
+mod typescript;
+
+use zon_lib::schema::Schema;
+
+use model::Model;
+
+/// rust structs over `zon_lib::ZonReader`, one per object type.
+pub fn rust(schema: &Schema) -> Result<String, String> {
+    Ok(rust::render(&Model::new(schema)?))
+}
+
+/// typescript classes over the `zon-wasm` `ZonReader` imported from `module`.
+pub fn typescript(schema: &Schema, module: &str) -> Result<String, String> {
+    typescript::render(&Model::new(schema)?, module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROSTER: &str = r#"
+        type Player = { name: string, score: uint, nick?: string, type: string }
+        type Tags = [string]
+        root { players: [Player], tags: Tags, meta?: { version: uint } }
+    "#;
+
+    #[test]
+    fn test_rust_output() {
+        let schema = Schema::parse(ROSTER).unwrap();
+        let code = rust(&schema).unwrap();
+
+        assert!(code.contains("pub struct Player<'a> {"));
+        assert!(code.contains("pub struct Root<'a> {"));
+        assert!(code.contains("pub struct RootMeta<'a> {"));
+        assert!(code.contains("pub type Tags<'a> = ::zon_lib::ZonList<'a, &'a str>;"));
+        assert!(code.contains("pub fn name(&self) -> ::core::result::Result<&'a str, &'static str> {"));
+        assert!(code.contains("pub fn nick(&self) -> ::core::result::Result<::core::option::Option<&'a str>, &'static str> {"));
+        assert!(code.contains("pub fn r#type(&self)"));
+        assert!(code.contains("pub fn players(&self) -> ::core::result::Result<::zon_lib::ZonList<'a, Player<'a>>, &'static str> {"));
+        assert!(code.contains("pub fn meta(&self) -> ::core::result::Result<::core::option::Option<RootMeta<'a>>, &'static str> {"));
+        assert!(code.contains("pub fn read_root<'a>(reader: &::zon_lib::ZonReader<'a>) -> ::core::result::Result<Root<'a>, &'static str> {"));
+    }
+
+    #[test]
+    fn test_typescript_output() {
+        let schema = Schema::parse(ROSTER).unwrap();
+        let code = typescript(&schema, "@zon-lib/zon").unwrap();
+
+        assert!(code.contains("import { ZonReader } from \"@zon-lib/zon\";"));
+        assert!(code.contains("export class Player {"));
+        assert!(code.contains("export type Tags = ZonList<string>;"));
+        assert!(code.contains("return this.$reader.readText(this.$reader.field(this.$offset, \"name\"));"));
+        assert!(code.contains("get nick(): string | undefined {"));
+        assert!(code.contains("get players(): ZonList<Player> {"));
+        assert!(code.contains("new ZonList(this.$reader, this.$reader.field(this.$offset, \"players\"), (r, a, i) => new Player(r, r.arrayOffset(a, i)))"));
+        // typed arrays hold their elements inline, with no node to point at
+        assert!(code.contains("return new ZonList(reader, offset, (r, a, i) => r.readTextAt(a, i));"));
+        assert!(code.contains("export function readRoot(reader: ZonReader): Root {"));
+    }
+
+    // generated code has to build on its own, not just look right
+    #[test]
+    fn test_rust_output_compiles() {
+        // type names that are keywords, shadow the prelude or name themselves
+        let schema = Schema::parse(&format!(
+            "{}\n{}\n{}",
+            ROSTER.replace("root", "type Roster ="),
+            "type Tree = [Tree]\ntype Even = [Odd]\ntype Odd = [Even]\ntype Result = { a: int }\ntype Option = [int]\ntype match = { b: int }",
+            "root { try: int, as_object: int, fooBar: int, foo_bar: int, self: int, gen: [int], roster: Roster, \
+             tree: Tree, even: Even, result: Result, option?: Option, match: match }",
+        ))
+        .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("zon-codegen-check-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        let core = concat!(env!("CARGO_MANIFEST_DIR"), "/../zon-core");
+        let manifest = format!(
+            "[package]\nname = \"generated\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\nzon-lib = {{ path = {:?} }}\n\n[workspace]\n",
+            core
+        );
+        std::fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+        std::fs::write(dir.join("src/generated.rs"), rust(&schema).unwrap()).unwrap();
+        std::fs::write(
+            dir.join("src/lib.rs"),
+            "#![deny(warnings)]
+pub mod generated;
+
+pub fn touch(reader: &zon_lib::ZonReader) -> Result<(), &'static str> {
+    let root = generated::read_root(reader)?;
+    root.as_object();
+    let _: (i64, i64, i64, i64, i64) = (root.r#try()?, root.as_object2()?, root.foo_bar()?, root.foo_bar2()?, root.self_()?);
+    let _: Vec<i64> = root.r#gen()?.iter().collect::<Result<_, _>>()?;
+    for player in root.roster()?.players()?.iter() {
+        let _: &str = player?.r#type()?;
+    }
+    for child in root.tree()?.0.iter() {
+        let _: generated::Tree = child?;
+    }
+    let _: Option<generated::Odd> = root.even()?.0.get(0).ok();
+    let _: (i64, i64) = (root.result()?.a()?, root.r#match()?.b()?);
+    let _: Option<generated::Option> = root.option()?;
+    Ok(())
+}
+",
+        )
+        .unwrap();
+
+        let output = std::process::Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+            .args(["check", "--offline", "--quiet"])
+            .current_dir(&dir)
+            .env("CARGO_TARGET_DIR", concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/codegen-check"))
+            .output()
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    #[test]
+    fn test_typescript_typed_arrays() {
+        let schema = Schema::parse("type Score = uint\nroot { hp: [int], fills: [bool], scores: [Score], any: [any], bins: [bytes] }").unwrap();
+        let code = typescript(&schema, "@zon-lib/zon").unwrap();
+        let list = |field: &str, read: &str| {
+            format!("new ZonList(this.$reader, this.$reader.field(this.$offset, {:?}), (r, a, i) => {})", field, read)
+        };
+        assert!(code.contains(&list("hp", "r.readNumberAt(a, i)")));
+        assert!(code.contains(&list("fills", "r.readBoolAt(a, i)")));
+        assert!(code.contains(&list("scores", "r.readNumberAt(a, i)")));
+        assert!(code.contains(&list("any", "r.readValueAt(a, i)")));
+        assert!(code.contains(&list("bins", "r.readBinary(r.arrayOffset(a, i))")));
+    }
+
+    #[test]
+    fn test_scalar_root() {
+        let schema = Schema::parse("root [{ id: uint }]").unwrap();
+        let code = rust(&schema).unwrap();
+        assert!(code.contains("pub struct RootItem<'a> {"));
+        assert!(code.contains("Result<::zon_lib::ZonList<'a, RootItem<'a>>, &'static str>"));
+    }
+
+    #[test]
+    fn test_recursive_aliases() {
+        let schema = Schema::parse("type Tree = [Tree]\nroot Tree").unwrap();
+        let code = rust(&schema).unwrap();
+        assert!(code.contains("pub struct Tree<'a>(pub ::zon_lib::ZonList<'a, Tree<'a>>);"));
+        assert!(typescript(&schema, "@zon-lib/zon").unwrap().contains("export type Tree = ZonList<Tree>;"));
+
+        // aliases that only name each other describe no value at all
+        let schema = Schema::parse("type A = B\ntype B = A\nroot A").unwrap();
+        assert_eq!(rust(&schema).unwrap_err(), "type `A` is defined only in terms of itself");
+    }
+
+    #[test]
+    fn test_typescript_name_clashes() {
+        for (source, error) in [
+            ("type ZonList = [int]\nroot ZonList", "type `ZonList` clashes with the generated TypeScript `ZonList`"),
+            ("type ZonReader = { a: int }\nroot ZonReader", "type `ZonReader` clashes with the generated TypeScript `ZonReader`"),
+            ("type Root = [int]\nroot { a: Root }", "type `Root` clashes with the generated TypeScript `readRoot`"),
+        ] {
+            let schema = Schema::parse(source).unwrap();
+            assert_eq!(typescript(&schema, "@zon-lib/zon").unwrap_err(), error);
+            assert!(rust(&schema).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(model::snake_case("playerName"), "player_name");
+        assert_eq!(model::snake_case("player-name"), "player_name");
+        assert_eq!(model::snake_case("HP"), "hp");
+        assert_eq!(model::snake_case("2d"), "_2d");
+        assert_eq!(model::pascal_case("player_name"), "PlayerName");
+    }
+}
@@ -0,0 +1,85 @@
+use std::env;
+use std::fs;
+use std::process;
+
+use zon_lib::schema::Schema;
+
+const USAGE: &str = "Usage: zon-codegen <schema.zons> [--rust <out.rs>] [--ts <out.ts>] [--module <import path>]";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let mut schema_path = None;
+    let mut rust_out = None;
+    let mut ts_out = None;
+    let mut module = "@zon-lib/zon".to_string();
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        let slot = match arg.as_str() {
+            "--rust" => &mut rust_out,
+            "--ts" => &mut ts_out,
+            "--module" => {
+                module = iter.next().unwrap_or_else(|| usage());
+                continue;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if schema_path.is_none() && !arg.starts_with('-') => {
+                schema_path = Some(arg);
+                continue;
+            }
+            _ => usage(),
+        };
+        *slot = Some(iter.next().unwrap_or_else(|| usage()));
+    }
+
+    let schema_path = schema_path.unwrap_or_else(|| usage());
+    let source = match fs::read_to_string(&schema_path) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error reading file '{}': {}", schema_path, e);
+            process::exit(1);
+        }
+    };
+    let schema = match Schema::parse(&source) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error parsing schema '{}': {}", schema_path, e);
+            process::exit(1);
+        }
+    };
+
+    // with no outputs given, print the rust accessors
+    if rust_out.is_none() && ts_out.is_none() {
+        print!("{}", generate(&schema_path, zon_codegen::rust(&schema)));
+        return;
+    }
+    if let Some(path) = rust_out {
+        write(&path, &generate(&schema_path, zon_codegen::rust(&schema)));
+    }
+    if let Some(path) = ts_out {
+        write(&path, &generate(&schema_path, zon_codegen::typescript(&schema, &module)));
+    }
+}
+
+fn generate(schema_path: &str, code: Result<String, String>) -> String {
+    code.unwrap_or_else(|e| {
+        eprintln!("Error generating code from '{}': {}", schema_path, e);
+        process::exit(1);
+    })
+}
+
+fn write(path: &str, code: &str) {
+    if let Err(e) = fs::write(path, code) {
+        eprintln!("Error writing file '{}': {}", path, e);
+        process::exit(1);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(1);
+}
@@ -0,0 +1,205 @@
+use zon_lib::schema::{ObjectType, Schema, SchemaType};
+
+/// a schema type after anonymous objects have been given names.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeRef {
+    /// a primitive schema type (never `Array`, `Object` or `Named`).
+    Prim(SchemaType),
+    List(Box<TypeRef>),
+    Struct(String),
+    Alias(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct StructDecl {
+    pub name: String,
+    pub fields: Vec<FieldDecl>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldDecl {
+    /// the key as stored in the data.
+    pub key: String,
+    pub ty: TypeRef,
+    pub optional: bool,
+}
+
+/// everything the renderers need: one struct per object type,
+/// one alias per named non-object type, and the root type.
+#[derive(Debug, Clone)]
+pub struct Model {
+    pub structs: Vec<StructDecl>,
+    pub aliases: Vec<(String, TypeRef)>,
+    pub root: TypeRef,
+}
+
+impl Model {
+    /// fails on aliases that only name each other, e.g. `type A = B` and
+    /// `type B = A`, which no value can match.
+    pub fn new(schema: &Schema) -> Result<Self, String> {
+        let mut builder = Builder { schema, structs: Vec::new(), taken: schema.types.keys().cloned().collect() };
+
+        let mut aliases = Vec::new();
+        for (name, ty) in &schema.types {
+            match ty {
+                SchemaType::Object(object) => builder.object(name.clone(), object),
+                _ => {
+                    let target = builder.lower(ty, name);
+                    aliases.push((name.clone(), target));
+                }
+            }
+        }
+
+        let root = builder.lower(&schema.root, "Root");
+
+        let mut structs = builder.structs;
+        structs.sort_by(|a, b| a.name.cmp(&b.name));
+        let model = Self { structs, aliases, root };
+        for (name, _) in &model.aliases {
+            let mut seen = vec![name.as_str()];
+            let mut target = model.target(name);
+            while let Some(TypeRef::Alias(next)) = target {
+                if seen.contains(&next.as_str()) {
+                    return Err(format!("type `{}` is defined only in terms of itself", name));
+                }
+                seen.push(next);
+                target = model.target(next);
+            }
+        }
+        Ok(model)
+    }
+
+    /// the type an alias stands for.
+    pub fn target(&self, name: &str) -> Option<&TypeRef> {
+        self.aliases.iter().find(|(alias, _)| alias == name).map(|(_, target)| target)
+    }
+
+    /// whether an alias refers back to itself, e.g. `type Tree = [Tree]`.
+    /// structs end a cycle, since they are named types in both outputs.
+    pub fn is_recursive(&self, name: &str) -> bool {
+        let mut seen: Vec<&str> = Vec::new();
+        let mut pending: Vec<&TypeRef> = self.target(name).into_iter().collect();
+        while let Some(ty) = pending.pop() {
+            match ty {
+                TypeRef::List(item) => pending.push(item),
+                TypeRef::Alias(alias) if alias == name => return true,
+                TypeRef::Alias(alias) if !seen.contains(&alias.as_str()) => {
+                    seen.push(alias);
+                    pending.extend(self.target(alias));
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// whether the rust type borrows from the buffer.
+    pub fn borrows(&self, ty: &TypeRef) -> bool {
+        self.borrows_inner(ty, 0)
+    }
+
+    fn borrows_inner(&self, ty: &TypeRef, depth: usize) -> bool {
+        match ty {
+            TypeRef::Prim(SchemaType::String | SchemaType::Binary | SchemaType::Any) => true,
+            TypeRef::Prim(_) => false,
+            TypeRef::List(_) | TypeRef::Struct(_) => true,
+            // a self-referencing alias can only be built from lists, which borrow anyway
+            TypeRef::Alias(_) if depth > self.aliases.len() => true,
+            TypeRef::Alias(name) => self.target(name).is_none_or(|target| self.borrows_inner(target, depth + 1)),
+        }
+    }
+}
+
+struct Builder<'s> {
+    schema: &'s Schema,
+    structs: Vec<StructDecl>,
+    taken: Vec<String>,
+}
+
+impl Builder<'_> {
+    fn object(&mut self, name: String, object: &ObjectType) {
+        let fields = object
+            .fields
+            .iter()
+            .map(|field| {
+                let hint = format!("{}{}", name, pascal_case(&field.name));
+                FieldDecl { key: field.name.clone(), ty: self.lower(&field.ty, &hint), optional: field.optional }
+            })
+            .collect();
+        self.structs.push(StructDecl { name, fields });
+    }
+
+    // `hint` names an anonymous object found at this position.
+    fn lower(&mut self, ty: &SchemaType, hint: &str) -> TypeRef {
+        match ty {
+            SchemaType::Array(item) => TypeRef::List(Box::new(self.lower(item, &format!("{}Item", hint)))),
+            SchemaType::Object(object) => {
+                let name = self.fresh(hint);
+                self.object(name.clone(), object);
+                TypeRef::Struct(name)
+            }
+            SchemaType::Named(name) => match self.schema.types.get(name) {
+                Some(SchemaType::Object(_)) => TypeRef::Struct(name.clone()),
+                Some(_) => TypeRef::Alias(name.clone()),
+                None => TypeRef::Prim(SchemaType::Any),
+            },
+            prim => TypeRef::Prim(prim.clone()),
+        }
+    }
+
+    fn fresh(&mut self, hint: &str) -> String {
+        let mut name = hint.to_string();
+        let mut n = 2;
+        while self.taken.contains(&name) {
+            name = format!("{}{}", hint, n);
+            n += 1;
+        }
+        self.taken.push(name.clone());
+        name
+    }
+}
+
+/// `player_name`, `player-name` and `playerName` all become `PlayerName`.
+pub fn pascal_case(s: &str) -> String {
+    let mut out = String::new();
+    let mut upper = true;
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            if upper {
+                out.push(c.to_ascii_uppercase());
+            } else {
+                out.push(c);
+            }
+            upper = false;
+        } else {
+            upper = true;
+        }
+    }
+    out
+}
+
+/// `playerName`, `player-name` and `PlayerName` all become `player_name`.
+pub fn snake_case(s: &str) -> String {
+    let mut out = String::new();
+    let mut prev_lower = false;
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && prev_lower {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        } else {
+            if !out.is_empty() && !out.ends_with('_') {
+                out.push('_');
+            }
+            prev_lower = false;
+        }
+    }
+    let out = out.trim_end_matches('_').to_string();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", out)
+    } else {
+        out
+    }
+}
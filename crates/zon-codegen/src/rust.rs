@@ -0,0 +1,178 @@
+use std::fmt::Write;
+
+use zon_lib::schema::SchemaType;
+
+use crate::model::{snake_case, Model, TypeRef};
+
+// strict and reserved keywords, including those reserved for later editions
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn", "else",
+    "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match",
+    "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "static", "struct", "super", "trait",
+    "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+// methods every generated struct has besides the accessors
+const GENERATED_METHODS: &[&str] = &["as_object"];
+
+/// renders zero-copy accessor structs over `zon_lib::ZonReader`.
+///
+/// paths into `zon_lib` and the prelude are written out in full, so schema
+/// types named e.g. `Result` or `Option` cannot shadow them.
+pub fn render(model: &Model) -> String {
+    let mut out = String::new();
+    out.push_str("// generated by zon-codegen. do not edit.\n");
+
+    for (name, target) in &model.aliases {
+        if model.is_recursive(name) {
+            // a type alias cannot name itself, but a struct can
+            let _ = write!(
+                out,
+                "
+{allow}#[derive(Debug, Clone, Copy)]
+pub struct {name}<'a>(pub {ty});
+
+impl<'a> ::zon_lib::FromZon<'a> for {name}<'a> {{
+    fn from_zon(value: ::zon_lib::ZonValue<'a>) -> ::core::result::Result<Self, &'static str> {{
+        ::zon_lib::FromZon::from_zon(value).map(Self)
+    }}
+}}
+",
+                allow = allow_case(name),
+                name = type_name(name),
+                ty = rust_type(model, target),
+            );
+        } else {
+            let _ = write!(
+                out,
+                "\n{}pub type {} = {};\n",
+                allow_case(name),
+                with_lifetime(model, name, &TypeRef::Alias(name.clone())),
+                rust_type(model, target)
+            );
+        }
+    }
+
+    for decl in &model.structs {
+        let _ = write!(
+            out,
+            "
+{allow}#[derive(Debug, Clone, Copy)]
+pub struct {name}<'a> {{
+    object: ::zon_lib::ZonObject<'a>,
+}}
+
+impl<'a> ::zon_lib::FromZon<'a> for {name}<'a> {{
+    fn from_zon(value: ::zon_lib::ZonValue<'a>) -> ::core::result::Result<Self, &'static str> {{
+        let object = value.as_object().ok_or(\"Expected object\")?;
+        ::core::result::Result::Ok(Self {{ object }})
+    }}
+}}
+
+impl<'a> {name}<'a> {{
+    pub fn as_object(&self) -> ::zon_lib::ZonObject<'a> {{
+        self.object
+    }}
+",
+            allow = allow_case(&decl.name),
+            name = type_name(&decl.name)
+        );
+        let mut taken: Vec<String> = GENERATED_METHODS.iter().map(|name| name.to_string()).collect();
+        for field in &decl.fields {
+            let method = method_name(&field.key, &mut taken);
+            let ty = rust_type(model, &field.ty);
+            let (ty, getter) = if field.optional {
+                (format!("::core::option::Option<{}>", ty), "optional_field")
+            } else {
+                (ty, "field")
+            };
+            let _ = write!(
+                out,
+                "
+    pub fn {method}(&self) -> ::core::result::Result<{ty}, &'static str> {{
+        self.object.{getter}({key:?})
+    }}
+",
+                key = field.key,
+            );
+        }
+        out.push_str("}\n");
+    }
+
+    let _ = write!(
+        out,
+        "
+/// reads the root value of `reader`.
+pub fn read_root<'a>(reader: &::zon_lib::ZonReader<'a>) -> ::core::result::Result<{}, &'static str> {{
+    ::zon_lib::FromZon::from_zon(reader.root_value()?)
+}}
+",
+        rust_type(model, &model.root)
+    );
+    out
+}
+
+fn rust_type(model: &Model, ty: &TypeRef) -> String {
+    match ty {
+        TypeRef::Prim(prim) => match prim {
+            SchemaType::Null => "()",
+            SchemaType::Bool => "bool",
+            SchemaType::Int => "i64",
+            SchemaType::UInt => "u64",
+            SchemaType::Float | SchemaType::Number => "f64",
+            SchemaType::String => "&'a str",
+            SchemaType::Binary => "&'a [u8]",
+            _ => "::zon_lib::ZonValue<'a>",
+        }
+        .to_string(),
+        TypeRef::List(item) => format!("::zon_lib::ZonList<'a, {}>", rust_type(model, item)),
+        TypeRef::Struct(name) => format!("{}<'a>", type_name(name)),
+        TypeRef::Alias(name) => with_lifetime(model, name, ty),
+    }
+}
+
+fn with_lifetime(model: &Model, name: &str, ty: &TypeRef) -> String {
+    if model.borrows(ty) {
+        format!("{}<'a>", type_name(name))
+    } else {
+        type_name(name)
+    }
+}
+
+// schema type names are used as they are, unless they are keywords
+fn type_name(name: &str) -> String {
+    escape(name.to_string())
+}
+
+// so that e.g. `type point = ...` builds under `#![deny(warnings)]`
+fn allow_case(name: &str) -> &'static str {
+    if name.starts_with(|c: char| c.is_ascii_uppercase()) && !name.contains('_') {
+        ""
+    } else {
+        "#[allow(non_camel_case_types)]\n"
+    }
+}
+
+// keys that map to a taken name, e.g. `fooBar` next to `foo_bar`, get a number
+fn method_name(key: &str, taken: &mut Vec<String>) -> String {
+    let base = snake_case(key);
+    let mut name = base.clone();
+    let mut n = 2;
+    while taken.contains(&name) {
+        name = format!("{}{}", base, n);
+        n += 1;
+    }
+    taken.push(name.clone());
+    escape(name)
+}
+
+fn escape(name: String) -> String {
+    if matches!(name.as_str(), "self" | "Self" | "super" | "crate") {
+        // these cannot be raw identifiers
+        format!("{}_", name)
+    } else if KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else {
+        name
+    }
+}
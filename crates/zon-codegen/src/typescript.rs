@@ -0,0 +1,194 @@
+use std::fmt::Write;
+
+use zon_lib::schema::SchemaType;
+
+use crate::model::{Model, TypeRef};
+
+const PRELUDE: &str = "
+export class ZonList<T> implements Iterable<T> {
+  constructor(
+    readonly $reader: ZonReader,
+    readonly $offset: number,
+    private readonly read: (reader: ZonReader, array: number, index: number) => T,
+  ) {}
+
+  get length(): number {
+    return this.$reader.arrayLength(this.$offset);
+  }
+
+  get(index: number): T {
+    return this.read(this.$reader, this.$offset, index);
+  }
+
+  *[Symbol.iterator](): Iterator<T> {
+    const length = this.length;
+    for (let i = 0; i < length; i++) {
+      yield this.get(i);
+    }
+  }
+}
+";
+
+/// renders classes wrapping the `ZonReader` exported by `zon-wasm`.
+/// `module` is the import path of the wasm package. fails on type names
+/// that clash with the names the output declares itself.
+pub fn render(model: &Model, module: &str) -> Result<String, String> {
+    let names = model.aliases.iter().map(|(name, _)| name).chain(model.structs.iter().map(|decl| &decl.name));
+    for name in names {
+        if matches!(name.as_str(), "ZonList" | "ZonReader") {
+            return Err(format!("type `{}` clashes with the generated TypeScript `{}`", name, name));
+        }
+    }
+    // aliases get a `read{name}` function, next to the `readRoot` below
+    if model.target("Root").is_some() {
+        return Err("type `Root` clashes with the generated TypeScript `readRoot`".to_string());
+    }
+
+    let mut out = String::new();
+    out.push_str("// generated by zon-codegen. do not edit.\n");
+    let _ = writeln!(out, "import {{ ZonReader }} from {:?};", module);
+    out.push_str(PRELUDE);
+
+    for (name, target) in &model.aliases {
+        let _ = write!(
+            out,
+            "
+export type {name} = {ty};
+
+export function read{name}(reader: ZonReader, offset: number): {name} {{
+  return {read};
+}}
+",
+            ty = ts_type(target),
+            read = read_expr(model, target, "reader", "offset"),
+        );
+    }
+
+    for decl in &model.structs {
+        let _ = write!(
+            out,
+            "
+export class {} {{
+  constructor(readonly $reader: ZonReader, readonly $offset: number) {{}}
+",
+            decl.name
+        );
+        for field in &decl.fields {
+            let ty = ts_type(&field.ty);
+            let key = property_name(&field.key);
+            let lookup = format!("{:?}", field.key);
+            if field.optional {
+                let _ = write!(
+                    out,
+                    "
+  get {key}(): {ty} | undefined {{
+    const offset = this.$reader.findField(this.$offset, {lookup});
+    return offset === undefined ? undefined : {read};
+  }}
+",
+                    read = read_expr(model, &field.ty, "this.$reader", "offset"),
+                );
+            } else {
+                let offset = format!("this.$reader.field(this.$offset, {})", lookup);
+                let _ = write!(
+                    out,
+                    "
+  get {key}(): {ty} {{
+    return {read};
+  }}
+",
+                    read = read_expr(model, &field.ty, "this.$reader", &offset),
+                );
+            }
+        }
+        out.push_str("}\n");
+    }
+
+    let _ = write!(
+        out,
+        "
+export function readRoot(reader: ZonReader): {} {{
+  return {};
+}}
+",
+        ts_type(&model.root),
+        read_expr(model, &model.root, "reader", "reader.rootOffset"),
+    );
+    Ok(out)
+}
+
+fn ts_type(ty: &TypeRef) -> String {
+    match ty {
+        TypeRef::Prim(prim) => match prim {
+            SchemaType::Null => "null",
+            SchemaType::Bool => "boolean",
+            SchemaType::Int | SchemaType::UInt | SchemaType::Float | SchemaType::Number => "number",
+            SchemaType::String => "string",
+            SchemaType::Binary => "Uint8Array",
+            _ => "unknown",
+        }
+        .to_string(),
+        TypeRef::List(item) => format!("ZonList<{}>", ts_type(item)),
+        TypeRef::Struct(name) | TypeRef::Alias(name) => name.clone(),
+    }
+}
+
+fn read_expr(model: &Model, ty: &TypeRef, reader: &str, offset: &str) -> String {
+    match ty {
+        TypeRef::Prim(prim) => match prim {
+            SchemaType::Null => format!("{}.readNull({})", reader, offset),
+            SchemaType::Bool => format!("{}.readBool({})", reader, offset),
+            SchemaType::Int | SchemaType::UInt | SchemaType::Float | SchemaType::Number => {
+                format!("{}.readNumber({})", reader, offset)
+            }
+            SchemaType::String => format!("{}.readText({})", reader, offset),
+            SchemaType::Binary => format!("{}.readBinary({})", reader, offset),
+            _ => format!("{}.readValue({})", reader, offset),
+        },
+        TypeRef::List(item) => format!("new ZonList({}, {}, (r, a, i) => {})", reader, offset, element_expr(model, item)),
+        TypeRef::Struct(name) => format!("new {}({}, {})", name, reader, offset),
+        TypeRef::Alias(name) => format!("read{}({}, {})", name, reader, offset),
+    }
+}
+
+// reads element `i` of the array at `a`. typed arrays store bools, numbers
+// and strings inline, so those are read by index rather than through a node.
+fn element_expr(model: &Model, item: &TypeRef) -> String {
+    let method = match resolve(model, item) {
+        TypeRef::Prim(SchemaType::Bool) => "readBoolAt",
+        TypeRef::Prim(SchemaType::Int | SchemaType::UInt | SchemaType::Float | SchemaType::Number) => "readNumberAt",
+        TypeRef::Prim(SchemaType::String) => "readTextAt",
+        TypeRef::Prim(SchemaType::Null | SchemaType::Binary) => return node_expr(model, item),
+        TypeRef::Prim(_) => "readValueAt",
+        _ => return node_expr(model, item),
+    };
+    format!("r.{}(a, i)", method)
+}
+
+fn node_expr(model: &Model, item: &TypeRef) -> String {
+    read_expr(model, item, "r", "r.arrayOffset(a, i)")
+}
+
+// follows aliases to the type they stand for. `Model::new` refuses aliases
+// that only name each other, so this ends.
+fn resolve<'m>(model: &'m Model, ty: &'m TypeRef) -> &'m TypeRef {
+    let mut ty = ty;
+    while let Some(target) = match ty {
+        TypeRef::Alias(name) => model.target(name),
+        _ => None,
+    } {
+        ty = target;
+    }
+    ty
+}
+
+fn property_name(key: &str) -> String {
+    let mut chars = key.chars();
+    let ident = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if ident {
+        key.to_string()
+    } else {
+        format!("[{:?}]", key)
+    }
+}
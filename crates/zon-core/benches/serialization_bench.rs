@@ -1,6 +1,6 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use serde::{Deserialize, Serialize};
-use zon_lib::{ZonReader, ZonWriter};
+use zon_lib::{ZonReader, ZonWriter, ZonWriterPool};
 
 #[derive(Serialize, Deserialize)]
 struct Player {
@@ -28,11 +28,8 @@ fn bench_serialization(c: &mut Criterion) {
     
     // 2. pad to 64-byte alignment
     // we are at some offset. Note: write_string guarantees 4-byte aligned end.
-    // we need to write padding zeros until writer.len() % 64 == 0.
-    // effective padding can be done by writing u32(0) repeatedly since we are 4-byte aligned.
-    while writer.len() % 64 != 0 {
-        writer.write_u32(0);
-    }
+    // pad_to writes zeros until writer.len() % 64 == 0.
+    writer.pad_to(64);
     
     let struct_start = writer.len() as u32;
     // 3. write fields
@@ -79,5 +76,49 @@ fn bench_serialization(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_serialization);
+// one small message, as a server would send it
+fn write_message(writer: &mut ZonWriter, player: &Player) -> usize {
+    let id = writer.write_uint(player.id as u64);
+    let score = writer.write_uint(player.score as u64);
+    let name = writer.write_text(&player.name);
+    let root = writer.write_object(&[("id", id), ("score", score), ("name", name)]);
+    writer.set_root(root);
+    writer.len()
+}
+
+fn bench_writer_reuse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("writer_reuse");
+    let player = Player {
+        id: 123456,
+        score: 999000,
+        name: "Zaim".to_string(),
+    };
+
+    group.bench_function("new_writer", |b| {
+        b.iter(|| {
+            let mut writer = ZonWriter::new();
+            black_box(write_message(&mut writer, black_box(&player)));
+        })
+    });
+
+    let mut writer = ZonWriter::new();
+    group.bench_function("reset", |b| {
+        b.iter(|| {
+            writer.reset();
+            black_box(write_message(&mut writer, black_box(&player)));
+        })
+    });
+
+    let pool = ZonWriterPool::new(4);
+    group.bench_function("pool", |b| {
+        b.iter(|| {
+            let mut writer = pool.get();
+            black_box(write_message(&mut writer, black_box(&player)));
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_serialization, bench_writer_reuse);
 criterion_main!(benches);
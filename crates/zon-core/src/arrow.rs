@@ -0,0 +1,331 @@
+//! conversion between columnar ZON tables and Arrow record batches.
+//!
+//! typed arrays map to Arrow arrays directly: `int[]` to `Int64`, `float[]`
+//! to `Float64`, `bool[]` to `Boolean` and `string[]` to `Utf8`. integer,
+//! float and string columns share the ZON buffer instead of being copied,
+//! as long as the elements are aligned in memory; bool columns are always
+//! copied, since Arrow packs them into bits.
+//!
+//! Arrow columns with nulls (or of types without a typed array, such as
+//! `Binary`) are written as plain arrays of nodes, with `null` nodes for the
+//! nulls, and read back as nullable Arrow columns.
+
+use std::io::Cursor;
+use std::sync::Arc;
+
+use ::arrow::array::{
+    Array, ArrayRef, AsArray, BinaryArray, BooleanArray, Float64Array, Int64Array, NullArray, RecordBatch,
+    RecordBatchOptions, StringArray, UInt64Array,
+};
+use ::arrow::buffer::{Buffer, OffsetBuffer, ScalarBuffer};
+use ::arrow::datatypes::{self as types, DataType, Field, Schema, SchemaRef};
+use ::arrow::error::ArrowError;
+use ::arrow::ipc::reader::{FileReader, StreamReader};
+use ::arrow::ipc::writer::FileWriter;
+
+use crate::reader::ZonReader;
+use crate::table::ZonTable;
+use crate::value::{Tag, ZonArray, ZonValue};
+use crate::writer::ZonWriter;
+
+/// reads the root table of a ZON buffer as a record batch.
+/// aligned numeric and string columns are slices of `buffer`, not copies.
+pub fn to_record_batch(buffer: &Buffer) -> Result<RecordBatch, ArrowError> {
+    if cfg!(target_endian = "big") {
+        return Err(ArrowError::NotYetImplemented("ZON tables on big-endian targets".to_string()));
+    }
+    let reader = ZonReader::new(buffer.as_slice()).map_err(zon_error)?;
+    let root = reader.root_value().map_err(zon_error)?;
+    let table = ZonTable::new(root.as_object().ok_or(zon_error("Expected object"))?).map_err(zon_error)?;
+
+    let mut fields = Vec::new();
+    let mut columns = Vec::new();
+    for name in table.columns() {
+        let name = name.map_err(zon_error)?;
+        let array = table.array(name).map_err(zon_error)?;
+        let column = to_arrow(buffer, array).map_err(|e| column_error(name, e))?;
+        fields.push(Field::new(name, column.data_type().clone(), column.null_count() > 0));
+        columns.push(column);
+    }
+    let options = RecordBatchOptions::new().with_row_count(Some(table.rows()));
+    RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), columns, &options)
+}
+
+/// writes record batches with a shared schema as one ZON table.
+pub fn from_record_batches(schema: &Schema, batches: &[RecordBatch]) -> Result<Vec<u8>, ArrowError> {
+    let mut writer = ZonWriter::new();
+    let mut columns = Vec::with_capacity(schema.fields().len());
+    for (i, field) in schema.fields().iter().enumerate() {
+        let arrays: Vec<&ArrayRef> = batches.iter().map(|batch| batch.column(i)).collect();
+        let offset = write_column(&mut writer, field.data_type(), &arrays).map_err(|e| column_error(field.name(), e))?;
+        columns.push((field.name().as_str(), offset));
+    }
+    let root = writer.write_object(&columns);
+    Ok(writer.finish(root))
+}
+
+/// writes a record batch as a ZON table.
+pub fn from_record_batch(batch: &RecordBatch) -> Result<Vec<u8>, ArrowError> {
+    from_record_batches(&batch.schema(), std::slice::from_ref(batch))
+}
+
+/// converts Arrow IPC data (file or stream format) into a ZON table.
+pub fn encode(ipc: &[u8]) -> Result<Vec<u8>, ArrowError> {
+    let (schema, batches): (SchemaRef, Vec<RecordBatch>) = if ipc.starts_with(b"ARROW1") {
+        let reader = FileReader::try_new(Cursor::new(ipc), None)?;
+        (reader.schema(), reader.collect::<Result<_, _>>()?)
+    } else {
+        let reader = StreamReader::try_new(ipc, None)?;
+        (reader.schema(), reader.collect::<Result<_, _>>()?)
+    };
+    from_record_batches(&schema, &batches)
+}
+
+/// converts the root table of `reader` into an Arrow IPC file.
+pub fn decode(reader: &ZonReader) -> Result<Vec<u8>, ArrowError> {
+    let batch = to_record_batch(&Buffer::from(reader.as_bytes()))?;
+    let mut writer = FileWriter::try_new(Vec::new(), &batch.schema())?;
+    writer.write(&batch)?;
+    writer.into_inner()
+}
+
+fn to_arrow(buffer: &Buffer, array: ZonArray) -> Result<ArrayRef, &'static str> {
+    let data = array.offset() as usize + 8;
+    let len = array.len();
+    let column: ArrayRef = match array.tag() {
+        Tag::IntArray => Arc::new(Int64Array::new(scalars(buffer, data, len), None)),
+        Tag::FloatArray => Arc::new(Float64Array::new(scalars(buffer, data, len), None)),
+        Tag::BoolArray => {
+            let values = array.iter().map(|v| v?.as_bool().ok_or("Expected bool")).collect::<Result<Vec<_>, _>>()?;
+            Arc::new(BooleanArray::from(values))
+        }
+        Tag::StringArray => {
+            let reader = ZonReader::new(buffer.as_slice())?;
+            let mut last = 0;
+            for i in 0..=len {
+                let end = reader.read_u32((data + i * 4) as u32)?;
+                if end < last || end > i32::MAX as u32 {
+                    return Err("Invalid string array offsets");
+                }
+                last = end;
+            }
+            let offsets = OffsetBuffer::new(scalars::<i32>(buffer, data, len + 1));
+            let values = buffer.slice_with_length(data + (len + 1) * 4, last as usize);
+            Arc::new(StringArray::try_new(offsets, values, None).map_err(|_| "Invalid UTF-8")?)
+        }
+        _ => nodes_to_arrow(array)?,
+    };
+    Ok(column)
+}
+
+/// `len` elements at byte offset `at`, shared with `buffer` when aligned.
+fn scalars<T: types::ArrowNativeType>(buffer: &Buffer, at: usize, len: usize) -> ScalarBuffer<T> {
+    let slice = buffer.slice_with_length(at, len * std::mem::size_of::<T>());
+    if slice.as_ptr().align_offset(std::mem::align_of::<T>()) == 0 {
+        ScalarBuffer::from(slice)
+    } else {
+        ScalarBuffer::from(Buffer::from_slice_ref(slice.as_slice()))
+    }
+}
+
+/// builds a nullable column from an array of scalar nodes.
+fn nodes_to_arrow(array: ZonArray) -> Result<ArrayRef, &'static str> {
+    let values = array.iter().collect::<Result<Vec<_>, _>>()?;
+    let all = |f: fn(&ZonValue) -> bool| values.iter().all(|v| matches!(v, ZonValue::Null) || f(v));
+
+    let column: ArrayRef = if all(|_| false) {
+        Arc::new(NullArray::new(values.len()))
+    } else if all(|v| matches!(v, ZonValue::Bool(_))) {
+        Arc::new(values.iter().map(ZonValue::as_bool).collect::<BooleanArray>())
+    } else if all(|v| v.as_i64().is_some()) {
+        Arc::new(values.iter().map(ZonValue::as_i64).collect::<Int64Array>())
+    } else if all(|v| v.as_u64().is_some()) {
+        Arc::new(values.iter().map(ZonValue::as_u64).collect::<UInt64Array>())
+    } else if all(|v| v.as_f64().is_some()) {
+        Arc::new(values.iter().map(ZonValue::as_f64).collect::<Float64Array>())
+    } else if all(|v| matches!(v, ZonValue::String(_))) {
+        Arc::new(values.iter().map(ZonValue::as_str).collect::<StringArray>())
+    } else if all(|v| matches!(v, ZonValue::Binary(_))) {
+        Arc::new(values.iter().map(ZonValue::as_binary).collect::<BinaryArray>())
+    } else {
+        return Err("Column mixes types or holds containers");
+    };
+    Ok(column)
+}
+
+/// one cell of an Arrow column.
+#[derive(Debug, Clone, Copy)]
+enum Cell<'a> {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(&'a str),
+    Binary(&'a [u8]),
+}
+
+fn write_column(writer: &mut ZonWriter, data_type: &DataType, arrays: &[&ArrayRef]) -> Result<u32, String> {
+    let mut cells = Vec::new();
+    for array in arrays {
+        for i in 0..array.len() {
+            cells.push(cell(array.as_ref(), i)?);
+        }
+    }
+
+    // columns without nulls become typed arrays, everything else plain nodes
+    let typed = match data_type {
+        DataType::Boolean => {
+            collect(&cells, |c| match *c {
+                Cell::Bool(b) => Some(b),
+                _ => None,
+            })
+            .map(|v| writer.write_bool_array(&v))
+        }
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+            collect(&cells, |c| match *c {
+                Cell::Float(v) => Some(v),
+                _ => None,
+            })
+            .map(|v| writer.write_float_array(&v))
+        }
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => {
+            collect(&cells, |c| match *c {
+                Cell::String(s) => Some(s),
+                _ => None,
+            })
+            .map(|v| writer.write_string_array(&v))
+        }
+        t if t.is_integer() => {
+            collect(&cells, |c| match *c {
+                Cell::Int(v) => Some(v),
+                Cell::UInt(v) => i64::try_from(v).ok(),
+                _ => None,
+            })
+            .map(|v| writer.write_int_array(&v))
+        }
+        _ => None,
+    };
+    if let Some(offset) = typed {
+        return Ok(offset);
+    }
+
+    let items: Vec<u32> = cells
+        .iter()
+        .map(|c| match *c {
+            Cell::Null => writer.write_null(),
+            Cell::Bool(b) => writer.write_bool(b),
+            Cell::Int(v) => writer.write_int(v),
+            Cell::UInt(v) => writer.write_uint(v),
+            Cell::Float(v) => writer.write_float(v),
+            Cell::String(s) => writer.write_text(s),
+            Cell::Binary(b) => writer.write_binary(b),
+        })
+        .collect();
+    Ok(writer.write_array(&items))
+}
+
+/// every cell mapped by `f`, or `None` if any of them is not.
+fn collect<'a, T>(cells: &[Cell<'a>], f: impl Fn(&Cell<'a>) -> Option<T>) -> Option<Vec<T>> {
+    cells.iter().map(f).collect()
+}
+
+fn cell(array: &dyn Array, i: usize) -> Result<Cell<'_>, String> {
+    if array.is_null(i) {
+        return Ok(Cell::Null);
+    }
+    let cell = match array.data_type() {
+        DataType::Null => Cell::Null,
+        DataType::Boolean => Cell::Bool(array.as_boolean().value(i)),
+        DataType::Int8 => Cell::Int(array.as_primitive::<types::Int8Type>().value(i).into()),
+        DataType::Int16 => Cell::Int(array.as_primitive::<types::Int16Type>().value(i).into()),
+        DataType::Int32 => Cell::Int(array.as_primitive::<types::Int32Type>().value(i).into()),
+        DataType::Int64 => Cell::Int(array.as_primitive::<types::Int64Type>().value(i)),
+        DataType::UInt8 => Cell::Int(array.as_primitive::<types::UInt8Type>().value(i).into()),
+        DataType::UInt16 => Cell::Int(array.as_primitive::<types::UInt16Type>().value(i).into()),
+        DataType::UInt32 => Cell::Int(array.as_primitive::<types::UInt32Type>().value(i).into()),
+        DataType::UInt64 => Cell::UInt(array.as_primitive::<types::UInt64Type>().value(i)),
+        DataType::Float16 => Cell::Float(array.as_primitive::<types::Float16Type>().value(i).into()),
+        DataType::Float32 => Cell::Float(array.as_primitive::<types::Float32Type>().value(i).into()),
+        DataType::Float64 => Cell::Float(array.as_primitive::<types::Float64Type>().value(i)),
+        DataType::Utf8 => Cell::String(array.as_string::<i32>().value(i)),
+        DataType::LargeUtf8 => Cell::String(array.as_string::<i64>().value(i)),
+        DataType::Utf8View => Cell::String(array.as_string_view().value(i)),
+        DataType::Binary => Cell::Binary(array.as_binary::<i32>().value(i)),
+        DataType::LargeBinary => Cell::Binary(array.as_binary::<i64>().value(i)),
+        DataType::BinaryView => Cell::Binary(array.as_binary_view().value(i)),
+        other => return Err(format!("unsupported Arrow type {}", other)),
+    };
+    Ok(cell)
+}
+
+fn zon_error(e: &'static str) -> ArrowError {
+    ArrowError::ParseError(e.to_string())
+}
+
+fn column_error(name: &str, e: impl std::fmt::Display) -> ArrowError {
+    ArrowError::InvalidArgumentError(format!("column `{}`: {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Vec<u8> {
+        let mut writer = ZonWriter::new();
+        let name = writer.write_string_array(&["apple", "", "cherry"]);
+        let price = writer.write_float_array(&[1.5, 0.25, 8.0]);
+        let stock = writer.write_int_array(&[3, -1, 40]);
+        let organic = writer.write_bool_array(&[true, false, true]);
+        let (a, b) = (writer.write_uint(7), writer.write_null());
+        let c = writer.write_uint(9);
+        let rating = writer.write_array(&[a, b, c]);
+        let root = writer.write_object(&[
+            ("name", name),
+            ("price", price),
+            ("stock", stock),
+            ("organic", organic),
+            ("rating", rating),
+        ]);
+        writer.set_root(root);
+        writer.as_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_to_record_batch_shares_buffer() {
+        let buffer = Buffer::from_vec(table());
+        let batch = to_record_batch(&buffer).unwrap();
+        assert_eq!(batch.num_rows(), 3);
+
+        let price = batch.column_by_name("price").unwrap().as_primitive::<types::Float64Type>();
+        assert_eq!(price.values(), &[1.5, 0.25, 8.0]);
+        let names = batch.column_by_name("name").unwrap().as_string::<i32>();
+        assert_eq!(names.value(2), "cherry");
+        let rating = batch.column_by_name("rating").unwrap().as_primitive::<types::Int64Type>();
+        assert!(rating.is_null(1));
+        assert!(batch.schema().field_with_name("rating").unwrap().is_nullable());
+
+        // zero-copy: the column points into the ZON buffer
+        let range = buffer.as_ptr() as usize..buffer.as_ptr() as usize + buffer.len();
+        assert!(range.contains(&(price.values().as_ptr() as usize)));
+        assert!(range.contains(&(names.values().as_ptr() as usize)));
+    }
+
+    #[test]
+    fn test_ipc_roundtrip() {
+        let original = table();
+        let ipc = decode(&ZonReader::new(&original).unwrap()).unwrap();
+        let buffer = encode(&ipc).unwrap();
+
+        let reader = ZonReader::new(&buffer).unwrap();
+        let table = ZonTable::new(reader.root_value().unwrap().as_object().unwrap()).unwrap();
+        assert_eq!(table.column::<f64>("price").unwrap(), &[1.5, 0.25, 8.0]);
+        assert_eq!(table.column::<i64>("stock").unwrap(), &[3, -1, 40]);
+        assert_eq!(table.column::<&str>("name").unwrap().get(0).unwrap(), "apple");
+        assert!(table.column::<bool>("organic").unwrap().get(0).unwrap());
+
+        let rating = table.array("rating").unwrap();
+        assert_eq!(rating.tag(), Tag::Array);
+        assert!(matches!(rating.get(1).unwrap(), ZonValue::Null));
+    }
+}
@@ -0,0 +1,280 @@
+//! owned, shareable buffers.
+//!
+//! `ZonReader` borrows its bytes, which is what makes it free to create but
+//! awkward to keep in a struct or move into a task. `ZonBuf` owns them
+//! instead: it checks the header once when it is built, clones by bumping
+//! a reference count, and hands out readers without checking again.
+//!
+//! typed arrays are only borrowed in place from 8-byte aligned memory, so
+//! bytes that do not start on such a boundary are copied once into an
+//! `AlignedBuf`. mmaps and `AlignedBuf`s always qualify, and so, in
+//! practice, do vectors and `Arc`s from the global allocator. compressed
+//! containers are decompressed, as `ZonFile::open` does.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+#[cfg(feature = "tokio")]
+use std::io;
+
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::aligned::AlignedBuf;
+use crate::compress;
+#[cfg(feature = "mmap")]
+use crate::file::ZonFile;
+use crate::reader::ZonReader;
+use crate::value::ZonValue;
+
+/// an owned, checked buffer. `Send + Sync`, and cheap to clone.
+#[derive(Clone)]
+pub struct ZonBuf {
+    data: Data,
+}
+
+#[derive(Clone)]
+enum Data {
+    Vec(Arc<Vec<u8>>),
+    Shared(Arc<[u8]>),
+    Aligned(Arc<AlignedBuf>),
+    #[cfg(feature = "bytes")]
+    Bytes(bytes::Bytes),
+    #[cfg(feature = "mmap")]
+    File(Arc<ZonFile>),
+}
+
+impl ZonBuf {
+    /// takes over a vector, e.g. from `ZonWriter::into_bytes`.
+    pub fn from_vec(buffer: Vec<u8>) -> Result<Self, &'static str> {
+        Self::checked(Data::Vec(Arc::new(buffer)))
+    }
+
+    /// shares an `Arc<[u8]>` with its other owners.
+    pub fn from_arc(buffer: Arc<[u8]>) -> Result<Self, &'static str> {
+        Self::checked(Data::Shared(buffer))
+    }
+
+    /// shares a `bytes::Bytes`, e.g. a frame cut from a network buffer.
+    #[cfg(feature = "bytes")]
+    pub fn from_bytes(buffer: bytes::Bytes) -> Result<Self, &'static str> {
+        Self::checked(Data::Bytes(buffer))
+    }
+
+    /// takes over an aligned buffer, e.g. from `decompress` or `open_encrypted`.
+    pub fn from_aligned(buffer: AlignedBuf) -> Result<Self, &'static str> {
+        Self::checked(Data::Aligned(Arc::new(buffer)))
+    }
+
+    /// copies `buffer`.
+    pub fn copy_from_slice(buffer: &[u8]) -> Result<Self, &'static str> {
+        Self::from_aligned(AlignedBuf::from_bytes(buffer))
+    }
+
+    /// shares an opened file, which checked its header already.
+    #[cfg(feature = "mmap")]
+    pub fn from_file(file: ZonFile) -> Self {
+        Self { data: Data::File(Arc::new(file)) }
+    }
+
+    /// reads a whole buffer from `reader`, e.g. a `tokio::fs::File`, without
+    /// blocking the executor. the header is checked before the rest is read,
+    /// and buffers larger than `max_size` bytes, or that would decompress to
+    /// more, are refused with `InvalidData` rather than read to the end.
+    #[cfg(feature = "tokio")]
+    pub async fn read_from<R>(reader: &mut R, max_size: usize) -> io::Result<Self>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut buffer = Vec::new();
+        (&mut *reader).take(compress::HEADER_SIZE as u64).read_to_end(&mut buffer).await?;
+        if compress::is_compressed(&buffer) {
+            if compress::read_u64(&buffer, 24) > max_size as u64 {
+                return Err(invalid("Buffer exceeds maximum size"));
+            }
+        } else {
+            ZonReader::new(&buffer).map_err(invalid)?;
+        }
+
+        let limit = (max_size.saturating_sub(compress::HEADER_SIZE) as u64).saturating_add(1);
+        (&mut *reader).take(limit).read_to_end(&mut buffer).await?;
+        if buffer.len() > max_size {
+            return Err(invalid("Buffer exceeds maximum size"));
+        }
+        Self::from_vec(buffer).map_err(invalid)
+    }
+
+    fn checked(data: Data) -> Result<Self, &'static str> {
+        let mut buf = Self { data };
+        if compress::is_compressed(&buf) {
+            buf.data = Data::Aligned(Arc::new(compress::decompress(&buf)?));
+        } else if buf.as_ptr().align_offset(8) != 0 {
+            buf.data = Data::Aligned(Arc::new(AlignedBuf::from_bytes(&buf)));
+        }
+        ZonReader::new(&buf)?;
+        Ok(buf)
+    }
+
+    /// a reader over the buffer. the header was checked when it was built.
+    pub fn reader(&self) -> ZonReader<'_> {
+        ZonReader::new_unchecked(self)
+    }
+
+    /// the root value.
+    pub fn root_value(&self) -> Result<ZonValue<'_>, &'static str> {
+        self.reader().root_value()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.data {
+            Data::Vec(buffer) => buffer,
+            Data::Shared(buffer) => buffer,
+            Data::Aligned(buffer) => buffer,
+            #[cfg(feature = "bytes")]
+            Data::Bytes(buffer) => buffer,
+            #[cfg(feature = "mmap")]
+            Data::File(file) => file.as_bytes(),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl Deref for ZonBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<[u8]> for ZonBuf {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl fmt::Debug for ZonBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZonBuf").field("len", &self.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZonWriter;
+
+    fn sample() -> Vec<u8> {
+        let mut writer = ZonWriter::new();
+        let ids = writer.write_int_array(&[4, 8, 15]);
+        let root = writer.write_object(&[("ids", ids)]);
+        writer.finish(root)
+    }
+
+    fn ids(buf: &ZonBuf) -> &[i64] {
+        let root = buf.root_value().unwrap().as_object().unwrap();
+        root.get("ids").unwrap().unwrap().as_array().unwrap().as_slice::<i64>().unwrap()
+    }
+
+    #[test]
+    fn test_sources() {
+        fn shareable<T: Send + Sync + Clone + 'static>(_: &T) {}
+
+        let buffer = sample();
+        let from_vec = ZonBuf::from_vec(buffer.clone()).unwrap();
+        shareable(&from_vec);
+        assert_eq!(ids(&from_vec), [4, 8, 15]);
+        let clone = from_vec.clone();
+        assert_eq!(clone.as_ptr(), from_vec.as_ptr());
+
+        let from_arc = ZonBuf::from_arc(Arc::from(&buffer[..])).unwrap();
+        assert_eq!(ids(&from_arc), [4, 8, 15]);
+
+        let copied = ZonBuf::copy_from_slice(&buffer).unwrap();
+        assert_eq!(ids(&copied), [4, 8, 15]);
+
+        assert_eq!(ZonBuf::from_vec(vec![0; 64]).unwrap_err(), "Invalid Magic Number");
+        assert_eq!(ZonBuf::from_vec(vec![0; 8]).unwrap_err(), "Buffer too small for ZonHeader");
+    }
+
+    #[test]
+    fn test_across_threads() {
+        let buf = ZonBuf::from_vec(sample()).unwrap();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let buf = buf.clone();
+                std::thread::spawn(move || ids(&buf).iter().sum::<i64>())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 27);
+        }
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_bytes() {
+        let mut frames = vec![0xff; 8];
+        frames.extend_from_slice(&sample());
+        frames.push(0xff);
+        frames.extend_from_slice(&sample());
+        let frames = bytes::Bytes::from(frames);
+        let (first, second) = (8, 8 + sample().len() + 1);
+
+        let aligned = ZonBuf::from_bytes(frames.slice(first..second - 1)).unwrap();
+        assert_eq!(aligned.as_ptr(), frames[first..].as_ptr());
+        assert_eq!(ids(&aligned), [4, 8, 15]);
+        // the second frame starts off an 8-byte boundary and is copied once
+        let copied = ZonBuf::from_bytes(frames.slice(second..)).unwrap();
+        assert_ne!(copied.as_ptr(), frames[second..].as_ptr());
+        assert_eq!(ids(&copied), [4, 8, 15]);
+        assert_eq!(ZonBuf::from_bytes(frames.slice(7..)).unwrap_err(), "Invalid Magic Number");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_file() {
+        let path = std::env::temp_dir().join(format!("zon-async-test-{}.zon", std::process::id()));
+        let mut writer = ZonWriter::new();
+        let ids_offset = writer.write_int_array(&[4, 8, 15]);
+        let root = writer.write_object(&[("ids", ids_offset)]);
+        writer.set_root(root);
+
+        let mut file = tokio::fs::File::create(&path).await.unwrap();
+        writer.write_to(&mut file).await.unwrap();
+        drop(file);
+
+        let mut file = tokio::fs::File::open(&path).await.unwrap();
+        let buf = ZonBuf::read_from(&mut file, 1 << 20).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(buf.as_bytes(), writer.as_bytes());
+        assert_eq!(ids(&buf), [4, 8, 15]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_limits() {
+        let buffer = sample();
+        for max_size in [buffer.len(), usize::MAX] {
+            let mut input = &buffer[..];
+            assert!(ZonBuf::read_from(&mut input, max_size).await.is_ok());
+        }
+
+        let mut input = &buffer[..];
+        let err = ZonBuf::read_from(&mut input, buffer.len() - 1).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Buffer exceeds maximum size");
+
+        // anything else is refused after the header, without reading on
+        let garbage = vec![0; 4096];
+        let mut input = &garbage[..];
+        let err = ZonBuf::read_from(&mut input, usize::MAX).await.unwrap_err();
+        assert_eq!(err.to_string(), "Invalid Magic Number");
+        assert_eq!(input.len(), 4096 - 64);
+    }
+}
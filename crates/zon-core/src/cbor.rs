@@ -0,0 +1,140 @@
+//! conversion between CBOR and self-describing ZON buffers.
+//!
+//! integers and floats keep their kind (non-negative integers become
+//! `UInt` nodes, as in the `json` module), byte strings become blobs and
+//! map entries keep their order. semantic tags are dropped in favour of
+//! the tagged value, and integer map keys are written in decimal.
+
+use std::io;
+
+use ciborium::value::Value;
+use serde::ser::Error as _;
+
+use crate::interop::Serialized;
+use crate::reader::ZonReader;
+use crate::value::{ZonValue, MAX_DEPTH};
+use crate::writer::ZonWriter;
+
+pub type DecodeError = ciborium::de::Error<io::Error>;
+pub type EncodeError = ciborium::ser::Error<io::Error>;
+
+/// converts one CBOR data item into a new ZON buffer with it as root.
+pub fn encode(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut rest = data;
+    let buffer = from_reader(&mut rest)?;
+    if !rest.is_empty() {
+        return Err(semantic("trailing bytes after CBOR value"));
+    }
+    Ok(buffer)
+}
+
+/// reads one CBOR data item from `reader` into a ZON buffer.
+pub fn from_reader<R: io::Read>(reader: R) -> Result<Vec<u8>, DecodeError> {
+    let value: Value = ciborium::from_reader(reader)?;
+    let mut writer = ZonWriter::new();
+    let root = write_value(&mut writer, &value, 0)?;
+    Ok(writer.finish(root))
+}
+
+/// converts the root value of `reader` into CBOR.
+pub fn decode(reader: &ZonReader) -> Result<Vec<u8>, EncodeError> {
+    let root = reader.root_value().map_err(EncodeError::custom)?;
+    let mut out = Vec::new();
+    to_writer(root, &mut out)?;
+    Ok(out)
+}
+
+/// writes a value and everything below it as CBOR.
+pub fn to_writer<W: io::Write>(value: ZonValue, writer: W) -> Result<(), EncodeError> {
+    ciborium::into_writer(&Serialized { value, depth: 0 }, writer)
+}
+
+fn write_value(writer: &mut ZonWriter, value: &Value, depth: usize) -> Result<u32, DecodeError> {
+    if depth > MAX_DEPTH {
+        return Err(DecodeError::RecursionLimitExceeded);
+    }
+    let offset = match value {
+        Value::Null => writer.write_null(),
+        Value::Bool(b) => writer.write_bool(*b),
+        Value::Integer(v) => {
+            let v = i128::from(*v);
+            if let Ok(v) = u64::try_from(v) {
+                writer.write_uint(v)
+            } else {
+                writer.write_int(i64::try_from(v).map_err(|_| semantic("integer out of range"))?)
+            }
+        }
+        Value::Float(v) => writer.write_float(*v),
+        Value::Text(s) => writer.write_text(s),
+        Value::Bytes(b) => writer.write_binary(b),
+        Value::Tag(_, inner) => write_value(writer, inner, depth + 1)?,
+        Value::Array(items) => {
+            let items = items
+                .iter()
+                .map(|item| write_value(writer, item, depth + 1))
+                .collect::<Result<Vec<_>, _>>()?;
+            writer.write_array(&items)
+        }
+        Value::Map(entries) => {
+            let mut fields = Vec::with_capacity(entries.len());
+            for (key, item) in entries {
+                let key = match key {
+                    Value::Text(s) => writer.write_string(s),
+                    Value::Integer(v) => writer.write_string(&i128::from(*v).to_string()),
+                    _ => return Err(semantic("map keys must be strings or integers")),
+                };
+                fields.push((key, write_value(writer, item, depth + 1)?));
+            }
+            writer.write_object_offsets(&fields)
+        }
+        _ => return Err(semantic("unsupported CBOR value")),
+    };
+    Ok(offset)
+}
+
+fn semantic(message: &str) -> DecodeError {
+    DecodeError::Semantic(None, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut writer = ZonWriter::new();
+        let name = writer.write_text("Hero");
+        let hp = writer.write_int(-3);
+        let ratio = writer.write_float(2.0);
+        let blob = writer.write_binary(&[0, 1, 255]);
+        let big = writer.write_uint(u64::MAX);
+        let root = writer.write_object(&[("name", name), ("hp", hp), ("ratio", ratio), ("blob", blob), ("big", big)]);
+        writer.set_root(root);
+
+        let packed = decode(&ZonReader::new(writer.as_bytes()).unwrap()).unwrap();
+        let buffer = encode(&packed).unwrap();
+        let reader = ZonReader::new(&buffer).unwrap();
+        let root = reader.root_value().unwrap().as_object().unwrap();
+
+        let keys: Vec<&str> = root.iter().map(|f| f.unwrap().0).collect();
+        assert_eq!(keys, ["name", "hp", "ratio", "blob", "big"]);
+        assert!(matches!(root.get("hp").unwrap(), Some(ZonValue::Int(-3))));
+        assert!(matches!(root.get("ratio").unwrap(), Some(ZonValue::Float(v)) if v == 2.0));
+        assert!(matches!(root.get("blob").unwrap(), Some(ZonValue::Binary(&[0, 1, 255]))));
+        assert!(matches!(root.get("big").unwrap(), Some(ZonValue::UInt(u64::MAX))));
+    }
+
+    #[test]
+    fn test_tags_and_keys() {
+        // {2: 1(1700000000), "a": [true]}
+        let data = [0xA2, 0x02, 0xC1, 0x1A, 0x65, 0x53, 0xF1, 0x00, 0x61, b'a', 0x81, 0xF5];
+        let buffer = encode(&data).unwrap();
+        let reader = ZonReader::new(&buffer).unwrap();
+        let root = reader.root_value().unwrap().as_object().unwrap();
+        assert_eq!(root.entry(0).unwrap().0, "2");
+        assert!(matches!(root.get("2").unwrap(), Some(ZonValue::UInt(1_700_000_000))));
+
+        assert!(encode(&[0x01, 0x02]).is_err());
+        assert!(encode(&[0xA1, 0x80, 0x01]).is_err());
+    }
+}
@@ -0,0 +1,512 @@
+//! seekable compressed containers.
+//!
+//! the original buffer is split into chunks of `chunk_size` bytes (a
+//! multiple of 64), each compressed on its own and stored at a 64-byte
+//! aligned position. an index after the header locates them:
+//!
+//! | offset | field |
+//! | :-- | :-- |
+//! | 0..16 | as in `compress`, with `FLAG_CHUNKED` also set |
+//! | 16 | codec: 1 = LZ4, 2 = Zstd |
+//! | 20 | chunk size (u32) |
+//! | 24 | length of the original buffer (u64) |
+//! | 32 | number of chunks (u64) |
+//! | 64 | per chunk: its position (u64) and compressed length (u64) |
+//!
+//! `ChunkedReader` reads such a container without decompressing all of it:
+//! it decompresses the chunks a read touches and keeps the most recently
+//! used ones. offsets are positions in the original buffer, as usual.
+//!
+//! `ZonReader` itself does not read chunked containers: values need one
+//! contiguous buffer, so `ChunkedReader` hands out copies, not views. its
+//! API stops at `read_at`, `extract` and `get`; `ZonValue`, `Query`, typed
+//! accessors and tables only work on the standalone buffers those return.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+
+use crate::aligned::AlignedBuf;
+use crate::compress::{check_unsigned, codec, decode, encode, is_chunked, read_u32, read_u64, Codec, HEADER_SIZE};
+use crate::header::{FLAG_CHUNKED, FLAG_COMPRESSED, ZON_MAGIC};
+use crate::query::{PlainStep, Query};
+use crate::reader::ZonReader;
+use crate::value::{node_size, tag_of, Tag, MAX_DEPTH};
+use crate::writer::ZonWriter;
+
+/// a good default for random access: small enough that one record costs
+/// little to decompress, large enough to compress well.
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+const DEFAULT_CACHE_CHUNKS: usize = 16;
+
+/// compresses a plain buffer into a container of independent chunks.
+pub fn compress_chunked(buffer: &[u8], codec: Codec, chunk_size: usize) -> Result<Vec<u8>, &'static str> {
+    check_unsigned(buffer)?;
+    if chunk_size == 0 || !chunk_size.is_multiple_of(64) || chunk_size > u32::MAX as usize {
+        return Err("Chunk size must be a non-zero multiple of 64");
+    }
+    let count = buffer.len().div_ceil(chunk_size);
+
+    let mut container = Vec::new();
+    container.extend_from_slice(&buffer[..12]);
+    container.extend_from_slice(&(read_u32(buffer, 12) | FLAG_COMPRESSED | FLAG_CHUNKED).to_le_bytes());
+    container.extend_from_slice(&codec.id().to_le_bytes());
+    container.extend_from_slice(&(chunk_size as u32).to_le_bytes());
+    container.extend_from_slice(&(buffer.len() as u64).to_le_bytes());
+    container.extend_from_slice(&(count as u64).to_le_bytes());
+    container.resize(HEADER_SIZE + count * 16, 0);
+    for (i, chunk) in buffer.chunks(chunk_size).enumerate() {
+        container.resize(container.len().next_multiple_of(64), 0);
+        let payload = encode(chunk, codec)?;
+        let (entry, at) = (HEADER_SIZE + i * 16, container.len() as u64);
+        container[entry..entry + 8].copy_from_slice(&at.to_le_bytes());
+        container[entry + 8..entry + 16].copy_from_slice(&(payload.len() as u64).to_le_bytes());
+        container.extend_from_slice(&payload);
+    }
+    Ok(container)
+}
+
+/// decompresses every chunk, for `compress::decompress`.
+pub(crate) fn decompress_all(container: &[u8]) -> Result<AlignedBuf, &'static str> {
+    let reader = ChunkedReader::with_cache(container, 1)?;
+    let mut buffer = AlignedBuf::zeroed(reader.len);
+    for (i, chunk) in buffer.chunks_mut(reader.chunk_size).enumerate() {
+        reader.decode_chunk(i, chunk)?;
+    }
+    ZonReader::new(&buffer)?;
+    Ok(buffer)
+}
+
+/// random access to a chunked container. values are read out with `get`
+/// or `extract` as small standalone buffers, since a `ZonReader` needs the
+/// whole buffer in one piece.
+///
+/// the cache makes this `!Sync`; use one reader per thread.
+#[derive(Debug)]
+pub struct ChunkedReader<'a> {
+    container: &'a [u8],
+    codec: Codec,
+    chunk_size: usize,
+    len: usize,
+    count: usize,
+    // most recently used last
+    cache: RefCell<Vec<(usize, Vec<u8>)>>,
+    capacity: usize,
+    decompressed: Cell<usize>,
+}
+
+impl<'a> ChunkedReader<'a> {
+    pub fn new(container: &'a [u8]) -> Result<Self, &'static str> {
+        Self::with_cache(container, DEFAULT_CACHE_CHUNKS)
+    }
+
+    /// keeps up to `chunks` decompressed chunks in memory (at least one).
+    pub fn with_cache(container: &'a [u8], chunks: usize) -> Result<Self, &'static str> {
+        if !is_chunked(container) {
+            return Err("Buffer is not compressed in chunks");
+        }
+        let codec = codec(container)?.ok_or("Buffer is not compressed")?;
+        let chunk_size = read_u32(container, 20) as usize;
+        let len = read_u64(container, 24);
+        let count = read_u64(container, 32);
+        if chunk_size == 0
+            || !chunk_size.is_multiple_of(64)
+            || len < HEADER_SIZE as u64
+            || len > u32::MAX as u64
+            || count != len.div_ceil(chunk_size as u64)
+            || container.len() < HEADER_SIZE + count as usize * 16
+        {
+            return Err("Invalid chunk index");
+        }
+        // the payloads all lie within the container
+        if len > codec.max_expanded(container.len() as u64) {
+            return Err("Invalid compressed length");
+        }
+
+        let reader = Self {
+            container,
+            codec,
+            chunk_size,
+            len: len as usize,
+            count: count as usize,
+            cache: RefCell::new(Vec::new()),
+            capacity: chunks.max(1),
+            decompressed: Cell::new(0),
+        };
+        // the first chunk holds the original header
+        if reader.read_u32(0)? != ZON_MAGIC {
+            return Err("Invalid Magic Number");
+        }
+        Ok(reader)
+    }
+
+    /// length of the original buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.count
+    }
+
+    /// bytes of the original buffer per chunk; the last one may be shorter.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// how many chunks were decompressed so far, counting chunks that were
+    /// evicted from the cache and decompressed again.
+    pub fn chunks_decompressed(&self) -> usize {
+        self.decompressed.get()
+    }
+
+    pub fn root(&self) -> Result<u32, &'static str> {
+        self.read_u32(8)
+    }
+
+    pub fn read_u32(&self, offset: u32) -> Result<u32, &'static str> {
+        let mut bytes = [0; 4];
+        self.read_at(offset, &mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    /// copies the bytes at `offset` of the original buffer into `out`.
+    pub fn read_at(&self, offset: u32, out: &mut [u8]) -> Result<(), &'static str> {
+        let start = offset as usize;
+        if start.checked_add(out.len()).is_none_or(|end| end > self.len) {
+            return Err("Read out of bounds");
+        }
+        let mut done = 0;
+        while done < out.len() {
+            let pos = start + done;
+            let within = pos % self.chunk_size;
+            let rest = &mut out[done..];
+            let n = self.with_chunk(pos / self.chunk_size, |data| {
+                let n = (data.len() - within).min(rest.len());
+                rest[..n].copy_from_slice(&data[within..within + n]);
+                n
+            })?;
+            done += n;
+        }
+        Ok(())
+    }
+
+    /// copies the value at `offset` and everything below it into a
+    /// standalone buffer, decompressing only the chunks it touches.
+    pub fn extract(&self, offset: u32) -> Result<AlignedBuf, &'static str> {
+        let mut writer = ZonWriter::new();
+        let root = self.copy(offset, &mut writer, &mut BTreeMap::new(), 0)?;
+        writer.set_root(root);
+        Ok(AlignedBuf::from_bytes(writer.as_bytes()))
+    }
+
+    /// `extract` for the value at a plain path such as `players[3].name`,
+    /// or `None` when nothing is there. a path may end in an element of a
+    /// typed array.
+    pub fn get(&self, path: &str) -> Result<Option<AlignedBuf>, &'static str> {
+        let query = Query::parse(path).map_err(|_| "Invalid path")?;
+        let steps = query.plain_steps().ok_or("Invalid path")?;
+        let mut node = self.root()?;
+        for (i, step) in steps.iter().enumerate() {
+            if i > MAX_DEPTH {
+                return Err("Maximum nesting depth exceeded");
+            }
+            let tag = tag_of(self.read_u32(node)?)?;
+            node = match (step, tag) {
+                (PlainStep::Field(key), Tag::Object) => match self.field(node, key)? {
+                    Some(value) => value,
+                    None => return Ok(None),
+                },
+                (PlainStep::Index(index), Tag::Array) => match self.index(node, *index)? {
+                    Some(at) => self.read_u32(shift(node, 8 + at as u64 * 4)?)?,
+                    None => return Ok(None),
+                },
+                (PlainStep::Index(index), tag) if tag.element().is_some() && i + 1 == steps.len() => {
+                    return self.index(node, *index)?.map(|at| self.element(node, tag, at)).transpose();
+                }
+                _ => return Ok(None),
+            };
+        }
+        self.extract(node).map(Some)
+    }
+
+    fn copy(
+        &self,
+        offset: u32,
+        writer: &mut ZonWriter,
+        copied: &mut BTreeMap<u32, u32>,
+        depth: usize,
+    ) -> Result<u32, &'static str> {
+        if depth > MAX_DEPTH {
+            return Err("Maximum nesting depth exceeded");
+        }
+        if let Some(&copy) = copied.get(&offset) {
+            return Ok(copy);
+        }
+        let copy = match tag_of(self.read_u32(offset)?)? {
+            Tag::Array => {
+                let items = self.words(shift(offset, 8)?, self.read_u32(shift(offset, 4)?)? as usize)?;
+                let items = items
+                    .into_iter()
+                    .map(|item| self.copy(item, writer, copied, depth + 1))
+                    .collect::<Result<Vec<_>, _>>()?;
+                writer.write_array(&items)
+            }
+            Tag::Object => {
+                let table = self.words(shift(offset, 8)?, self.read_u32(shift(offset, 4)?)? as usize * 2)?;
+                let mut fields = Vec::with_capacity(table.len() / 2);
+                for pair in table.chunks_exact(2) {
+                    let key = self.read_string(pair[0])?;
+                    fields.push((key, self.copy(pair[1], writer, copied, depth + 1)?));
+                }
+                let fields: Vec<(&str, u32)> = fields.iter().map(|(key, value)| (key.as_str(), *value)).collect();
+                writer.write_object(&fields)
+            }
+            // scalars and typed arrays hold no offsets, so their bytes decode on their own
+            _ => {
+                let size = node_size(offset, |at| self.read_u32(at))?;
+                if offset as u64 + size as u64 > self.len as u64 {
+                    return Err("Read out of bounds");
+                }
+                let mut node = AlignedBuf::zeroed(HEADER_SIZE + size as usize);
+                node[..HEADER_SIZE].copy_from_slice(ZonWriter::new().as_bytes());
+                self.read_at(offset, &mut node[HEADER_SIZE..])?;
+                writer.write_value(ZonReader::new(&node)?.value(HEADER_SIZE as u32)?)?
+            }
+        };
+        copied.insert(offset, copy);
+        Ok(copy)
+    }
+
+    // element `at` of the typed array at `node`, as a buffer of its own
+    fn element(&self, node: u32, tag: Tag, at: u32) -> Result<AlignedBuf, &'static str> {
+        let mut writer = ZonWriter::new();
+        let value = match tag {
+            Tag::IntArray => writer.write_int(self.read_u64(shift(node, 8 + at as u64 * 8)?)? as i64),
+            Tag::FloatArray => writer.write_float(f64::from_bits(self.read_u64(shift(node, 8 + at as u64 * 8)?)?)),
+            Tag::BoolArray => {
+                let mut byte = [0];
+                self.read_at(shift(node, 8 + at as u64)?, &mut byte)?;
+                writer.write_bool(byte[0] != 0)
+            }
+            _ => {
+                // a table of count + 1 cumulative ends, then the bytes
+                let entry = shift(node, 8 + at as u64 * 4)?;
+                let (start, end) = (self.read_u32(entry)?, self.read_u32(shift(entry, 4)?)?);
+                let data = shift(node, 8 + 4 * (self.read_u32(shift(node, 4)?)? as u64 + 1))?;
+                let len = end.checked_sub(start).ok_or("Invalid string table")?;
+                // checked before allocating, since both ends come from the container
+                if data as u64 + end as u64 > self.len as u64 {
+                    return Err("Read out of bounds");
+                }
+                let mut bytes = vec![0; len as usize];
+                self.read_at(shift(data, start as u64)?, &mut bytes)?;
+                writer.write_text(core::str::from_utf8(&bytes).map_err(|_| "Invalid UTF-8")?)
+            }
+        };
+        writer.set_root(value);
+        Ok(AlignedBuf::from_bytes(writer.as_bytes()))
+    }
+
+    // the value offset of `key` in the object at `node`
+    fn field(&self, node: u32, key: &str) -> Result<Option<u32>, &'static str> {
+        for i in 0..self.read_u32(shift(node, 4)?)? {
+            let slot = shift(node, 8 + i as u64 * 8)?;
+            let key_offset = self.read_u32(slot)?;
+            if self.read_u32(key_offset)? as usize == key.len() && self.read_string(key_offset)? == key {
+                return Ok(Some(self.read_u32(shift(slot, 4)?)?));
+            }
+        }
+        Ok(None)
+    }
+
+    // resolves a possibly negative index into the container at `node`
+    fn index(&self, node: u32, index: i64) -> Result<Option<u32>, &'static str> {
+        let len = self.read_u32(shift(node, 4)?)? as i64;
+        let index = if index < 0 { len + index } else { index };
+        Ok((0..len).contains(&index).then_some(index as u32))
+    }
+
+    fn read_u64(&self, offset: u32) -> Result<u64, &'static str> {
+        let mut bytes = [0; 8];
+        self.read_at(offset, &mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn read_string(&self, offset: u32) -> Result<String, &'static str> {
+        let len = self.read_u32(offset)?;
+        if offset as u64 + 4 + len as u64 > self.len as u64 {
+            return Err("String read out of bounds");
+        }
+        let mut bytes = vec![0; len as usize];
+        self.read_at(shift(offset, 4)?, &mut bytes)?;
+        String::from_utf8(bytes).map_err(|_| "Invalid UTF-8")
+    }
+
+    // `count` u32 words from `offset`, checked before allocating
+    fn words(&self, offset: u32, count: usize) -> Result<Vec<u32>, &'static str> {
+        if offset as u64 + count as u64 * 4 > self.len as u64 {
+            return Err("Read out of bounds");
+        }
+        let mut bytes = vec![0; count * 4];
+        self.read_at(offset, &mut bytes)?;
+        Ok(bytes.chunks_exact(4).map(|word| u32::from_le_bytes(word.try_into().unwrap())).collect())
+    }
+
+    fn with_chunk<T>(&self, index: usize, f: impl FnOnce(&[u8]) -> T) -> Result<T, &'static str> {
+        let mut cache = self.cache.borrow_mut();
+        match cache.iter().position(|(i, _)| *i == index) {
+            Some(at) => {
+                let entry = cache.remove(at);
+                cache.push(entry);
+            }
+            None => {
+                let mut data = vec![0; self.chunk_size.min(self.len - index * self.chunk_size)];
+                self.decode_chunk(index, &mut data)?;
+                if cache.len() == self.capacity {
+                    cache.remove(0);
+                }
+                cache.push((index, data));
+            }
+        }
+        Ok(f(&cache.last().expect("chunk was just cached").1))
+    }
+
+    // decompresses chunk `index` into `out`, which has the chunk's length
+    fn decode_chunk(&self, index: usize, out: &mut [u8]) -> Result<(), &'static str> {
+        let entry = HEADER_SIZE + index * 16;
+        let (at, size) = (read_u64(self.container, entry), read_u64(self.container, entry + 8));
+        let payload = usize::try_from(at)
+            .ok()
+            .zip(usize::try_from(size).ok())
+            .and_then(|(at, size)| self.container.get(at..at.checked_add(size)?))
+            .ok_or("Compressed payload is truncated")?;
+        if decode(payload, self.codec, out)? != out.len() {
+            return Err("Compressed payload is corrupt");
+        }
+        self.decompressed.set(self.decompressed.get() + 1);
+        Ok(())
+    }
+}
+
+// `base + delta` as an offset into the original buffer. both come from the
+// container, so the sum may not fit
+fn shift(base: u32, delta: u64) -> Result<u32, &'static str> {
+    u32::try_from(base as u64 + delta).map_err(|_| "Read out of bounds")
+}
+
+#[cfg(all(test, feature = "lz4"))]
+mod tests {
+    use super::*;
+    use crate::compress::decompress;
+    use crate::diff;
+
+    fn sample() -> Vec<u8> {
+        let mut writer = ZonWriter::new();
+        let players: Vec<u32> = (0..2000)
+            .map(|i| {
+                let name = writer.write_text(&format!("player-{}", i));
+                let score = writer.write_uint(i * 10);
+                writer.write_object(&[("name", name), ("score", score)])
+            })
+            .collect();
+        let players = writer.write_array(&players);
+        let ids = writer.write_int_array(&(0..5000).collect::<Vec<_>>());
+        let tags = writer.write_string_array(&["red", "green", "blue"]);
+        let root = writer.write_object(&[("players", players), ("ids", ids), ("tags", tags)]);
+        writer.set_root(root);
+        writer.as_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_lazy_reads() {
+        let buffer = sample();
+        let container = compress_chunked(&buffer, Codec::Lz4, 1024).unwrap();
+        let chunked = ChunkedReader::with_cache(&container, 4).unwrap();
+        assert_eq!(chunked.len(), buffer.len());
+
+        let player = chunked.get("players[1500]").unwrap().unwrap();
+        let player = player.reader().unwrap().root_value().unwrap().as_object().unwrap();
+        assert_eq!(player.get("name").unwrap().unwrap().as_str(), Some("player-1500"));
+        assert_eq!(player.get("score").unwrap().unwrap().as_u64(), Some(15000));
+        assert!(chunked.chunks_decompressed() < chunked.chunk_count() / 10);
+
+        let id = chunked.get("ids[-1]").unwrap().unwrap();
+        assert_eq!(id.reader().unwrap().root_value().unwrap().as_i64(), Some(4999));
+        let tag = chunked.get("tags[1]").unwrap().unwrap();
+        assert_eq!(tag.reader().unwrap().root_value().unwrap().as_str(), Some("green"));
+        assert!(chunked.get("players[2000]").unwrap().is_none());
+        assert!(chunked.get("missing.field").unwrap().is_none());
+        assert_eq!(chunked.get("players[*]").unwrap_err(), "Invalid path");
+
+        let all = chunked.extract(chunked.root().unwrap()).unwrap();
+        let original = ZonReader::new(&buffer).unwrap();
+        assert!(diff(&original, &all.reader().unwrap()).unwrap().is_empty());
+        assert_eq!(&decompress(&container).unwrap()[..], &buffer[..]);
+    }
+
+    #[test]
+    fn test_cache() {
+        let buffer = sample();
+        assert!(compress_chunked(&buffer, Codec::Lz4, 100).is_err());
+        let container = compress_chunked(&buffer, Codec::Lz4, 4096).unwrap();
+        let chunked = ChunkedReader::with_cache(&container, 1).unwrap();
+        let decompressed = chunked.chunks_decompressed();
+
+        let mut bytes = [0; 8];
+        chunked.read_at(20_000, &mut bytes).unwrap();
+        chunked.read_at(20_008, &mut bytes).unwrap();
+        assert_eq!(chunked.chunks_decompressed(), decompressed + 1);
+        // a read across a chunk boundary, then back: both chunks again
+        chunked.read_at(4096 * 6 - 4, &mut bytes).unwrap();
+        assert_eq!(&bytes, &buffer[4096 * 6 - 4..4096 * 6 + 4]);
+        chunked.read_at(20_000, &mut bytes).unwrap();
+        assert_eq!(chunked.chunks_decompressed(), decompressed + 4);
+
+        let end = buffer.len() as u32;
+        assert_eq!(chunked.read_at(end - 4, &mut bytes).unwrap_err(), "Read out of bounds");
+        assert_eq!(ChunkedReader::new(&buffer).unwrap_err(), "Buffer is not compressed in chunks");
+    }
+
+    #[test]
+    fn test_crafted_offsets() {
+        let mut writer = ZonWriter::new();
+        let tags = writer.write_string_array(&["a", "b"]);
+        writer.set_root(tags);
+        let buffer = writer.as_bytes().to_vec();
+
+        // cumulative ends of element 1 sit at tags + 12 and tags + 16
+        let craft = |start: u32, end: u32| {
+            let mut buffer = buffer.clone();
+            let at = tags as usize + 12;
+            buffer[at..at + 4].copy_from_slice(&start.to_le_bytes());
+            buffer[at + 4..at + 8].copy_from_slice(&end.to_le_bytes());
+            compress_chunked(&buffer, Codec::Lz4, 1024).unwrap()
+        };
+        let container = compress_chunked(&buffer, Codec::Lz4, 1024).unwrap();
+        let tag = ChunkedReader::new(&container).unwrap().get("[1]").unwrap().unwrap();
+        assert_eq!(tag.reader().unwrap().root_value().unwrap().as_str(), Some("b"));
+        // would wrap past u32::MAX
+        let container = craft(0xFFFF_FFF0, 0xFFFF_FFF8);
+        let chunked = ChunkedReader::new(&container).unwrap();
+        assert_eq!(chunked.get("[1]").unwrap_err(), "Read out of bounds");
+        // would allocate 4 GiB
+        let container = craft(0, 0xFFFF_FFF0);
+        let chunked = ChunkedReader::new(&container).unwrap();
+        assert_eq!(chunked.get("[1]").unwrap_err(), "Read out of bounds");
+
+        // one chunk claiming 4 GiB, refused before `decompress` allocates it
+        let mut container = compress_chunked(&buffer, Codec::Lz4, 1024).unwrap();
+        container[20..24].copy_from_slice(&0xFFFF_FFC0u32.to_le_bytes());
+        container[24..32].copy_from_slice(&0xFFFF_FFC0u64.to_le_bytes());
+        assert_eq!(ChunkedReader::new(&container).unwrap_err(), "Invalid compressed length");
+        assert_eq!(decompress(&container).unwrap_err(), "Invalid compressed length");
+    }
+}
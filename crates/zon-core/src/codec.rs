@@ -0,0 +1,224 @@
+//! length-delimited framing for sockets.
+//!
+//! `ZonCodec` plugs into `tokio_util::codec::Framed` (or `FramedRead` and
+//! `FramedWrite`) over a TCP or Unix stream. each frame is the buffer's
+//! length as a u64 (little-endian), followed by the buffer:
+//!
+//! | offset | field |
+//! | :-- | :-- |
+//! | 0 | length of the buffer (u64) |
+//! | 8 | the buffer, header included |
+//!
+//! the 8-byte prefix keeps a frame at the start of the read buffer on an
+//! 8-byte boundary, so typed arrays can be borrowed without a copy; `ZonBuf`
+//! copies frames that do not start on one. frames larger than the limit
+//! are refused before they are buffered, and each frame's header is checked
+//! on both ends. compressed containers travel as they are and arrive
+//! decompressed.
+
+use std::io;
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::buf::ZonBuf;
+use crate::compress::{self, read_u64};
+use crate::reader::ZonReader;
+
+/// the largest frame a codec accepts unless told otherwise.
+pub const DEFAULT_MAX_SIZE: usize = 16 * 1024 * 1024;
+
+const PREFIX_SIZE: usize = 8;
+
+/// encodes buffers into frames and decodes frames into `ZonBuf`s.
+#[derive(Debug, Clone, Copy)]
+pub struct ZonCodec {
+    max_size: usize,
+}
+
+impl ZonCodec {
+    /// a codec that accepts frames of up to `DEFAULT_MAX_SIZE` bytes.
+    pub fn new() -> Self {
+        Self::with_max_size(DEFAULT_MAX_SIZE)
+    }
+
+    /// a codec that refuses buffers larger than `max_size` bytes, both ways.
+    /// compressed buffers are also refused if they would decompress to more.
+    pub fn with_max_size(max_size: usize) -> Self {
+        Self { max_size }
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+}
+
+impl Default for ZonCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for ZonCodec {
+    type Item = ZonBuf;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<ZonBuf>> {
+        if src.len() < PREFIX_SIZE {
+            return Ok(None);
+        }
+        let len = read_u64(src, 0);
+        if len > self.max_size as u64 {
+            return Err(invalid("Frame exceeds maximum size"));
+        }
+        let end = PREFIX_SIZE + len as usize;
+        if src.len() < end {
+            src.reserve(end - src.len());
+            return Ok(None);
+        }
+        src.advance(PREFIX_SIZE);
+        let frame = src.split_to(len as usize).freeze();
+        check_frame(&frame, self.max_size)?;
+        ZonBuf::from_bytes(frame).map(Some).map_err(invalid)
+    }
+}
+
+impl<T: AsRef<[u8]>> Encoder<T> for ZonCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> io::Result<()> {
+        let buffer = item.as_ref();
+        if buffer.len() > self.max_size {
+            return Err(invalid("Frame exceeds maximum size"));
+        }
+        check_frame(buffer, self.max_size)?;
+        dst.reserve(PREFIX_SIZE + buffer.len());
+        dst.put_u64_le(buffer.len() as u64);
+        dst.put_slice(buffer);
+        Ok(())
+    }
+}
+
+// checks the header of a plain buffer or a compressed container. a small
+// compressed frame must not expand past the limit either
+fn check_frame(buffer: &[u8], max_size: usize) -> io::Result<()> {
+    if !compress::is_compressed(buffer) {
+        ZonReader::new(buffer).map_err(invalid)?;
+    } else if read_u64(buffer, 24) > max_size as u64 {
+        return Err(invalid("Frame exceeds maximum size"));
+    }
+    Ok(())
+}
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZonWriter;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_util::codec::Framed;
+
+    fn message(id: i64) -> Vec<u8> {
+        let mut writer = ZonWriter::new();
+        let ids = writer.write_int_array(&[id, id + 1]);
+        let name = writer.write_text("tick");
+        let root = writer.write_object(&[("ids", ids), ("name", name)]);
+        writer.finish(root)
+    }
+
+    fn ids(buf: &ZonBuf) -> Vec<i64> {
+        let root = buf.root_value().unwrap().as_object().unwrap();
+        root.get("ids").unwrap().unwrap().as_array().unwrap().as_slice::<i64>().unwrap().to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut framed = Framed::new(stream, ZonCodec::new());
+            // echo every message back, as received
+            while let Some(buf) = framed.next().await {
+                let buf = buf.unwrap();
+                framed.send(&buf).await.unwrap();
+            }
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut framed = Framed::new(stream, ZonCodec::new());
+        for id in 0..100 {
+            framed.send(message(id)).await.unwrap();
+        }
+        for id in 0..100 {
+            let buf = framed.next().await.unwrap().unwrap();
+            assert_eq!(ids(&buf), [id, id + 1]);
+        }
+        // compressed containers are sent as they are and decompressed on arrival
+        #[cfg(feature = "lz4")]
+        {
+            let container = crate::compress(&message(100), crate::Codec::Lz4).unwrap();
+            framed.send(&container).await.unwrap();
+            let buf = framed.next().await.unwrap().unwrap();
+            assert!(!compress::is_compressed(&buf));
+            assert_eq!(ids(&buf), [100, 101]);
+        }
+        drop(framed);
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut codec = ZonCodec::new();
+        let mut src = BytesMut::new();
+        codec.encode(message(7), &mut src).unwrap();
+        codec.encode(message(8), &mut src).unwrap();
+
+        // frames arrive in pieces
+        let mut partial = src.split_to(20);
+        assert!(codec.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(src);
+        assert_eq!(ids(&codec.decode(&mut partial).unwrap().unwrap()), [7, 8]);
+        assert_eq!(ids(&codec.decode(&mut partial).unwrap().unwrap()), [8, 9]);
+        assert!(codec.decode(&mut partial).unwrap().is_none());
+
+        let mut garbage = BytesMut::new();
+        garbage.put_u64_le(64);
+        garbage.put_slice(&[0; 64]);
+        assert_eq!(codec.decode(&mut garbage).unwrap_err().to_string(), "Invalid Magic Number");
+        assert!(codec.encode(&[0u8; 64][..], &mut garbage).is_err());
+    }
+
+    #[test]
+    fn test_max_size() {
+        let buffer = message(1);
+        let mut small = ZonCodec::with_max_size(buffer.len() - 1);
+        let mut dst = BytesMut::new();
+        let err = small.encode(&buffer, &mut dst).unwrap_err();
+        assert_eq!(err.to_string(), "Frame exceeds maximum size");
+
+        // refused from the prefix alone, before the frame is buffered
+        let mut src = BytesMut::new();
+        src.put_u64_le(buffer.len() as u64);
+        let err = small.decode(&mut src).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Frame exceeds maximum size");
+
+        // a container that fits, but would not once decompressed
+        #[cfg(feature = "lz4")]
+        {
+            let mut writer = ZonWriter::new();
+            let zeros = writer.write_int_array(&[0; 1000]);
+            let buffer = writer.finish(zeros);
+            let container = crate::compress(&buffer, crate::Codec::Lz4).unwrap();
+            let mut small = ZonCodec::with_max_size(buffer.len() - 1);
+            assert!(container.len() < small.max_size());
+            let err = small.encode(&container, &mut dst).unwrap_err();
+            assert_eq!(err.to_string(), "Frame exceeds maximum size");
+        }
+    }
+}
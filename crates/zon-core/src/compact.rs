@@ -0,0 +1,127 @@
+//! garbage collection for buffers that grew by appending.
+//!
+//! appended versions and copy-on-write updates leave the old nodes behind.
+//! `compact` walks the tree from the root and copies only what is still
+//! reachable into a fresh buffer. nodes and keys referenced from several
+//! places stay shared in the copy instead of being duplicated.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::reader::ZonReader;
+use crate::value::{Tag, ZonValue, MAX_DEPTH};
+use crate::writer::ZonWriter;
+
+/// copies the value tree below the root of `reader` into a new buffer with
+/// rewritten offsets. the tree must be made of tagged nodes; raw fields
+/// written with `write_u32`/`write_string` cannot be followed. the bytes
+/// reclaimed are `reader.len() - compact(reader)?.len()`.
+pub fn compact(reader: &ZonReader) -> Result<Vec<u8>, &'static str> {
+    let mut compactor = Compactor { reader, writer: ZonWriter::new(), nodes: BTreeMap::new(), keys: BTreeMap::new() };
+    let root = compactor.copy(reader.root()?, 0)?;
+    Ok(compactor.writer.finish(root))
+}
+
+struct Compactor<'r, 'a> {
+    reader: &'r ZonReader<'a>,
+    writer: ZonWriter,
+    // old offset -> new offset, for nodes and keys seen before
+    nodes: BTreeMap<u32, u32>,
+    keys: BTreeMap<u32, u32>,
+}
+
+impl Compactor<'_, '_> {
+    fn copy(&mut self, offset: u32, depth: usize) -> Result<u32, &'static str> {
+        if depth > MAX_DEPTH {
+            return Err("Maximum nesting depth exceeded");
+        }
+        if let Some(&copied) = self.nodes.get(&offset) {
+            return Ok(copied);
+        }
+        let copied = match self.reader.value(offset)? {
+            ZonValue::Array(array) if array.tag() == Tag::Array => {
+                let items = (0..array.len())
+                    .map(|i| self.copy(array.offset_at(i)?, depth + 1))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.writer.write_array(&items)
+            }
+            ZonValue::Object(object) => {
+                let mut fields = Vec::with_capacity(object.len());
+                for i in 0..object.len() {
+                    let (key, value) = object.entry(i)?;
+                    // the key offset is the first word of the field slot
+                    let key_offset = self.reader.read_u32(object.offset() + 8 + i as u32 * 8)?;
+                    let key = match self.keys.get(&key_offset) {
+                        Some(&copied) => copied,
+                        None => {
+                            let copied = self.writer.write_string(key);
+                            self.keys.insert(key_offset, copied);
+                            copied
+                        }
+                    };
+                    fields.push((key, self.copy(value, depth + 1)?));
+                }
+                self.writer.write_object_offsets(&fields)
+            }
+            // scalars and typed arrays have no pointers to follow
+            value => self.writer.write_value(value)?,
+        };
+        self.nodes.insert(offset, copied);
+        Ok(copied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff;
+
+    #[test]
+    fn test_compact_appended() {
+        let mut writer = ZonWriter::new();
+        let hp = writer.write_uint(100);
+        let log = writer.write_binary(&[7; 4096]);
+        let scores = writer.write_int_array(&[1, 2, 3]);
+        let root = writer.write_object(&[("hp", hp), ("log", log), ("scores", scores)]);
+        writer.set_root(root);
+
+        let mut writer = ZonWriter::append(writer.as_bytes().to_vec()).unwrap();
+        let hp = writer.write_uint(90);
+        let root = writer.write_update(root, "hp", hp).unwrap();
+        let empty = writer.write_binary(&[]);
+        let root = writer.write_update(root, "log", empty).unwrap();
+        writer.set_root(root);
+        let old = writer.as_bytes().to_vec();
+
+        let compacted = compact(&ZonReader::new(&old).unwrap()).unwrap();
+        assert!(compacted.len() < old.len() - 4096);
+        let (old, new) = (ZonReader::new(&old).unwrap(), ZonReader::new(&compacted).unwrap());
+        assert!(diff(&old, &new).unwrap().is_empty());
+        let scores = new.root_value().unwrap().as_object().unwrap().get("scores").unwrap().unwrap();
+        assert_eq!(scores.as_array().unwrap().as_slice::<i64>().unwrap(), &[1, 2, 3]);
+
+        // already compact
+        assert_eq!(compact(&new).unwrap(), compacted);
+    }
+
+    #[test]
+    fn test_shared_nodes_stay_shared() {
+        let mut writer = ZonWriter::new();
+        let blob = writer.write_binary(&[1; 1024]);
+        let name = writer.write_string("name");
+        let a = writer.write_object_offsets(&[(name, blob)]);
+        let b = writer.write_object_offsets(&[(name, blob)]);
+        let root = writer.write_array(&[a, b, a]);
+        writer.set_root(root);
+        let old = writer.as_bytes().to_vec();
+
+        let compacted = compact(&ZonReader::new(&old).unwrap()).unwrap();
+        assert!(compacted.len() <= old.len());
+        let reader = ZonReader::new(&compacted).unwrap();
+        let root = reader.root_value().unwrap().as_array().unwrap();
+        assert_eq!(root.offset_at(0).unwrap(), root.offset_at(2).unwrap());
+        let first = root.get(0).unwrap().as_object().unwrap();
+        let second = root.get(1).unwrap().as_object().unwrap();
+        assert_eq!(first.offset_of("name").unwrap(), second.offset_of("name").unwrap());
+    }
+}
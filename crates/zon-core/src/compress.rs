@@ -0,0 +1,253 @@
+//! compressed containers.
+//!
+//! a compressed container is a 64-byte header followed by the whole
+//! original buffer, header included, compressed as one block:
+//!
+//! | offset | field |
+//! | :-- | :-- |
+//! | 0..12 | magic, version and root, copied from the original |
+//! | 12 | flags, with `FLAG_COMPRESSED` set |
+//! | 16 | codec: 1 = LZ4, 2 = Zstd |
+//! | 24 | length of the original buffer (u64) |
+//! | 32 | length of the compressed payload (u64) |
+//!
+//! `ZonReader` refuses containers, since offsets point into the original.
+//! `decompress` restores it once into an aligned buffer, after which reads
+//! are zero-copy again. the codecs sit behind the `lz4` and `zstd` features;
+//! detection works without them.
+//!
+//! containers compressed in chunks (see `chunked`) share the first 24 bytes
+//! of this header and are decompressed by `decompress` too.
+
+use alloc::vec::Vec;
+
+use crate::aligned::AlignedBuf;
+use crate::chunked;
+use crate::header::{ZonHeader, FLAG_CHUNKED, FLAG_COMPRESSED, FLAG_ENCRYPTED, FLAG_SIGNED, ZON_MAGIC};
+use crate::reader::ZonReader;
+
+pub(crate) const HEADER_SIZE: usize = core::mem::size_of::<ZonHeader>();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    /// fast, with a moderate ratio.
+    Lz4,
+    /// slower to write, smaller files. decompresses nearly as fast as LZ4.
+    Zstd,
+}
+
+impl Codec {
+    pub fn name(self) -> &'static str {
+        match self {
+            Codec::Lz4 => "lz4",
+            Codec::Zstd => "zstd",
+        }
+    }
+
+    pub(crate) fn id(self) -> u32 {
+        match self {
+            Codec::Lz4 => 1,
+            Codec::Zstd => 2,
+        }
+    }
+
+    /// the most that `payload_len` compressed bytes can decompress to, so a
+    /// header claiming more is refused before anything is allocated.
+    pub(crate) fn max_expanded(self, payload_len: u64) -> u64 {
+        match self {
+            // a match costs at least one length byte per 255 bytes it copies
+            Codec::Lz4 => payload_len.saturating_mul(255),
+            // an RLE block repeats one byte up to 128 KiB after a 3-byte header
+            Codec::Zstd => payload_len.saturating_mul(128 * 1024 / 4),
+        }
+    }
+
+    fn from_id(id: u32) -> Option<Self> {
+        match id {
+            1 => Some(Codec::Lz4),
+            2 => Some(Codec::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// whether `buffer` is a compressed container. an encrypted one only is
+/// once decrypted.
+pub fn is_compressed(buffer: &[u8]) -> bool {
+    buffer.len() >= HEADER_SIZE
+        && read_u32(buffer, 0) == ZON_MAGIC
+        && read_u32(buffer, 12) & (FLAG_COMPRESSED | FLAG_ENCRYPTED) == FLAG_COMPRESSED
+}
+
+/// the codec of a compressed container, or `None` for a plain buffer.
+pub fn codec(buffer: &[u8]) -> Result<Option<Codec>, &'static str> {
+    if !is_compressed(buffer) {
+        return Ok(None);
+    }
+    Codec::from_id(read_u32(buffer, 16)).map(Some).ok_or("Unknown compression codec")
+}
+
+/// compresses a plain buffer into a container.
+pub fn compress(buffer: &[u8], codec: Codec) -> Result<Vec<u8>, &'static str> {
+    check_unsigned(buffer)?;
+    let payload = encode(buffer, codec)?;
+
+    let mut container = Vec::with_capacity(HEADER_SIZE + payload.len());
+    container.extend_from_slice(&buffer[..12]);
+    container.extend_from_slice(&(read_u32(buffer, 12) | FLAG_COMPRESSED).to_le_bytes());
+    container.extend_from_slice(&codec.id().to_le_bytes());
+    container.extend_from_slice(&[0; 4]);
+    container.extend_from_slice(&(buffer.len() as u64).to_le_bytes());
+    container.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    container.resize(HEADER_SIZE, 0);
+    container.extend_from_slice(&payload);
+    Ok(container)
+}
+
+/// whether `buffer` is a container compressed in chunks.
+pub fn is_chunked(buffer: &[u8]) -> bool {
+    is_compressed(buffer) && read_u32(buffer, 12) & FLAG_CHUNKED != 0
+}
+
+/// restores the original buffer from a container.
+pub fn decompress(container: &[u8]) -> Result<AlignedBuf, &'static str> {
+    let codec = codec(container)?.ok_or("Buffer is not compressed")?;
+    if is_chunked(container) {
+        return chunked::decompress_all(container);
+    }
+    let len = read_u64(container, 24);
+    let payload_len = read_u64(container, 32);
+    // offsets are u32, so no valid buffer is larger
+    if len < HEADER_SIZE as u64 || len > u32::MAX as u64 {
+        return Err("Invalid compressed length");
+    }
+    let payload = usize::try_from(payload_len)
+        .ok()
+        .and_then(|payload_len| container[HEADER_SIZE..].get(..payload_len))
+        .ok_or("Compressed payload is truncated")?;
+    if len > codec.max_expanded(payload_len) {
+        return Err("Invalid compressed length");
+    }
+
+    let mut buffer = AlignedBuf::zeroed(len as usize);
+    if decode(payload, codec, &mut buffer)? != buffer.len() {
+        return Err("Compressed payload is corrupt");
+    }
+    ZonReader::new(&buffer)?;
+    Ok(buffer)
+}
+
+// a plain buffer that is not signed. signatures go on the finished
+// container, since its header could not tell the two trailers apart
+pub(crate) fn check_unsigned(buffer: &[u8]) -> Result<(), &'static str> {
+    ZonReader::new(buffer)?;
+    match read_u32(buffer, 12) & FLAG_SIGNED {
+        0 => Ok(()),
+        _ => Err("Buffer is signed, sign after compressing or encrypting"),
+    }
+}
+
+// the inputs go unused when no codec feature is enabled
+#[allow(unused_variables)]
+pub(crate) fn encode(buffer: &[u8], codec: Codec) -> Result<Vec<u8>, &'static str> {
+    match codec {
+        #[cfg(feature = "lz4")]
+        Codec::Lz4 => Ok(lz4_flex::block::compress(buffer)),
+        #[cfg(feature = "zstd")]
+        Codec::Zstd => zstd::bulk::compress(buffer, zstd::DEFAULT_COMPRESSION_LEVEL).map_err(|_| "Zstd compression failed"),
+        #[allow(unreachable_patterns)]
+        _ => Err(not_enabled(codec)),
+    }
+}
+
+// decompresses into `out` and returns the number of bytes written
+#[allow(unused_variables)]
+pub(crate) fn decode(payload: &[u8], codec: Codec, out: &mut [u8]) -> Result<usize, &'static str> {
+    match codec {
+        #[cfg(feature = "lz4")]
+        Codec::Lz4 => lz4_flex::block::decompress_into(payload, out).map_err(|_| "Compressed payload is corrupt"),
+        #[cfg(feature = "zstd")]
+        Codec::Zstd => zstd::bulk::decompress_to_buffer(payload, out).map_err(|_| "Compressed payload is corrupt"),
+        #[allow(unreachable_patterns)]
+        _ => Err(not_enabled(codec)),
+    }
+}
+
+#[allow(dead_code)]
+fn not_enabled(codec: Codec) -> &'static str {
+    match codec {
+        Codec::Lz4 => "LZ4 support is not enabled (feature `lz4`)",
+        Codec::Zstd => "Zstd support is not enabled (feature `zstd`)",
+    }
+}
+
+// callers check that the buffer holds a full header
+pub(crate) fn read_u32(buffer: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(buffer[at..at + 4].try_into().unwrap())
+}
+
+pub(crate) fn read_u64(buffer: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(buffer[at..at + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZonWriter;
+
+    fn sample() -> Vec<u8> {
+        let mut writer = ZonWriter::new();
+        let names: Vec<String> = (0..500).map(|i| format!("player-{}", i % 20)).collect();
+        let names = writer.write_string_array(&names);
+        let scores = writer.write_int_array(&(0..500).collect::<Vec<_>>());
+        let root = writer.write_object(&[("names", names), ("scores", scores)]);
+        writer.set_root(root);
+        writer.as_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_detect() {
+        let buffer = sample();
+        assert!(!is_compressed(&buffer));
+        assert_eq!(codec(&buffer), Ok(None));
+        assert_eq!(decompress(&buffer).unwrap_err(), "Buffer is not compressed");
+
+        let mut container = buffer.clone();
+        container[12] = 1;
+        container[16] = 1;
+        assert!(is_compressed(&container));
+        assert_eq!(codec(&container), Ok(Some(Codec::Lz4)));
+        assert_eq!(ZonReader::new(&container).unwrap_err(), "Buffer is compressed, decompress it first");
+        container[16] = 9;
+        assert_eq!(codec(&container), Err("Unknown compression codec"));
+    }
+
+    #[cfg(all(feature = "lz4", feature = "zstd"))]
+    #[test]
+    fn test_round_trip() {
+        let buffer = sample();
+        for codec in [Codec::Lz4, Codec::Zstd] {
+            let container = compress(&buffer, codec).unwrap();
+            assert!(container.len() < buffer.len() / 2, "{} ratio", codec.name());
+            assert_eq!(super::codec(&container), Ok(Some(codec)));
+            assert!(compress(&container, codec).is_err());
+
+            let restored = decompress(&container).unwrap();
+            assert_eq!(&restored[..], &buffer[..]);
+            let root = restored.reader().unwrap().root_value().unwrap().as_object().unwrap();
+            let scores = root.get("scores").unwrap().unwrap().as_array().unwrap();
+            assert_eq!(scores.as_slice::<i64>().unwrap()[499], 499);
+
+            let mut truncated = container.clone();
+            truncated.truncate(container.len() - 10);
+            assert_eq!(decompress(&truncated).unwrap_err(), "Compressed payload is truncated");
+            let mut corrupt = container.clone();
+            corrupt[24] ^= 1;
+            assert!(decompress(&corrupt).is_err());
+            // more than the payload could expand to, refused before allocating
+            let mut inflated = container.clone();
+            inflated[24..32].copy_from_slice(&(u32::MAX as u64).to_le_bytes());
+            assert_eq!(decompress(&inflated).unwrap_err(), "Invalid compressed length");
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! CSV/TSV import into columnar tables (see `ZonTable`).
+//!
+//! the first record is the header, and column names must be unique. each
+//! column becomes one typed array, with its type inferred from every cell:
+//! - `bool[]` if all cells are `true` or `false`
+//! - `int[]` if all cells are integers
+//! - `float[]` if all non-empty cells are numbers; empty cells become NaN
+//! - `string[]` otherwise
+//!
+//! a numeric column with gaps is therefore stored as floats.
+
+use std::io;
+
+use crate::writer::ZonWriter;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Bool,
+    Int,
+    Float,
+    String,
+}
+
+/// reads delimited text and writes it as a table, which becomes the root.
+/// use `b','` for CSV and `b'\t'` for TSV.
+pub fn from_reader<R: io::Read>(reader: R, delimiter: u8) -> Result<Vec<u8>, ::csv::Error> {
+    let mut reader = ::csv::ReaderBuilder::new().delimiter(delimiter).from_reader(reader);
+    let names: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+    // each name becomes an object key, which has to be unique
+    if let Some(name) = names.iter().enumerate().find_map(|(i, name)| names[..i].contains(name).then_some(name)) {
+        let message = format!("duplicate column name `{}`", name);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message).into());
+    }
+
+    let mut cells: Vec<Vec<String>> = vec![Vec::new(); names.len()];
+    for record in reader.records() {
+        for (column, cell) in cells.iter_mut().zip(record?.iter()) {
+            column.push(cell.to_string());
+        }
+    }
+
+    let mut writer = ZonWriter::new();
+    let mut columns = Vec::with_capacity(names.len());
+    for (name, column) in names.iter().zip(&cells) {
+        let cells = column.iter().map(|c| c.trim());
+        let offset = match infer(column) {
+            Kind::Bool => writer.write_bool_array(&cells.map(|c| c == "true").collect::<Vec<_>>()),
+            Kind::Int => writer.write_int_array(&cells.map(|c| c.parse().unwrap_or(0)).collect::<Vec<_>>()),
+            Kind::Float => writer.write_float_array(&cells.map(|c| c.parse().unwrap_or(f64::NAN)).collect::<Vec<_>>()),
+            Kind::String => writer.write_string_array(column),
+        };
+        columns.push((name.as_str(), offset));
+    }
+    let root = writer.write_object(&columns);
+    Ok(writer.finish(root))
+}
+
+/// reads CSV text into a table. see `from_reader`.
+pub fn from_str(text: &str) -> Result<Vec<u8>, ::csv::Error> {
+    from_reader(text.as_bytes(), b',')
+}
+
+fn infer(column: &[String]) -> Kind {
+    let cells = || column.iter().map(|c| c.trim());
+    if column.is_empty() || cells().all(str::is_empty) {
+        Kind::String
+    } else if cells().all(|c| c == "true" || c == "false") {
+        Kind::Bool
+    } else if cells().all(|c| c.parse::<i64>().is_ok()) {
+        Kind::Int
+    } else if cells().all(|c| c.is_empty() || is_number(c)) {
+        Kind::Float
+    } else {
+        Kind::String
+    }
+}
+
+// rust also parses "inf" and "NaN", which are more likely words than numbers in a CSV
+fn is_number(cell: &str) -> bool {
+    cell.bytes().any(|b| b.is_ascii_digit()) && cell.parse::<f64>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Tag;
+    use crate::{ZonReader, ZonTable};
+
+    #[test]
+    fn test_import() {
+        let text = "name,price,stock,organic,note\napple,1.5,3,true,\nbanana,,12,false,ripe\ncherry,8,-1,true,NaN\n";
+        let buffer = from_str(text).unwrap();
+        let reader = ZonReader::new(&buffer).unwrap();
+        let table = ZonTable::new(reader.root_value().unwrap().as_object().unwrap()).unwrap();
+        assert_eq!(table.rows(), 3);
+
+        let tags: Vec<Tag> = table.columns().map(|name| table.array(name.unwrap()).unwrap().tag()).collect();
+        assert_eq!(tags, [Tag::StringArray, Tag::FloatArray, Tag::IntArray, Tag::BoolArray, Tag::StringArray]);
+
+        let price = table.column::<f64>("price").unwrap();
+        assert_eq!((price[0], price[2]), (1.5, 8.0));
+        assert!(price[1].is_nan());
+        assert_eq!(table.column::<i64>("stock").unwrap(), &[3, 12, -1]);
+        assert_eq!(table.column::<&str>("note").unwrap().get(2).unwrap(), "NaN");
+    }
+
+    #[test]
+    fn test_tsv_and_errors() {
+        let buffer = from_reader("a\tb\n1\tx\n".as_bytes(), b'\t').unwrap();
+        let reader = ZonReader::new(&buffer).unwrap();
+        let table = ZonTable::new(reader.root_value().unwrap().as_object().unwrap()).unwrap();
+        assert_eq!(table.column::<i64>("a").unwrap(), &[1]);
+
+        // ragged rows are rejected by the parser
+        assert!(from_str("a,b\n1\n").is_err());
+        let err = from_str("a,b,a\n1,2,3\n").unwrap_err();
+        assert_eq!(err.to_string(), "duplicate column name `a`");
+    }
+}
@@ -0,0 +1,224 @@
+//! structural comparison of two buffers.
+//!
+//! values are compared through the value model, so two buffers that hold
+//! the same data with a different layout (node order, padding, field order
+//! within objects, typed vs generic arrays) compare equal. integers compare
+//! by value whether they are stored as `int` or `uint`.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::reader::ZonReader;
+use crate::schema::push_key;
+use crate::value::{ZonValue, MAX_DEPTH};
+
+/// one difference, with the path of the value it concerns,
+/// e.g. `players[3].name` (empty for the root).
+#[derive(Debug, Clone)]
+pub enum Change<'a> {
+    /// a field or trailing array element only present in the new value.
+    Added { path: String, value: ZonValue<'a> },
+    /// a field or trailing array element only present in the old value.
+    Removed { path: String, value: ZonValue<'a> },
+    /// a value whose type or contents changed.
+    Changed { path: String, old: ZonValue<'a>, new: ZonValue<'a> },
+}
+
+impl Change<'_> {
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. } | Change::Removed { path, .. } | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// every difference between the roots of `old` and `new`, in the order of `old`.
+pub fn diff<'a>(old: &ZonReader<'a>, new: &ZonReader<'a>) -> Result<Vec<Change<'a>>, &'static str> {
+    diff_values(old.root_value()?, new.root_value()?)
+}
+
+/// like `diff`, for any two values.
+pub fn diff_values<'a>(old: ZonValue<'a>, new: ZonValue<'a>) -> Result<Vec<Change<'a>>, &'static str> {
+    let mut changes = Vec::new();
+    walk(old, new, &mut Vec::new(), &mut |steps, delta| {
+        let path = path_text(steps);
+        changes.push(match delta {
+            Delta::Added(value) => Change::Added { path, value },
+            Delta::Removed(value) => Change::Removed { path, value },
+            Delta::Changed(old, new) => Change::Changed { path, old, new },
+        });
+        Ok(())
+    })?;
+    Ok(changes)
+}
+
+/// one step of a path below a value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Step<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// a `Change` without its path.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Delta<'a> {
+    Added(ZonValue<'a>),
+    Removed(ZonValue<'a>),
+    Changed(ZonValue<'a>, ZonValue<'a>),
+}
+
+/// `players[3].name` for the steps `players`, `3`, `name`.
+pub(crate) fn path_text(steps: &[Step]) -> String {
+    let mut path = String::new();
+    for step in steps {
+        match step {
+            Step::Key(key) => push_key(&mut path, key),
+            Step::Index(i) => path.push_str(&format!("[{}]", i)),
+        }
+    }
+    path
+}
+
+pub(crate) type OnDelta<'f, 'a> = dyn FnMut(&[Step<'a>], Delta<'a>) -> Result<(), &'static str> + 'f;
+
+/// calls `f` with the path of every difference.
+pub(crate) fn walk<'a>(
+    old: ZonValue<'a>,
+    new: ZonValue<'a>,
+    steps: &mut Vec<Step<'a>>,
+    f: &mut OnDelta<'_, 'a>,
+) -> Result<(), &'static str> {
+    if steps.len() > MAX_DEPTH {
+        return Err("Maximum nesting depth exceeded");
+    }
+    match (old, new) {
+        (ZonValue::Object(a), ZonValue::Object(b)) => {
+            let mut fields = BTreeMap::new();
+            for field in b.iter() {
+                let (key, value) = field?;
+                fields.entry(key).or_insert(value);
+            }
+            for field in a.iter() {
+                let (key, value) = field?;
+                steps.push(Step::Key(key));
+                match fields.remove(key) {
+                    Some(other) => walk(value, other, steps, f)?,
+                    None => f(steps, Delta::Removed(value))?,
+                }
+                steps.pop();
+            }
+            // what is left was added. report it in the order of `new`
+            for field in b.iter() {
+                let (key, value) = field?;
+                if fields.remove(key).is_some() {
+                    steps.push(Step::Key(key));
+                    f(steps, Delta::Added(value))?;
+                    steps.pop();
+                }
+            }
+        }
+        (ZonValue::Array(a), ZonValue::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                steps.push(Step::Index(i));
+                match (i < a.len(), i < b.len()) {
+                    (true, true) => walk(a.get(i)?, b.get(i)?, steps, f)?,
+                    (true, false) => f(steps, Delta::Removed(a.get(i)?))?,
+                    _ => f(steps, Delta::Added(b.get(i)?))?,
+                }
+                steps.pop();
+            }
+        }
+        (old, new) if !scalars_equal(&old, &new) => f(steps, Delta::Changed(old, new))?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn scalars_equal(a: &ZonValue, b: &ZonValue) -> bool {
+    match (a, b) {
+        (ZonValue::Null, ZonValue::Null) => true,
+        (ZonValue::Bool(a), ZonValue::Bool(b)) => a == b,
+        (ZonValue::Int(_) | ZonValue::UInt(_), ZonValue::Int(_) | ZonValue::UInt(_)) => integer(a) == integer(b),
+        // NaN is unchanged if it stays NaN
+        (ZonValue::Float(a), ZonValue::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+        (ZonValue::String(a), ZonValue::String(b)) => a == b,
+        (ZonValue::Binary(a), ZonValue::Binary(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn integer(value: &ZonValue) -> Option<i128> {
+    match value {
+        ZonValue::Int(v) => Some((*v).into()),
+        ZonValue::UInt(v) => Some((*v).into()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZonWriter;
+
+    #[test]
+    fn test_layout_independent() {
+        // same data, different node order, field order and array kind
+        let mut writer = ZonWriter::new();
+        let name = writer.write_text("Hero");
+        let scores = writer.write_int_array(&[1, 2]);
+        let version = writer.write_int(3);
+        let root = writer.write_object(&[("name", name), ("scores", scores), ("version", version)]);
+        writer.set_root(root);
+        let a = writer.as_bytes().to_vec();
+
+        let mut writer = ZonWriter::new();
+        let version = writer.write_uint(3);
+        let one = writer.write_uint(1);
+        let two = writer.write_uint(2);
+        let scores = writer.write_array(&[one, two]);
+        let name = writer.write_text("Hero");
+        let root = writer.write_object(&[("version", version), ("scores", scores), ("name", name)]);
+        writer.set_root(root);
+        let b = writer.as_bytes().to_vec();
+
+        assert_ne!(a, b);
+        let (a, b) = (ZonReader::new(&a).unwrap(), ZonReader::new(&b).unwrap());
+        assert!(diff(&a, &b).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_changes() {
+        let mut writer = ZonWriter::new();
+        let hp = writer.write_uint(100);
+        let nick = writer.write_text("h");
+        let items = writer.write_string_array(&["sword", "shield"]);
+        let root = writer.write_object(&[("hp", hp), ("nick", nick), ("items", items)]);
+        writer.set_root(root);
+        let old = writer.as_bytes().to_vec();
+
+        let mut writer = ZonWriter::new();
+        let hp = writer.write_float(99.5);
+        let items = writer.write_string_array(&["sword", "bow", "arrow"]);
+        let level = writer.write_uint(2);
+        let root = writer.write_object(&[("hp", hp), ("items", items), ("level", level)]);
+        writer.set_root(root);
+        let new = writer.as_bytes().to_vec();
+
+        let (old, new) = (ZonReader::new(&old).unwrap(), ZonReader::new(&new).unwrap());
+        let changes = diff(&old, &new).unwrap();
+        let kinds: Vec<String> = changes
+            .iter()
+            .map(|change| match change {
+                Change::Added { path, .. } => format!("+{}", path),
+                Change::Removed { path, .. } => format!("-{}", path),
+                Change::Changed { path, .. } => format!("~{}", path),
+            })
+            .collect();
+        assert_eq!(kinds, ["~hp", "-nick", "~items[1]", "+items[2]", "+level"]);
+
+        assert!(matches!(changes[0], Change::Changed { old: ZonValue::UInt(100), new: ZonValue::Float(v), .. } if v == 99.5));
+        assert!(matches!(changes[3], Change::Added { value: ZonValue::String("arrow"), .. }));
+    }
+}
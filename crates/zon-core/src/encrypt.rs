@@ -0,0 +1,221 @@
+//! authenticated encryption.
+//!
+//! an encrypted buffer keeps the first 16 bytes of the original header in
+//! the clear and encrypts everything after them:
+//!
+//! | offset | field |
+//! | :-- | :-- |
+//! | 0..16 | magic, version, root and flags, with `FLAG_ENCRYPTED` set |
+//! | 16 | cipher: 1 = ChaCha20-Poly1305, 2 = AES-256-GCM |
+//! | 20..32 | nonce |
+//! | 32 | length of the original buffer (u64) |
+//! | 64 | the original from byte 16 on, encrypted, then the 16-byte tag |
+//!
+//! the whole 64-byte header is authenticated along with the data, so any
+//! change to either makes decryption fail. compress before encrypting, since
+//! encrypted bytes do not compress; `ZonReader::open_encrypted` undoes both.
+//! keys are 32 bytes and come from the caller. a signature trailer, added
+//! after encrypting, is left out of the authenticated data.
+
+#[cfg(feature = "encryption")]
+use aes_gcm::aead::{AeadCore, AeadInPlace, KeyInit, OsRng};
+#[cfg(feature = "encryption")]
+use aes_gcm::{Aes256Gcm, Nonce};
+#[cfg(feature = "encryption")]
+use chacha20poly1305::ChaCha20Poly1305;
+
+#[cfg(feature = "encryption")]
+use crate::aligned::AlignedBuf;
+#[cfg(feature = "encryption")]
+use crate::compress::read_u64;
+use crate::compress::{read_u32, HEADER_SIZE};
+#[cfg(feature = "encryption")]
+use crate::header::FLAG_SIGNED;
+use crate::header::{FLAG_ENCRYPTED, ZON_MAGIC};
+#[cfg(feature = "encryption")]
+use crate::reader::ZonReader;
+
+#[cfg(feature = "encryption")]
+const TAG_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cipher {
+    /// fast everywhere, including CPUs without AES instructions.
+    ChaCha20Poly1305,
+    /// AES-256 in GCM mode, hardware accelerated on most CPUs.
+    Aes256Gcm,
+}
+
+impl Cipher {
+    pub fn name(self) -> &'static str {
+        match self {
+            Cipher::ChaCha20Poly1305 => "chacha20-poly1305",
+            Cipher::Aes256Gcm => "aes-256-gcm",
+        }
+    }
+
+    #[cfg(feature = "encryption")]
+    fn id(self) -> u32 {
+        match self {
+            Cipher::ChaCha20Poly1305 => 1,
+            Cipher::Aes256Gcm => 2,
+        }
+    }
+
+    fn from_id(id: u32) -> Option<Self> {
+        match id {
+            1 => Some(Cipher::ChaCha20Poly1305),
+            2 => Some(Cipher::Aes256Gcm),
+            _ => None,
+        }
+    }
+}
+
+/// whether `buffer` is encrypted.
+pub fn is_encrypted(buffer: &[u8]) -> bool {
+    buffer.len() >= HEADER_SIZE && read_u32(buffer, 0) == ZON_MAGIC && read_u32(buffer, 12) & FLAG_ENCRYPTED != 0
+}
+
+/// the cipher of an encrypted buffer, or `None` for a plain one.
+pub fn cipher(buffer: &[u8]) -> Result<Option<Cipher>, &'static str> {
+    if !is_encrypted(buffer) {
+        return Ok(None);
+    }
+    Cipher::from_id(read_u32(buffer, 16)).map(Some).ok_or("Unknown cipher")
+}
+
+/// encrypts a buffer, plain or compressed, with a fresh random nonce.
+#[cfg(feature = "encryption")]
+pub fn encrypt(buffer: &[u8], key: &[u8; 32], cipher: Cipher) -> Result<Vec<u8>, &'static str> {
+    if buffer.len() < HEADER_SIZE || read_u32(buffer, 0) != ZON_MAGIC {
+        return Err("Invalid Magic Number");
+    }
+    if is_encrypted(buffer) {
+        return Err("Buffer is already encrypted");
+    }
+    if read_u32(buffer, 12) & FLAG_SIGNED != 0 {
+        return Err("Buffer is signed, sign after compressing or encrypting");
+    }
+
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let mut container = Vec::with_capacity(HEADER_SIZE + buffer.len() - 16 + TAG_SIZE);
+    container.extend_from_slice(&buffer[..12]);
+    container.extend_from_slice(&(read_u32(buffer, 12) | FLAG_ENCRYPTED).to_le_bytes());
+    container.extend_from_slice(&cipher.id().to_le_bytes());
+    container.extend_from_slice(&nonce);
+    container.extend_from_slice(&(buffer.len() as u64).to_le_bytes());
+    container.resize(HEADER_SIZE, 0);
+    container.extend_from_slice(&buffer[16..]);
+
+    let (header, data) = container.split_at_mut(HEADER_SIZE);
+    let tag = match cipher {
+        Cipher::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.into()).encrypt_in_place_detached(&nonce, header, data),
+        Cipher::Aes256Gcm => Aes256Gcm::new(key.into()).encrypt_in_place_detached(&nonce, header, data),
+    }
+    .map_err(|_| "Encryption failed")?;
+    container.extend_from_slice(&tag);
+    Ok(container)
+}
+
+/// restores the buffer that was encrypted, failing if the key is wrong or
+/// anything was changed. the result may still be compressed.
+#[cfg(feature = "encryption")]
+pub fn decrypt(container: &[u8], key: &[u8; 32]) -> Result<AlignedBuf, &'static str> {
+    let cipher = cipher(container)?.ok_or("Buffer is not encrypted")?;
+    let container = crate::sign::strip_signature(container);
+    let len = read_u64(container, 32);
+    if container.len() < HEADER_SIZE + TAG_SIZE || len != (container.len() - HEADER_SIZE - TAG_SIZE + 16) as u64 {
+        return Err("Invalid encrypted length");
+    }
+    let (signed, rest) = container.split_at(HEADER_SIZE);
+    let (data, tag) = rest.split_at(rest.len() - TAG_SIZE);
+    // the header as it was encrypted, before any signing
+    let mut header = [0; HEADER_SIZE];
+    header.copy_from_slice(signed);
+    header[12..16].copy_from_slice(&(read_u32(signed, 12) & !FLAG_SIGNED).to_le_bytes());
+
+    let mut buffer = AlignedBuf::zeroed(len as usize);
+    buffer[..16].copy_from_slice(&header[..16]);
+    buffer[12..16].copy_from_slice(&(read_u32(&header, 12) & !FLAG_ENCRYPTED).to_le_bytes());
+    buffer[16..].copy_from_slice(data);
+    let (nonce, data, tag) = (Nonce::from_slice(&header[20..32]), &mut buffer[16..], tag.into());
+    match cipher {
+        Cipher::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.into()).decrypt_in_place_detached(nonce, &header, data, tag),
+        Cipher::Aes256Gcm => Aes256Gcm::new(key.into()).decrypt_in_place_detached(nonce, &header, data, tag),
+    }
+    .map_err(|_| "Decryption failed: wrong key or tampered data")?;
+    Ok(buffer)
+}
+
+#[cfg(feature = "encryption")]
+impl ZonReader<'_> {
+    /// decrypts `buffer` into an aligned buffer, decompressing it too if it
+    /// was compressed before encryption. read it with `AlignedBuf::reader`.
+    pub fn open_encrypted(buffer: &[u8], key: &[u8; 32]) -> Result<AlignedBuf, &'static str> {
+        let plain = decrypt(buffer, key)?;
+        if crate::compress::is_compressed(&plain) {
+            return crate::compress::decompress(&plain);
+        }
+        plain.reader()?;
+        Ok(plain)
+    }
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod tests {
+    use super::*;
+    use crate::ZonWriter;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    fn sample() -> Vec<u8> {
+        let mut writer = ZonWriter::new();
+        let email = writer.write_text("someone@example.com");
+        let root = writer.write_object(&[("email", email)]);
+        writer.set_root(root);
+        writer.as_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let buffer = sample();
+        for cipher in [Cipher::ChaCha20Poly1305, Cipher::Aes256Gcm] {
+            let container = encrypt(&buffer, &KEY, cipher).unwrap();
+            assert_eq!(super::cipher(&container), Ok(Some(cipher)));
+            assert!(!container.windows(7).any(|w| w == b"example"));
+            assert_eq!(ZonReader::new(&container).unwrap_err(), "Buffer is encrypted, open it with a key");
+            // a fresh nonce every time
+            assert_ne!(encrypt(&buffer, &KEY, cipher).unwrap(), container);
+
+            let plain = ZonReader::open_encrypted(&container, &KEY).unwrap();
+            assert_eq!(&plain[..], &buffer[..]);
+            let root = plain.reader().unwrap().root_value().unwrap().as_object().unwrap();
+            assert_eq!(root.get("email").unwrap().unwrap().as_str(), Some("someone@example.com"));
+        }
+    }
+
+    #[test]
+    fn test_tamper() {
+        let container = encrypt(&sample(), &KEY, Cipher::ChaCha20Poly1305).unwrap();
+        let failed = Err("Decryption failed: wrong key or tampered data");
+        assert_eq!(decrypt(&container, &[8; 32]).map(|_| ()), failed);
+        // the data, the root in the clear header and the tag are all covered
+        for at in [HEADER_SIZE + 3, 8, container.len() - 1] {
+            let mut tampered = container.clone();
+            tampered[at] ^= 1;
+            assert_eq!(decrypt(&tampered, &KEY).map(|_| ()), failed);
+        }
+        assert_eq!(decrypt(&container[..container.len() - 1], &KEY).map(|_| ()), Err("Invalid encrypted length"));
+        assert!(encrypt(&container, &KEY, Cipher::Aes256Gcm).is_err());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_compressed() {
+        let buffer = sample();
+        let compressed = crate::compress(&buffer, crate::Codec::Zstd).unwrap();
+        let container = encrypt(&compressed, &KEY, Cipher::Aes256Gcm).unwrap();
+        assert!(!crate::compress::is_compressed(&container));
+        assert_eq!(&ZonReader::open_encrypted(&container, &KEY).unwrap()[..], &buffer[..]);
+    }
+}
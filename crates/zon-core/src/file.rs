@@ -0,0 +1,146 @@
+//! memory-mapped ZON files.
+//!
+//! the OS pages the file in on demand, so opening a large file is cheap and
+//! values are read straight from the page cache. mappings are page aligned,
+//! so typed arrays can always be borrowed as slices.
+//!
+//! compressed files are decompressed once when opened, into an aligned
+//! buffer, and read from memory after that.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::aligned::AlignedBuf;
+use crate::compress;
+use crate::mutate::ZonMut;
+use crate::reader::ZonReader;
+
+/// a read-only mapping of a ZON file.
+#[derive(Debug)]
+pub struct ZonFile {
+    data: Data,
+}
+
+#[derive(Debug)]
+enum Data {
+    Mapped(Mmap),
+    Decompressed(AlignedBuf),
+}
+
+impl ZonFile {
+    /// maps `path` and checks its header. compressed files are decompressed.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // safety: the mapping is only sound while no one truncates or rewrites
+        // the file underneath it. that is the contract of every mmap reader.
+        let map = unsafe { Mmap::map(&file)? };
+        let data = if compress::is_compressed(&map) {
+            Data::Decompressed(compress::decompress(&map).map_err(invalid)?)
+        } else {
+            ZonReader::new(&map).map_err(invalid)?;
+            Data::Mapped(map)
+        };
+        Ok(Self { data })
+    }
+
+    pub fn reader(&self) -> ZonReader<'_> {
+        ZonReader::new(self.as_bytes()).expect("header checked in ZonFile::open")
+    }
+
+    /// the (decompressed) contents.
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.data {
+            Data::Mapped(map) => map,
+            Data::Decompressed(buffer) => buffer,
+        }
+    }
+
+    /// whether the file was compressed on disk.
+    pub fn is_compressed(&self) -> bool {
+        matches!(self.data, Data::Decompressed(_))
+    }
+}
+
+/// a writable mapping of a ZON file. changes made through `edit` go
+/// straight to the page cache; `flush` waits until they reach the disk.
+#[derive(Debug)]
+pub struct ZonFileMut {
+    map: MmapMut,
+}
+
+impl ZonFileMut {
+    /// maps `path` for reading and writing and checks its header.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        // safety: see `ZonFile::open`.
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        ZonReader::new(&map).map_err(invalid)?;
+        Ok(Self { map })
+    }
+
+    pub fn reader(&self) -> ZonReader<'_> {
+        ZonReader::new(&self.map).expect("header checked in ZonFileMut::open")
+    }
+
+    /// in-place updates of fixed-width values, see `ZonMut`.
+    pub fn edit(&mut self) -> ZonMut<'_> {
+        ZonMut::new(&mut self.map).expect("header checked in ZonFileMut::open")
+    }
+
+    pub fn flush(&self) -> io::Result<()> {
+        self.map.flush()
+    }
+}
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZonWriter;
+
+    #[test]
+    fn test_edit_file() {
+        let mut writer = ZonWriter::new();
+        let price = writer.write_float(1.5);
+        let sizes = writer.write_int_array(&[1, 2, 3]);
+        let root = writer.write_object(&[("price", price), ("sizes", sizes)]);
+        writer.set_root(root);
+
+        let path = std::env::temp_dir().join(format!("zon-file-test-{}.zon", std::process::id()));
+        std::fs::write(&path, writer.as_bytes()).unwrap();
+
+        let mut file = ZonFileMut::open(&path).unwrap();
+        let mut zon = file.edit();
+        zon.set_f64(price, 2.25).unwrap();
+        zon.as_mut_slice::<i64>(sizes).unwrap()[2] = 30;
+        file.flush().unwrap();
+        drop(file);
+
+        let file = ZonFile::open(&path).unwrap();
+        let root = file.reader().root_value().unwrap().as_object().unwrap();
+        assert_eq!(root.get("price").unwrap().unwrap().as_f64(), Some(2.25));
+        let sizes = root.get("sizes").unwrap().unwrap().as_array().unwrap();
+        assert_eq!(sizes.as_slice::<i64>().unwrap(), &[1, 2, 30]);
+        drop(file);
+
+        #[cfg(feature = "lz4")]
+        {
+            let container = compress::compress(writer.as_bytes(), compress::Codec::Lz4).unwrap();
+            std::fs::write(&path, container).unwrap();
+            let file = ZonFile::open(&path).unwrap();
+            assert!(file.is_compressed());
+            assert_eq!(file.as_bytes(), writer.as_bytes());
+            assert!(ZonFileMut::open(&path).is_err());
+        }
+
+        std::fs::write(&path, b"not a zon file").unwrap();
+        assert_eq!(ZonFile::open(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
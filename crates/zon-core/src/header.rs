@@ -1,6 +1,19 @@
 /// the magic number ZON! (0x5A4F4E21)
 pub const ZON_MAGIC: u32 = 0x5A4F4E21;
 
+/// `flags` bit set on compressed containers, see `compress`.
+pub const FLAG_COMPRESSED: u32 = 1;
+
+/// `flags` bit set, next to `FLAG_COMPRESSED`, on containers compressed in
+/// independent chunks, see `chunked`.
+pub const FLAG_CHUNKED: u32 = 2;
+
+/// `flags` bit set on encrypted buffers, see `encrypt`.
+pub const FLAG_ENCRYPTED: u32 = 4;
+
+/// `flags` bit set on buffers with a signature trailer, see `sign`.
+pub const FLAG_SIGNED: u32 = 8;
+
 /// the file header for ZON files.
 /// must be 64-byte aligned to ensure the start of the data segment
 /// is also cache-line aligned.
@@ -10,10 +23,12 @@ pub struct ZonHeader {
     pub magic: u32,
     pub version: u32,
     pub root: u32,
+    /// `FLAG_*` bits. zero for a plain buffer.
+    pub flags: u32,
     // reserved space to pad to 64 bytes.
-    // u32 + u32 + u32 = 12 bytes used.
-    // 64 - 12 = 52 bytes padding.
-    _reserved: [u8; 52],
+    // 4 * u32 = 16 bytes used.
+    // 64 - 16 = 48 bytes padding.
+    _reserved: [u8; 48],
 }
 
 impl Default for ZonHeader {
@@ -22,7 +37,8 @@ impl Default for ZonHeader {
             magic: ZON_MAGIC,
             version: 1,
             root: 0,
-            _reserved: [0; 52],
+            flags: 0,
+            _reserved: [0; 48],
         }
     }
 }
@@ -0,0 +1,167 @@
+//! serde plumbing shared by the format converters.
+//!
+//! `NodeSeed` writes whatever a self-describing deserializer yields straight
+//! into a `ZonWriter`, and `Serialized` feeds a `ZonValue` to any serializer.
+
+// each converter uses only part of this module
+#![cfg_attr(not(all(feature = "json", feature = "msgpack", feature = "cbor")), allow(dead_code))]
+
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::value::{ZonValue, MAX_DEPTH};
+use crate::writer::ZonWriter;
+
+/// deserializes any value by writing it as a node, yielding its offset.
+pub(crate) struct NodeSeed<'w> {
+    pub writer: &'w mut ZonWriter,
+}
+
+impl<'de> DeserializeSeed<'de> for NodeSeed<'_> {
+    type Value = u32;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<u32, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for NodeSeed<'_> {
+    type Value = u32;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<u32, E> {
+        Ok(self.writer.write_null())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<u32, E> {
+        Ok(self.writer.write_null())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<u32, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<u32, E> {
+        Ok(self.writer.write_bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<u32, E> {
+        // keep the signed/unsigned split stable: non-negative means UInt
+        match u64::try_from(v) {
+            Ok(v) => Ok(self.writer.write_uint(v)),
+            Err(_) => Ok(self.writer.write_int(v)),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<u32, E> {
+        Ok(self.writer.write_uint(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<u32, E> {
+        Ok(self.writer.write_float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<u32, E> {
+        Ok(self.writer.write_text(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<u32, E> {
+        Ok(self.writer.write_binary(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<u32, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element_seed(NodeSeed { writer: &mut *self.writer })? {
+            items.push(item);
+        }
+        Ok(self.writer.write_array(&items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<u32, A::Error> {
+        let mut fields = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(key) = map.next_key_seed(KeySeed { writer: &mut *self.writer })? {
+            let value = map.next_value_seed(NodeSeed { writer: &mut *self.writer })?;
+            fields.push((key, value));
+        }
+        Ok(self.writer.write_object_offsets(&fields))
+    }
+}
+
+/// deserializes an object key by writing it as a raw string.
+/// integer keys (common in MessagePack and CBOR) are written in decimal.
+struct KeySeed<'w> {
+    writer: &'w mut ZonWriter,
+}
+
+impl<'de> DeserializeSeed<'de> for KeySeed<'_> {
+    type Value = u32;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<u32, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeySeed<'_> {
+    type Value = u32;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string or integer key")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<u32, E> {
+        Ok(self.writer.write_string(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<u32, E> {
+        Ok(self.writer.write_string(&v.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<u32, E> {
+        Ok(self.writer.write_string(&v.to_string()))
+    }
+}
+
+/// serializes a value and everything below it. blobs go through
+/// `serialize_bytes`, so binary-capable formats keep them as blobs.
+pub(crate) struct Serialized<'a> {
+    pub value: ZonValue<'a>,
+    pub depth: usize,
+}
+
+impl Serialize for Serialized<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.depth > MAX_DEPTH {
+            return Err(ser::Error::custom("Maximum nesting depth exceeded"));
+        }
+        let child = |value| Serialized { value, depth: self.depth + 1 };
+        match self.value {
+            ZonValue::Null => serializer.serialize_unit(),
+            ZonValue::Bool(b) => serializer.serialize_bool(b),
+            ZonValue::Int(v) => serializer.serialize_i64(v),
+            ZonValue::UInt(v) => serializer.serialize_u64(v),
+            ZonValue::Float(v) => serializer.serialize_f64(v),
+            ZonValue::String(s) => serializer.serialize_str(s),
+            ZonValue::Binary(b) => serializer.serialize_bytes(b),
+            ZonValue::Array(array) => {
+                let mut seq = serializer.serialize_seq(Some(array.len()))?;
+                for item in array.iter() {
+                    seq.serialize_element(&child(item.map_err(ser::Error::custom)?))?;
+                }
+                seq.end()
+            }
+            ZonValue::Object(object) => {
+                let mut map = serializer.serialize_map(Some(object.len()))?;
+                for field in object.iter() {
+                    let (key, item) = field.map_err(ser::Error::custom)?;
+                    map.serialize_entry(key, &child(item))?;
+                }
+                map.end()
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! conversion between JSON and self-describing ZON buffers.
+//!
+//! numbers keep their kind: negative integers become `Int` nodes,
+//! non-negative integers `UInt` nodes and everything else `Float` nodes,
+//! so `decode(encode(v)) == v` for every `serde_json::Value`.
+//! object fields are written in document order.
+
+use std::io;
+
+use serde::de::{DeserializeSeed, Deserializer};
+
+use crate::interop::NodeSeed;
+use crate::reader::ZonReader;
+use crate::value::{ZonValue, MAX_DEPTH};
+use crate::writer::ZonWriter;
+
+/// encodes a JSON value into a new ZON buffer with the value as root.
+pub fn encode(value: &serde_json::Value) -> Vec<u8> {
+    let mut writer = ZonWriter::new();
+    let root = write_value(&mut writer, value);
+    writer.finish(root)
+}
+
+/// writes a JSON value (and everything below it) and returns its offset.
+pub fn write_value(writer: &mut ZonWriter, value: &serde_json::Value) -> u32 {
+    // a Value deserializer cannot fail on well-formed input
+    NodeSeed { writer }.deserialize(value).expect("serde_json::Value is always valid")
+}
+
+/// parses JSON text from `reader` straight into a ZON buffer.
+///
+/// nodes are written as the parser produces them, so memory use is the
+/// output buffer plus one offset per element of each open array or object,
+/// never a full `serde_json::Value` tree.
+pub fn from_reader<R: io::Read>(reader: R) -> Result<Vec<u8>, serde_json::Error> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let buffer = stream(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(buffer)
+}
+
+/// parses JSON text into a ZON buffer. see `from_reader`.
+pub fn from_str(text: &str) -> Result<Vec<u8>, serde_json::Error> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    let buffer = stream(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(buffer)
+}
+
+fn stream<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let mut writer = ZonWriter::new();
+    let root = NodeSeed { writer: &mut writer }.deserialize(deserializer)?;
+    Ok(writer.finish(root))
+}
+
+/// decodes the root value of `reader` into JSON.
+pub fn decode(reader: &ZonReader) -> Result<serde_json::Value, &'static str> {
+    to_value(reader.root_value()?)
+}
+
+/// converts a value and everything below it into JSON.
+/// blobs have no JSON equivalent and become arrays of byte values;
+/// non-finite floats become `null`, as in `serde_json`.
+pub fn to_value(value: ZonValue) -> Result<serde_json::Value, &'static str> {
+    to_value_at(value, 0)
+}
+
+fn to_value_at(value: ZonValue, depth: usize) -> Result<serde_json::Value, &'static str> {
+    if depth > MAX_DEPTH {
+        return Err("Maximum nesting depth exceeded");
+    }
+    let json = match value {
+        ZonValue::Null => serde_json::Value::Null,
+        ZonValue::Bool(b) => b.into(),
+        ZonValue::Int(v) => v.into(),
+        ZonValue::UInt(v) => v.into(),
+        ZonValue::Float(v) => v.into(),
+        ZonValue::String(s) => s.into(),
+        ZonValue::Binary(b) => b.to_vec().into(),
+        ZonValue::Array(array) => array
+            .iter()
+            .map(|item| to_value_at(item?, depth + 1))
+            .collect::<Result<_, _>>()?,
+        ZonValue::Object(object) => object
+            .iter()
+            .map(|field| {
+                let (key, item) = field?;
+                Ok((key.to_string(), to_value_at(item, depth + 1)?))
+            })
+            .collect::<Result<serde_json::Map<_, _>, &'static str>>()?
+            .into(),
+    };
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_roundtrip() {
+        let value = json!({
+            "name": "Hero",
+            "hp": 100,
+            "delta": -5,
+            "ratio": 0.5,
+            "whole": 2.0,
+            "big": u64::MAX,
+            "alive": true,
+            "nick": null,
+            "tags": ["a", "b", []],
+            "meta": { "version": 1 },
+        });
+        let buffer = encode(&value);
+        let reader = ZonReader::new(&buffer).unwrap();
+        assert_eq!(decode(&reader).unwrap(), value);
+
+        // `Value` equality ignores key order, so compare the text as well
+        let text = r#"{"zeta":1,"alpha":2,"mid":{"b":3,"a":4}}"#;
+        let buffer = from_str(text).unwrap();
+        let decoded = decode(&ZonReader::new(&buffer).unwrap()).unwrap();
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), text);
+
+        let root = reader.root_value().unwrap().as_object().unwrap();
+        assert!(matches!(root.get("hp").unwrap(), Some(ZonValue::UInt(100))));
+        assert!(matches!(root.get("delta").unwrap(), Some(ZonValue::Int(-5))));
+        assert!(matches!(root.get("whole").unwrap(), Some(ZonValue::Float(_))));
+    }
+
+    #[test]
+    fn test_stream_matches_encode() {
+        let text = r#"{"players": [{"name": "Hero", "score": 500}, {"name": "Mage", "score": -1}], "v": 1.5}"#;
+        let streamed = from_reader(text.as_bytes()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(text).unwrap();
+
+        // same document order, so byte-identical output
+        assert_eq!(streamed, from_str(text).unwrap());
+        let reader = ZonReader::new(&streamed).unwrap();
+        assert_eq!(decode(&reader).unwrap(), value);
+
+        let keys: Vec<&str> = reader.root_value().unwrap().as_object().unwrap().iter().map(|f| f.unwrap().0).collect();
+        assert_eq!(keys, ["players", "v"]);
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        assert!(from_str("[1, 2").is_err());
+        assert!(from_str("[1] [2]").is_err());
+    }
+}
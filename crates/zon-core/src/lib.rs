@@ -1,11 +1,104 @@
+//! zero-copy reading and writing of ZON buffers.
+//!
+//! the reader side (`ZonReader`, values, typed views, tables and in-place
+//! mutation) and `ZonSliceWriter` need only `core`. with the `alloc` feature
+//! come `ZonWriter`, the owned `ZonBuf`, queries, schemas, diffs, patches
+//! and containers; `std`, on by default, adds file I/O and `ZonWriterPool`,
+//! and is required by the format and codec features.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 pub mod header;
+#[cfg(feature = "alloc")]
 pub mod writer;
+pub mod slice_writer;
+#[cfg(feature = "std")]
+pub mod pool;
 pub mod reader;
+pub mod value;
+#[cfg(feature = "alloc")]
+pub mod schema;
+pub mod typed;
+pub mod table;
+#[cfg(feature = "alloc")]
+pub mod query;
+#[cfg(feature = "alloc")]
+pub mod diff;
+#[cfg(feature = "alloc")]
+pub mod patch;
+pub mod mutate;
+#[cfg(feature = "alloc")]
+pub mod compact;
+#[cfg(feature = "alloc")]
+pub mod aligned;
+#[cfg(feature = "alloc")]
+pub mod buf;
+#[cfg(feature = "alloc")]
+pub mod compress;
+#[cfg(feature = "alloc")]
+pub mod chunked;
+#[cfg(feature = "alloc")]
+pub mod encrypt;
+#[cfg(feature = "alloc")]
+pub mod sign;
+#[cfg(any(feature = "json", feature = "msgpack", feature = "cbor"))]
+mod interop;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "mmap")]
+pub mod file;
+#[cfg(feature = "tokio")]
+pub mod codec;
 
+#[cfg(feature = "alloc")]
 pub use writer::ZonWriter;
 pub use reader::ZonReader;
+pub use slice_writer::ZonSliceWriter;
+#[cfg(feature = "std")]
+pub use pool::{PooledWriter, ZonWriterPool};
+pub use typed::{FromZon, ZonList};
+pub use table::ZonTable;
+#[cfg(feature = "alloc")]
+pub use query::{Query, QueryError};
+#[cfg(feature = "alloc")]
+pub use diff::{diff, Change};
+#[cfg(feature = "alloc")]
+pub use patch::{apply_patch, make_patch};
+pub use mutate::ZonMut;
+#[cfg(feature = "alloc")]
+pub use compact::compact;
+#[cfg(feature = "alloc")]
+pub use aligned::AlignedBuf;
+#[cfg(feature = "alloc")]
+pub use buf::ZonBuf;
+#[cfg(feature = "alloc")]
+pub use compress::{compress, decompress, Codec};
+#[cfg(feature = "alloc")]
+pub use chunked::{compress_chunked, ChunkedReader};
+#[cfg(feature = "alloc")]
+pub use encrypt::Cipher;
+#[cfg(feature = "encryption")]
+pub use encrypt::{decrypt, encrypt};
+#[cfg(feature = "signing")]
+pub use sign::{sign, verify_signature};
+#[cfg(feature = "mmap")]
+pub use file::{ZonFile, ZonFileMut};
+#[cfg(feature = "tokio")]
+pub use codec::ZonCodec;
+pub use value::{Tag, ZonArray, ZonObject, ZonValue};
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use super::*;
 
@@ -52,7 +145,7 @@ mod tests {
         
         // 1. write String "Zaim"
         // returns offset where length is written
-        let name_offset = writer.write_string("Zaim");
+        let _name_offset = writer.write_string("Zaim");
         
         // 2. pad to 64-byte alignment for the Player struct
         // current length
@@ -0,0 +1,95 @@
+//! conversion between MessagePack and self-describing ZON buffers.
+//!
+//! integers and floats keep their kind (non-negative integers become
+//! `UInt` nodes, as in the `json` module), `bin` values become blobs and
+//! map entries keep their order. integer map keys are written in decimal,
+//! since ZON object keys are strings. extension types are rejected.
+
+use std::io;
+
+use serde::de::{DeserializeSeed, Error as _};
+use serde::ser::Error as _;
+use serde::Serialize;
+
+use crate::interop::{NodeSeed, Serialized};
+use crate::reader::ZonReader;
+use crate::value::{ZonValue, MAX_DEPTH};
+use crate::writer::ZonWriter;
+
+pub use rmp_serde::decode::Error as DecodeError;
+pub use rmp_serde::encode::Error as EncodeError;
+
+/// converts one MessagePack value into a new ZON buffer with it as root.
+pub fn encode(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut rest = data;
+    let buffer = from_reader(&mut rest)?;
+    if !rest.is_empty() {
+        return Err(DecodeError::custom("trailing bytes after MessagePack value"));
+    }
+    Ok(buffer)
+}
+
+/// reads one MessagePack value from `reader` straight into a ZON buffer.
+pub fn from_reader<R: io::Read>(reader: R) -> Result<Vec<u8>, DecodeError> {
+    let mut deserializer = rmp_serde::Deserializer::new(reader);
+    deserializer.set_max_depth(MAX_DEPTH);
+    let mut writer = ZonWriter::new();
+    let root = NodeSeed { writer: &mut writer }.deserialize(&mut deserializer)?;
+    Ok(writer.finish(root))
+}
+
+/// converts the root value of `reader` into MessagePack.
+pub fn decode(reader: &ZonReader) -> Result<Vec<u8>, EncodeError> {
+    let root = reader.root_value().map_err(EncodeError::custom)?;
+    let mut out = Vec::new();
+    to_writer(root, &mut out)?;
+    Ok(out)
+}
+
+/// writes a value and everything below it as MessagePack.
+pub fn to_writer<W: io::Write>(value: ZonValue, writer: W) -> Result<(), EncodeError> {
+    Serialized { value, depth: 0 }.serialize(&mut rmp_serde::Serializer::new(writer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut writer = ZonWriter::new();
+        let name = writer.write_text("Hero");
+        let hp = writer.write_int(-3);
+        let ratio = writer.write_float(2.0);
+        let blob = writer.write_binary(&[0, 1, 255]);
+        let big = writer.write_uint(u64::MAX);
+        let root = writer.write_object(&[("name", name), ("hp", hp), ("ratio", ratio), ("blob", blob), ("big", big)]);
+        writer.set_root(root);
+
+        let packed = decode(&ZonReader::new(writer.as_bytes()).unwrap()).unwrap();
+        let buffer = encode(&packed).unwrap();
+        let reader = ZonReader::new(&buffer).unwrap();
+        let root = reader.root_value().unwrap().as_object().unwrap();
+
+        let keys: Vec<&str> = root.iter().map(|f| f.unwrap().0).collect();
+        assert_eq!(keys, ["name", "hp", "ratio", "blob", "big"]);
+        assert!(matches!(root.get("name").unwrap(), Some(ZonValue::String("Hero"))));
+        assert!(matches!(root.get("hp").unwrap(), Some(ZonValue::Int(-3))));
+        assert!(matches!(root.get("ratio").unwrap(), Some(ZonValue::Float(v)) if v == 2.0));
+        assert!(matches!(root.get("blob").unwrap(), Some(ZonValue::Binary(&[0, 1, 255]))));
+        assert!(matches!(root.get("big").unwrap(), Some(ZonValue::UInt(u64::MAX))));
+    }
+
+    #[test]
+    fn test_integer_keys_and_order() {
+        // {2: "b", 1: "a"} as a fixmap
+        let packed = [0x82, 0x02, 0xA1, b'b', 0x01, 0xA1, b'a'];
+        let buffer = encode(&packed).unwrap();
+        let reader = ZonReader::new(&buffer).unwrap();
+        let keys: Vec<&str> = reader.root_value().unwrap().as_object().unwrap().iter().map(|f| f.unwrap().0).collect();
+        assert_eq!(keys, ["2", "1"]);
+
+        assert!(encode(&[0x01, 0x02]).is_err());
+        assert!(encode(&[0x92, 0x01]).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+//! in-place updates of fixed-width values.
+//!
+//! numbers and bools occupy the same bytes whatever their value, so they
+//! can be overwritten without moving anything else. strings, blobs and
+//! containers cannot change size this way; rewrite the buffer for those.
+
+use core::mem::size_of;
+
+use crate::header::ZonHeader;
+use crate::reader::ZonReader;
+use crate::value::{Element, Tag, ZonValue};
+
+/// a mutable view of a buffer, e.g. a `Vec<u8>` or a writable mmap
+/// (see `ZonFileMut` behind the `mmap` feature).
+///
+/// offsets come from reading the buffer first:
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// # use zon_lib::{ZonMut, ZonWriter};
+/// # let mut writer = ZonWriter::new();
+/// # let score = writer.write_uint(10);
+/// # let root = writer.write_object(&[("score", score)]);
+/// # writer.set_root(root);
+/// # let mut buffer = writer.as_bytes().to_vec();
+/// let mut zon = ZonMut::new(&mut buffer)?;
+/// let root = zon.reader().root_value()?.as_object().ok_or("Expected object")?;
+/// let score = root.offset_of("score")?.ok_or("Missing field")?;
+/// zon.set_u64(score, 1600)?;
+/// # }
+/// # Ok::<(), &'static str>(())
+/// ```
+#[derive(Debug)]
+pub struct ZonMut<'a> {
+    buffer: &'a mut [u8],
+}
+
+impl<'a> ZonMut<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Result<Self, &'static str> {
+        ZonReader::new(buffer)?;
+        Ok(Self { buffer })
+    }
+
+    /// a reader over the current contents, for finding offsets.
+    pub fn reader(&self) -> ZonReader<'_> {
+        // the header was checked in `new` and writes never reach it
+        ZonReader::new(self.buffer).expect("header checked in ZonMut::new")
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.buffer
+    }
+
+    /// overwrites a raw u32, as written by `ZonWriter::write_u32`.
+    /// raw fields carry no tag, so only the bounds are checked.
+    pub fn set_u32(&mut self, offset: u32, value: u32) -> Result<(), &'static str> {
+        self.write(offset as usize, &value.to_le_bytes())
+    }
+
+    /// overwrites a `bool` node.
+    pub fn set_bool(&mut self, offset: u32, value: bool) -> Result<(), &'static str> {
+        self.check_tag(offset, Tag::Bool)?;
+        self.write(offset as usize + 4, &(value as u32).to_le_bytes())
+    }
+
+    /// overwrites an `int` node.
+    pub fn set_i64(&mut self, offset: u32, value: i64) -> Result<(), &'static str> {
+        self.check_tag(offset, Tag::Int)?;
+        self.write(offset as usize + 8, &value.to_le_bytes())
+    }
+
+    /// overwrites a `uint` node.
+    pub fn set_u64(&mut self, offset: u32, value: u64) -> Result<(), &'static str> {
+        self.check_tag(offset, Tag::UInt)?;
+        self.write(offset as usize + 8, &value.to_le_bytes())
+    }
+
+    /// overwrites a `float` node.
+    pub fn set_f64(&mut self, offset: u32, value: f64) -> Result<(), &'static str> {
+        self.check_tag(offset, Tag::Float)?;
+        self.write(offset as usize + 8, &value.to_le_bytes())
+    }
+
+    /// overwrites element `index` of the `int[]` array at `offset`.
+    pub fn set_i64_at(&mut self, offset: u32, index: usize, value: i64) -> Result<(), &'static str> {
+        let at = self.element(offset, index, Tag::IntArray)?;
+        self.write(at, &value.to_le_bytes())
+    }
+
+    /// overwrites element `index` of the `float[]` array at `offset`.
+    pub fn set_f64_at(&mut self, offset: u32, index: usize, value: f64) -> Result<(), &'static str> {
+        let at = self.element(offset, index, Tag::FloatArray)?;
+        self.write(at, &value.to_le_bytes())
+    }
+
+    /// overwrites element `index` of the `bool[]` array at `offset`.
+    pub fn set_bool_at(&mut self, offset: u32, index: usize, value: bool) -> Result<(), &'static str> {
+        let at = self.element(offset, index, Tag::BoolArray)?;
+        self.write(at, &[value as u8])
+    }
+
+    /// borrows the elements of the typed array at `offset` mutably,
+    /// for bulk updates. see `ZonArray::as_slice` for when this fails.
+    pub fn as_mut_slice<T: Element>(&mut self, offset: u32) -> Result<&mut [T], &'static str> {
+        let len = self.typed_array(offset, T::TAG)?;
+        if cfg!(target_endian = "big") {
+            return Err("Typed arrays can only be borrowed on little-endian targets");
+        }
+        let start = offset as usize + 8;
+        let bytes = &mut self.buffer[start..start + len * size_of::<T>()];
+        // safety: `Element` is only implemented for i64 and f64, for which every
+        // bit pattern is valid, and align_to_mut only yields correctly aligned items.
+        let (head, items, tail) = unsafe { bytes.align_to_mut::<T>() };
+        if !head.is_empty() || !tail.is_empty() {
+            return Err("Typed array is not aligned in memory");
+        }
+        Ok(items)
+    }
+
+    fn check_tag(&self, offset: u32, tag: Tag) -> Result<(), &'static str> {
+        if self.reader().read_tag(offset)? != tag {
+            return Err("Node type mismatch");
+        }
+        Ok(())
+    }
+
+    // checks the array and returns its length. `value` checks the bounds.
+    fn typed_array(&self, offset: u32, tag: Tag) -> Result<usize, &'static str> {
+        match self.reader().value(offset)? {
+            ZonValue::Array(array) if array.tag() == tag => Ok(array.len()),
+            _ => Err("Node type mismatch"),
+        }
+    }
+
+    // byte position of a typed array element
+    fn element(&self, offset: u32, index: usize, tag: Tag) -> Result<usize, &'static str> {
+        if index >= self.typed_array(offset, tag)? {
+            return Err("Array index out of bounds");
+        }
+        let width = if tag == Tag::BoolArray { 1 } else { 8 };
+        Ok(offset as usize + 8 + index * width)
+    }
+
+    fn write(&mut self, at: usize, bytes: &[u8]) -> Result<(), &'static str> {
+        if at < size_of::<ZonHeader>() {
+            return Err("Cannot write into the header");
+        }
+        let target = self.buffer.get_mut(at..at + bytes.len()).ok_or("Write out of bounds")?;
+        target.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::ZonWriter;
+
+    #[test]
+    fn test_set_in_place() {
+        let mut writer = ZonWriter::new();
+        let raw = writer.write_u32(7);
+        let open = writer.write_bool(false);
+        let qty = writer.write_int(-1);
+        let seq = writer.write_uint(1);
+        let price = writer.write_float(99.5);
+        let bids = writer.write_float_array(&[1.0, 2.0, 3.0]);
+        let fills = writer.write_bool_array(&[false, false]);
+        let name = writer.write_text("book");
+        let root = writer.write_object(&[
+            ("open", open),
+            ("qty", qty),
+            ("seq", seq),
+            ("price", price),
+            ("bids", bids),
+            ("fills", fills),
+            ("name", name),
+        ]);
+        writer.set_root(root);
+        let mut buffer = writer.as_bytes().to_vec();
+        let len = buffer.len();
+
+        let mut zon = ZonMut::new(&mut buffer).unwrap();
+        zon.set_u32(raw, 8).unwrap();
+        zon.set_bool(open, true).unwrap();
+        zon.set_i64(qty, 250).unwrap();
+        zon.set_u64(seq, u64::MAX).unwrap();
+        zon.set_f64(price, 100.25).unwrap();
+        zon.set_f64_at(bids, 1, 2.5).unwrap();
+        zon.set_bool_at(fills, 1, true).unwrap();
+
+        let reader = ZonReader::new(&buffer).unwrap();
+        assert_eq!(buffer.len(), len);
+        assert_eq!(reader.read_u32(raw).unwrap(), 8);
+        let root = reader.root_value().unwrap().as_object().unwrap();
+        let field = |key| root.get(key).unwrap().unwrap();
+        assert_eq!(field("open").as_bool(), Some(true));
+        assert_eq!(field("qty").as_i64(), Some(250));
+        assert_eq!(field("seq").as_u64(), Some(u64::MAX));
+        assert_eq!(field("price").as_f64(), Some(100.25));
+        assert_eq!(field("bids").as_array().unwrap().as_slice::<f64>().unwrap(), &[1.0, 2.5, 3.0]);
+        assert_eq!(field("fills").as_array().unwrap().get(1).unwrap().as_bool(), Some(true));
+        assert_eq!(field("name").as_str(), Some("book"));
+    }
+
+    #[test]
+    fn test_checks() {
+        let mut writer = ZonWriter::new();
+        let qty = writer.write_int(5);
+        let ints = writer.write_int_array(&[1, 2]);
+        writer.set_root(qty);
+        let mut buffer = writer.as_bytes().to_vec();
+        let end = buffer.len() as u32;
+
+        let mut zon = ZonMut::new(&mut buffer).unwrap();
+        assert_eq!(zon.set_u64(qty, 1), Err("Node type mismatch"));
+        assert_eq!(zon.set_f64_at(ints, 0, 1.0), Err("Node type mismatch"));
+        assert_eq!(zon.set_i64_at(ints, 2, 1), Err("Array index out of bounds"));
+        assert_eq!(zon.set_u32(8, 0), Err("Cannot write into the header"));
+        assert_eq!(zon.set_u32(end - 2, 0), Err("Write out of bounds"));
+        assert!(zon.set_i64(end, 1).is_err());
+
+        // heap allocations are 8-aligned in practice, so the slice can be borrowed
+        zon.as_mut_slice::<i64>(ints).unwrap().iter_mut().for_each(|v| *v *= 10);
+        let reader = ZonReader::new(&buffer).unwrap();
+        assert_eq!(reader.value(ints).unwrap().as_array().unwrap().as_slice::<i64>().unwrap(), &[10, 20]);
+    }
+}
@@ -0,0 +1,356 @@
+//! patches: the difference between two buffers, as a ZON buffer itself.
+//!
+//! ```text
+//! { version: 1, ops: [
+//!     { op: "set", path: ["players", 0, "score"], value: 1600 },
+//!     { op: "remove", path: ["players", 1, "tags"] },
+//! ] }
+//! ```
+//!
+//! paths are arrays of keys (strings) and indices (uints), and always refer
+//! to the old value, so the order of the ops does not matter. `set`
+//! replaces a value, adds a field, or appends when the index is one past
+//! the end (counting earlier appends). `remove` drops a field or element.
+//!
+//! `apply_patch` writes a fresh buffer that holds the same data as the new
+//! one (see `diff`), not necessarily the same bytes.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::diff::{self, Delta, Step};
+use crate::reader::ZonReader;
+use crate::value::{Tag, ZonArray, ZonValue, MAX_DEPTH};
+use crate::writer::ZonWriter;
+
+pub const PATCH_VERSION: u64 = 1;
+
+/// a patch that turns `old` into `new`. it holds only the changed values.
+pub fn make_patch(old: &ZonReader, new: &ZonReader) -> Result<Vec<u8>, &'static str> {
+    let mut writer = ZonWriter::new();
+    let mut ops = Vec::new();
+    diff::walk(old.root_value()?, new.root_value()?, &mut Vec::new(), &mut |steps, delta| {
+        let path: Vec<u32> = steps
+            .iter()
+            .map(|step| match step {
+                Step::Key(key) => writer.write_text(key),
+                Step::Index(i) => writer.write_uint(*i as u64),
+            })
+            .collect();
+        let path = writer.write_array(&path);
+        let op = match delta {
+            Delta::Added(value) | Delta::Changed(_, value) => {
+                let value = writer.write_value(value)?;
+                let op = writer.write_text("set");
+                writer.write_object(&[("op", op), ("path", path), ("value", value)])
+            }
+            Delta::Removed(_) => {
+                let op = writer.write_text("remove");
+                writer.write_object(&[("op", op), ("path", path)])
+            }
+        };
+        ops.push(op);
+        Ok(())
+    })?;
+
+    let version = writer.write_uint(PATCH_VERSION);
+    let ops = writer.write_array(&ops);
+    let root = writer.write_object(&[("version", version), ("ops", ops)]);
+    Ok(writer.finish(root))
+}
+
+/// applies a patch from `make_patch` to `old` and returns the new buffer.
+pub fn apply_patch(old: &ZonReader, patch: &ZonReader) -> Result<Vec<u8>, &'static str> {
+    let edit = Edit::parse(patch)?;
+    if edit.removed {
+        return Err("Cannot remove the root");
+    }
+    let mut writer = ZonWriter::new();
+    let root = rebuild(&mut writer, old.root_value()?, &edit, 0)?;
+    Ok(writer.finish(root))
+}
+
+/// the ops of a patch, arranged as a tree over the old value.
+#[derive(Debug, Default)]
+struct Edit<'a> {
+    value: Option<ZonValue<'a>>,
+    removed: bool,
+    fields: Vec<(&'a str, Edit<'a>)>,
+    items: BTreeMap<usize, Edit<'a>>,
+}
+
+impl<'a> Edit<'a> {
+    fn parse(patch: &ZonReader<'a>) -> Result<Self, &'static str> {
+        let root = patch.root_value()?.as_object().ok_or("Invalid patch")?;
+        if root.get("version")?.and_then(|v| v.as_u64()) != Some(PATCH_VERSION) {
+            return Err("Unsupported patch version");
+        }
+        let ops = root.get("ops")?.and_then(|v| v.as_array()).ok_or("Invalid patch")?;
+
+        let mut tree = Edit::default();
+        for op in ops.iter() {
+            let op = op?.as_object().ok_or("Invalid patch op")?;
+            let path = op.get("path")?.and_then(|v| v.as_array()).ok_or("Invalid patch op")?;
+            // every step nests the tree one level deeper
+            if path.len() > MAX_DEPTH {
+                return Err("Invalid patch path");
+            }
+            let mut edit = &mut tree;
+            for step in path.iter() {
+                edit = match step? {
+                    ZonValue::String(key) => edit.field(key),
+                    ZonValue::UInt(i) => edit.items.entry(usize::try_from(i).map_err(|_| "Invalid patch path")?).or_default(),
+                    _ => return Err("Invalid patch path"),
+                };
+            }
+            match op.get("op")?.and_then(|v| v.as_str()) {
+                Some("set") => edit.value = Some(op.get("value")?.ok_or("Invalid patch op")?),
+                Some("remove") => edit.removed = true,
+                _ => return Err("Unknown patch op"),
+            }
+        }
+        Ok(tree)
+    }
+
+    fn field(&mut self, key: &'a str) -> &mut Edit<'a> {
+        let i = match self.fields.iter().position(|(k, _)| *k == key) {
+            Some(i) => i,
+            None => {
+                self.fields.push((key, Edit::default()));
+                self.fields.len() - 1
+            }
+        };
+        &mut self.fields[i].1
+    }
+
+    fn get_field(&self, key: &str) -> Option<&Edit<'a>> {
+        self.fields.iter().find(|(k, _)| *k == key).map(|(_, edit)| edit)
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.fields.is_empty() && self.items.is_empty()
+    }
+
+    // a new field or element must be set, not edited below
+    fn added_value(&self) -> Result<ZonValue<'a>, &'static str> {
+        match self.value {
+            Some(value) if !self.removed => Ok(value),
+            _ => Err("Patch path not found"),
+        }
+    }
+}
+
+/// an array element after patching.
+enum Entry<'e, 'a> {
+    Value(ZonValue<'a>),
+    Edited(ZonValue<'a>, &'e Edit<'a>),
+}
+
+fn rebuild<'a>(writer: &mut ZonWriter, old: ZonValue<'a>, edit: &Edit<'a>, depth: usize) -> Result<u32, &'static str> {
+    if depth > MAX_DEPTH {
+        return Err("Maximum nesting depth exceeded");
+    }
+    if let Some(value) = edit.value {
+        return writer.write_value(value);
+    }
+    if edit.is_leaf() {
+        return writer.write_value(old);
+    }
+
+    match old {
+        ZonValue::Object(object) => {
+            let mut fields = Vec::with_capacity(object.len());
+            for field in object.iter() {
+                let (key, value) = field?;
+                let offset = match edit.get_field(key) {
+                    Some(child) if child.removed => continue,
+                    Some(child) => rebuild(writer, value, child, depth + 1)?,
+                    None => writer.write_value(value)?,
+                };
+                fields.push((key, offset));
+            }
+            for (key, child) in &edit.fields {
+                if object.get(key)?.is_none() {
+                    fields.push((key, writer.write_value(child.added_value()?)?));
+                }
+            }
+            Ok(writer.write_object(&fields))
+        }
+        ZonValue::Array(array) => {
+            let entries = entries(array, edit)?;
+            if let Some(offset) = write_typed(writer, array.element_tag(), &entries) {
+                return Ok(offset);
+            }
+            let mut items = Vec::with_capacity(entries.len());
+            for entry in entries {
+                items.push(match entry {
+                    Entry::Value(value) => writer.write_value(value)?,
+                    Entry::Edited(value, child) => rebuild(writer, value, child, depth + 1)?,
+                });
+            }
+            Ok(writer.write_array(&items))
+        }
+        _ => Err("Patch path not found"),
+    }
+}
+
+fn entries<'e, 'a>(array: ZonArray<'a>, edit: &'e Edit<'a>) -> Result<Vec<Entry<'e, 'a>>, &'static str> {
+    let mut entries = Vec::with_capacity(array.len());
+    for (i, item) in array.iter().enumerate() {
+        match edit.items.get(&i) {
+            Some(child) if child.removed => {}
+            Some(child) => entries.push(match child.value {
+                Some(value) => Entry::Value(value),
+                None => Entry::Edited(item?, child),
+            }),
+            None => entries.push(Entry::Value(item?)),
+        }
+    }
+    // appends are numbered from the old length on
+    for (expected, (&i, child)) in (array.len()..).zip(edit.items.range(array.len()..)) {
+        if i != expected {
+            return Err("Patch index out of range");
+        }
+        entries.push(Entry::Value(child.added_value()?));
+    }
+    Ok(entries)
+}
+
+// keeps a patched typed array typed while every element still fits
+fn write_typed(writer: &mut ZonWriter, element: Option<Tag>, entries: &[Entry]) -> Option<u32> {
+    let values: Vec<ZonValue> = entries
+        .iter()
+        .map(|entry| match entry {
+            Entry::Value(value) => Some(*value),
+            Entry::Edited(..) => None,
+        })
+        .collect::<Option<_>>()?;
+    Some(match element? {
+        Tag::Int => writer.write_int_array(&collect(&values, |v| match v {
+            ZonValue::Int(_) | ZonValue::UInt(_) => v.as_i64(),
+            _ => None,
+        })?),
+        Tag::Float => writer.write_float_array(&collect(&values, |v| match v {
+            ZonValue::Float(v) => Some(v),
+            _ => None,
+        })?),
+        Tag::Bool => writer.write_bool_array(&collect(&values, |v| v.as_bool())?),
+        Tag::String => writer.write_string_array(&collect(&values, |v| v.as_str())?),
+        _ => return None,
+    })
+}
+
+fn collect<'a, T>(values: &[ZonValue<'a>], f: impl Fn(ZonValue<'a>) -> Option<T>) -> Option<Vec<T>> {
+    values.iter().map(|v| f(*v)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(writer: &mut ZonWriter, name: &str, score: u64, tags: &[&str]) -> u32 {
+        let name = writer.write_text(name);
+        let score = writer.write_uint(score);
+        let tags = writer.write_string_array(tags);
+        writer.write_object(&[("name", name), ("score", score), ("tags", tags)])
+    }
+
+    fn state(players: &[(&str, u64, &[&str])], scores: &[i64], version: u64) -> Vec<u8> {
+        let mut writer = ZonWriter::new();
+        let players: Vec<u32> = players.iter().map(|(n, s, t)| player(&mut writer, n, *s, t)).collect();
+        let players = writer.write_array(&players);
+        let scores = writer.write_int_array(scores);
+        let version = writer.write_uint(version);
+        let root = writer.write_object(&[("players", players), ("scores", scores), ("version", version)]);
+        writer.set_root(root);
+        writer.as_bytes().to_vec()
+    }
+
+    fn roundtrip(old: &[u8], new: &[u8]) -> Vec<u8> {
+        let (old, new) = (ZonReader::new(old).unwrap(), ZonReader::new(new).unwrap());
+        let patch = make_patch(&old, &new).unwrap();
+        let patched = apply_patch(&old, &ZonReader::new(&patch).unwrap()).unwrap();
+        assert!(diff::diff(&new, &ZonReader::new(&patched).unwrap()).unwrap().is_empty());
+        patch
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let old = state(&[("Hero", 10, &["a", "b"]), ("Bob", 5, &[]), ("Eve", 1, &["x"])], &[1, 2, 3], 1);
+        let new = state(&[("Hero", 12, &["a"]), ("Bob", 5, &["new"])], &[1, 7, 3, 4, 5], 2);
+        roundtrip(&old, &new);
+        roundtrip(&new, &old);
+        roundtrip(&old, &old);
+
+        // a small change to a large buffer makes a small patch
+        let many: Vec<(&str, u64, &[&str])> = (0..500).map(|i| ("Npc", i, &["idle"][..])).collect();
+        let old = state(&many, &[0; 1000], 1);
+        let mut changed = many.clone();
+        changed[250].1 = 9000;
+        let new = state(&changed, &[0; 1000], 1);
+        let patch = roundtrip(&old, &new);
+        assert!(patch.len() < 512 && new.len() > 40_000, "{} bytes", patch.len());
+    }
+
+    #[test]
+    fn test_typed_arrays_stay_typed() {
+        let old = state(&[], &[1, 2], 1);
+        let new = state(&[], &[1, -2, 3], 1);
+        let (old, new) = (ZonReader::new(&old).unwrap(), ZonReader::new(&new).unwrap());
+        let patch = make_patch(&old, &new).unwrap();
+        let patched = apply_patch(&old, &ZonReader::new(&patch).unwrap()).unwrap();
+
+        let reader = ZonReader::new(&patched).unwrap();
+        let scores = reader.query("scores").unwrap()[0].as_array().unwrap();
+        assert_eq!(scores.as_slice::<i64>().unwrap(), &[1, -2, 3]);
+    }
+
+    // a patch with one `set` of null at `[key, index]`
+    fn set_null(version: u64, key: &str, index: u64) -> Vec<u8> {
+        let mut writer = ZonWriter::new();
+        let op = writer.write_text("set");
+        let (key, index) = (writer.write_text(key), writer.write_uint(index));
+        let path = writer.write_array(&[key, index]);
+        let value = writer.write_null();
+        let op = writer.write_object(&[("op", op), ("path", path), ("value", value)]);
+        let ops = writer.write_array(&[op]);
+        let version = writer.write_uint(version);
+        let root = writer.write_object(&[("version", version), ("ops", ops)]);
+        writer.set_root(root);
+        writer.as_bytes().to_vec()
+    }
+
+    // a patch with one `remove` whose path repeats index 0 `steps` times
+    fn remove_deep(steps: usize) -> Vec<u8> {
+        let mut writer = ZonWriter::new();
+        let op = writer.write_text("remove");
+        let index = writer.write_uint(0);
+        let path = writer.write_array(&vec![index; steps]);
+        let op = writer.write_object(&[("op", op), ("path", path)]);
+        let ops = writer.write_array(&[op]);
+        let version = writer.write_uint(PATCH_VERSION);
+        let root = writer.write_object(&[("version", version), ("ops", ops)]);
+        writer.finish(root)
+    }
+
+    #[test]
+    fn test_invalid_patches() {
+        let old = state(&[("Hero", 10, &[])], &[], 1);
+        let old = ZonReader::new(&old).unwrap();
+        let apply = |patch: Vec<u8>| apply_patch(&old, &ZonReader::new(&patch).unwrap());
+
+        assert!(apply(set_null(1, "players", 1)).is_ok());
+        assert_eq!(apply(set_null(2, "players", 1)), Err("Unsupported patch version"));
+        assert_eq!(apply(set_null(1, "players", 2)), Err("Patch index out of range"));
+        assert_eq!(apply(set_null(1, "version", 0)), Err("Patch path not found"));
+        let mut writer = ZonWriter::new();
+        let root = writer.write_null();
+        writer.set_root(root);
+        assert_eq!(apply(writer.as_bytes().to_vec()), Err("Invalid patch"));
+
+        // refused before the edit tree is built, however long the path
+        assert_ne!(apply(remove_deep(MAX_DEPTH)), Err("Invalid patch path"));
+        assert_eq!(apply(remove_deep(MAX_DEPTH + 1)), Err("Invalid patch path"));
+        assert_eq!(apply(remove_deep(1_000_000)), Err("Invalid patch path"));
+    }
+}
@@ -0,0 +1,142 @@
+//! a thread-safe pool of reusable writers.
+//!
+//! `get` hands out a writer, reusing an idle one when there is one, and the
+//! writer goes back to the pool, reset, when the guard is dropped. writers
+//! keep their capacity, so once warmed up, the plain `write_*` calls do not
+//! allocate. each `get` and return takes a lock; a single writer that is
+//! `reset` between messages is cheaper where one can stay on a thread.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+use crate::writer::ZonWriter;
+
+pub struct ZonWriterPool {
+    idle: Mutex<Vec<ZonWriter>>,
+    max_idle: usize,
+    capacity: usize,
+}
+
+impl ZonWriterPool {
+    /// a pool that keeps up to `max_idle` writers around between uses.
+    pub fn new(max_idle: usize) -> Self {
+        Self::with_capacity(max_idle, 4096)
+    }
+
+    /// a pool whose new writers start with `capacity` bytes.
+    pub fn with_capacity(max_idle: usize, capacity: usize) -> Self {
+        Self { idle: Mutex::new(Vec::with_capacity(max_idle)), max_idle, capacity }
+    }
+
+    /// an idle writer, or a new one when none is left.
+    pub fn get(&self) -> PooledWriter<'_> {
+        let writer = self.lock().pop().unwrap_or_else(|| ZonWriter::with_capacity(self.capacity));
+        PooledWriter { pool: self, writer: Some(writer) }
+    }
+
+    /// resets `writer` and keeps it for later, unless the pool is full.
+    pub fn put(&self, mut writer: ZonWriter) {
+        writer.reset();
+        let mut idle = self.lock();
+        if idle.len() < self.max_idle {
+            idle.push(writer);
+        }
+    }
+
+    /// the number of writers waiting to be reused.
+    pub fn idle(&self) -> usize {
+        self.lock().len()
+    }
+
+    // a writer panicking mid-write cannot leave the list inconsistent
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<ZonWriter>> {
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// a writer borrowed from a `ZonWriterPool`, returned when dropped.
+pub struct PooledWriter<'a> {
+    pool: &'a ZonWriterPool,
+    writer: Option<ZonWriter>,
+}
+
+impl PooledWriter<'_> {
+    /// keeps the writer instead of returning it to the pool.
+    pub fn detach(mut self) -> ZonWriter {
+        self.writer.take().unwrap()
+    }
+}
+
+impl Deref for PooledWriter<'_> {
+    type Target = ZonWriter;
+
+    fn deref(&self) -> &ZonWriter {
+        self.writer.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledWriter<'_> {
+    fn deref_mut(&mut self) -> &mut ZonWriter {
+        self.writer.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledWriter<'_> {
+    fn drop(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            self.pool.put(writer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZonReader;
+
+    #[test]
+    fn test_reuse() {
+        let pool = ZonWriterPool::new(2);
+        let ptr = {
+            let mut writer = pool.get();
+            let root = writer.write_text("hello");
+            writer.set_root(root);
+            writer.as_bytes().as_ptr()
+        };
+        assert_eq!(pool.idle(), 1);
+
+        let mut writer = pool.get();
+        assert_eq!(pool.idle(), 0);
+        assert_eq!(writer.as_bytes().as_ptr(), ptr);
+        assert_eq!(writer.len(), 64);
+        let root = writer.write_int(7);
+        let bytes = writer.detach().finish(root);
+        assert_eq!(ZonReader::new(&bytes).unwrap().root_value().unwrap().as_i64(), Some(7));
+        assert_eq!(pool.idle(), 0);
+
+        // at most `max_idle` are kept
+        let writers = [pool.get(), pool.get(), pool.get()];
+        drop(writers);
+        assert_eq!(pool.idle(), 2);
+    }
+
+    #[test]
+    fn test_threads() {
+        let pool = ZonWriterPool::new(4);
+        std::thread::scope(|s| {
+            for i in 0..8 {
+                let pool = &pool;
+                s.spawn(move || {
+                    for _ in 0..100 {
+                        let mut writer = pool.get();
+                        let root = writer.write_int(i);
+                        writer.set_root(root);
+                        let reader = ZonReader::new(writer.as_bytes()).unwrap();
+                        assert_eq!(reader.root_value().unwrap().as_i64(), Some(i));
+                    }
+                });
+            }
+        });
+        assert!(pool.idle() <= 4);
+    }
+}
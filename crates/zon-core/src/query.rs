@@ -0,0 +1,668 @@
+//! path queries for pulling values out of a buffer.
+//!
+//! ```text
+//! meta.version                 # a field
+//! players[0].name              # an element, negative indices count from the end
+//! players[*].name              # every element (or every field value of an object)
+//! players[?score > 1000].name  # elements matching a filter
+//! ["first name"]               # keys that are not identifiers
+//! ```
+//!
+//! a query may start with `$` for the root. filters compare paths relative
+//! to the element (`@` is the element itself) with literals or other paths
+//! using `==`, `!=`, `<`, `<=`, `>`, `>=`, and combine them with `&&`, `||`,
+//! `!` and parentheses. a bare path such as `[?nick]` tests that the field
+//! exists and is neither `null` nor `false`.
+//!
+//! queries walk the buffer lazily: only the nodes on the way to a match are
+//! read, and matches are zero-copy `ZonValue`s.
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::reader::ZonReader;
+use crate::schema::push_key;
+use crate::value::{ZonValue, MAX_DEPTH};
+
+/// a parsed query, reusable across buffers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(i64),
+    Wildcard,
+    Filter(Expr),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PlainStep<'q> {
+    Field(&'q str),
+    /// negative indices count from the end.
+    Index(i64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Exists(Vec<Segment>),
+    Compare(Operand, Op, Operand),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Path(Vec<Segment>),
+    Literal(Literal),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Literal {
+    Null,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    String(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// why a query could not be run.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryError {
+    /// the query text is malformed. `column` counts characters from 1.
+    Syntax { column: usize, message: String },
+    /// the buffer could not be read along the way.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::Syntax { column, message } => write!(f, "column {}: {}", column, message),
+            QueryError::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+impl core::error::Error for QueryError {}
+
+impl From<&'static str> for QueryError {
+    fn from(message: &'static str) -> Self {
+        QueryError::Invalid(message)
+    }
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser { text, pos: 0 };
+        parser.skip_ws();
+        let rooted = parser.eat('$');
+        let segments = parser.segments(!rooted, 0)?;
+        parser.skip_ws();
+        match parser.peek() {
+            None => Ok(Query { segments }),
+            Some(c) => Err(parser.error(format!("unexpected `{}`", c))),
+        }
+    }
+
+    /// the keys and indices of a query without wildcards or filters.
+    pub(crate) fn plain_steps(&self) -> Option<Vec<PlainStep<'_>>> {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Field(key) => Some(PlainStep::Field(key)),
+                Segment::Index(index) => Some(PlainStep::Index(*index)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// every value matching the query, in storage order.
+    pub fn select<'a>(&self, root: ZonValue<'a>) -> Result<Vec<ZonValue<'a>>, &'static str> {
+        let mut matches = Vec::new();
+        walk(&self.segments, root, &mut String::new(), &mut |_, value| {
+            matches.push(value);
+            true
+        })?;
+        Ok(matches)
+    }
+
+    /// like `select`, with the path of each match, e.g. `players[3].name`.
+    pub fn select_paths<'a>(&self, root: ZonValue<'a>) -> Result<Vec<(String, ZonValue<'a>)>, &'static str> {
+        let mut matches = Vec::new();
+        walk(&self.segments, root, &mut String::new(), &mut |path, value| {
+            matches.push((path.to_string(), value));
+            true
+        })?;
+        Ok(matches)
+    }
+
+    /// the first match. stops walking as soon as it is found.
+    pub fn first<'a>(&self, root: ZonValue<'a>) -> Result<Option<ZonValue<'a>>, &'static str> {
+        first(&self.segments, root)
+    }
+}
+
+impl<'a> ZonReader<'a> {
+    /// parses `query` and selects the matching values below the root.
+    pub fn query(&self, query: &str) -> Result<Vec<ZonValue<'a>>, QueryError> {
+        Ok(Query::parse(query)?.select(self.root_value()?)?)
+    }
+}
+
+// calls `sink` for every match below `value`. returns false once `sink` asks to stop.
+fn walk<'a>(
+    segments: &[Segment],
+    value: ZonValue<'a>,
+    path: &mut String,
+    sink: &mut dyn FnMut(&str, ZonValue<'a>) -> bool,
+) -> Result<bool, &'static str> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return Ok(sink(path, value));
+    };
+    let len = path.len();
+    let mut step = |path: &mut String, child| {
+        let more = walk(rest, child, path, sink);
+        path.truncate(len);
+        more
+    };
+
+    match (segment, value) {
+        (Segment::Field(name), ZonValue::Object(object)) => {
+            if let Some(child) = object.get(name)? {
+                push_key(path, name);
+                return step(path, child);
+            }
+        }
+        (Segment::Index(index), ZonValue::Array(array)) => {
+            let index = if *index < 0 { array.len() as i64 + index } else { *index };
+            if (0..array.len() as i64).contains(&index) {
+                path.push_str(&format!("[{}]", index));
+                return step(path, array.get(index as usize)?);
+            }
+        }
+        (Segment::Wildcard | Segment::Filter(_), ZonValue::Array(array)) => {
+            for (i, child) in array.iter().enumerate() {
+                let child = child?;
+                if let Segment::Filter(filter) = segment {
+                    if !filter.matches(child)? {
+                        continue;
+                    }
+                }
+                path.push_str(&format!("[{}]", i));
+                if !step(path, child)? {
+                    return Ok(false);
+                }
+            }
+        }
+        (Segment::Wildcard | Segment::Filter(_), ZonValue::Object(object)) => {
+            for field in object.iter() {
+                let (key, child) = field?;
+                if let Segment::Filter(filter) = segment {
+                    if !filter.matches(child)? {
+                        continue;
+                    }
+                }
+                push_key(path, key);
+                if !step(path, child)? {
+                    return Ok(false);
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(true)
+}
+
+fn first<'a>(segments: &[Segment], value: ZonValue<'a>) -> Result<Option<ZonValue<'a>>, &'static str> {
+    let mut found = None;
+    walk(segments, value, &mut String::new(), &mut |_, value| {
+        found = Some(value);
+        false
+    })?;
+    Ok(found)
+}
+
+/// a value as a filter compares it. values of different kinds never compare equal.
+#[derive(Debug, Clone, Copy)]
+enum Scalar<'a> {
+    Null,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    String(&'a str),
+    /// blobs and containers.
+    Other,
+}
+
+impl Expr {
+    fn matches(&self, value: ZonValue) -> Result<bool, &'static str> {
+        Ok(match self {
+            Expr::Or(a, b) => a.matches(value)? || b.matches(value)?,
+            Expr::And(a, b) => a.matches(value)? && b.matches(value)?,
+            Expr::Not(expr) => !expr.matches(value)?,
+            Expr::Exists(path) => !matches!(first(path, value)?, None | Some(ZonValue::Null | ZonValue::Bool(false))),
+            Expr::Compare(a, op, b) => {
+                let (Some(a), Some(b)) = (a.resolve(value)?, b.resolve(value)?) else {
+                    return Ok(false);
+                };
+                let ordering = compare(a, b);
+                match op {
+                    Op::Eq => ordering == Some(Ordering::Equal),
+                    Op::Ne => ordering != Some(Ordering::Equal),
+                    Op::Lt => ordering == Some(Ordering::Less),
+                    Op::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    Op::Gt => ordering == Some(Ordering::Greater),
+                    Op::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                }
+            }
+        })
+    }
+}
+
+impl Operand {
+    // `None` when the path matches nothing
+    fn resolve<'s>(&'s self, value: ZonValue<'s>) -> Result<Option<Scalar<'s>>, &'static str> {
+        let value = match self {
+            Operand::Literal(literal) => {
+                return Ok(Some(match literal {
+                    Literal::Null => Scalar::Null,
+                    Literal::Bool(b) => Scalar::Bool(*b),
+                    Literal::Int(v) => Scalar::Int(*v),
+                    Literal::Float(v) => Scalar::Float(*v),
+                    Literal::String(s) => Scalar::String(s),
+                }))
+            }
+            Operand::Path(path) => match first(path, value)? {
+                Some(value) => value,
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(match value {
+            ZonValue::Null => Scalar::Null,
+            ZonValue::Bool(b) => Scalar::Bool(b),
+            ZonValue::Int(v) => Scalar::Int(v.into()),
+            ZonValue::UInt(v) => Scalar::Int(v.into()),
+            ZonValue::Float(v) => Scalar::Float(v),
+            ZonValue::String(s) => Scalar::String(s),
+            _ => Scalar::Other,
+        }))
+    }
+}
+
+fn compare(a: Scalar, b: Scalar) -> Option<Ordering> {
+    match (a, b) {
+        (Scalar::Null, Scalar::Null) => Some(Ordering::Equal),
+        (Scalar::Bool(a), Scalar::Bool(b)) => Some(a.cmp(&b)),
+        (Scalar::Int(a), Scalar::Int(b)) => Some(a.cmp(&b)),
+        (Scalar::Int(a), Scalar::Float(b)) => (a as f64).partial_cmp(&b),
+        (Scalar::Float(a), Scalar::Int(b)) => a.partial_cmp(&(b as f64)),
+        (Scalar::Float(a), Scalar::Float(b)) => a.partial_cmp(&b),
+        (Scalar::String(a), Scalar::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+struct Parser<'s> {
+    text: &'s str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.eat_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let found = self.text[self.pos..].starts_with(s);
+        if found {
+            self.pos += s.len();
+        }
+        found
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), QueryError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", c)))
+        }
+    }
+
+    fn error(&self, message: String) -> QueryError {
+        QueryError::Syntax { column: self.text[..self.pos].chars().count() + 1, message }
+    }
+
+    // a run of segments. `bare` allows a leading field name without a dot.
+    // `depth` counts the filters and groups around it.
+    fn segments(&mut self, bare: bool, depth: usize) -> Result<Vec<Segment>, QueryError> {
+        let mut segments = Vec::new();
+        if bare && self.peek().is_some_and(is_ident_start) {
+            segments.push(Segment::Field(self.ident()));
+        }
+        loop {
+            let segment = if self.eat('.') {
+                if self.eat('*') {
+                    Segment::Wildcard
+                } else if self.peek().is_some_and(is_ident_start) {
+                    Segment::Field(self.ident())
+                } else {
+                    return Err(self.error("expected a field name after `.`".to_string()));
+                }
+            } else if self.eat('[') {
+                self.skip_ws();
+                let segment = match self.peek() {
+                    Some('*') => {
+                        self.bump();
+                        Segment::Wildcard
+                    }
+                    Some('?') => {
+                        self.bump();
+                        Segment::Filter(self.expr(depth + 1)?)
+                    }
+                    Some('"' | '\'') => Segment::Field(self.string()?),
+                    Some(c) if c == '-' || c.is_ascii_digit() => {
+                        let index = match self.number()? {
+                            Literal::Int(index) => i64::try_from(index).ok(),
+                            _ => return Err(self.error("expected an integer index".to_string())),
+                        };
+                        Segment::Index(index.ok_or_else(|| self.error("index out of range".to_string()))?)
+                    }
+                    _ => return Err(self.error("expected an index, `*`, a quoted key or `?`".to_string())),
+                };
+                self.skip_ws();
+                self.expect(']')?;
+                segment
+            } else {
+                break;
+            };
+            segments.push(segment);
+            if segments.len() > MAX_DEPTH {
+                return Err(self.error("query is nested too deeply".to_string()));
+            }
+        }
+        Ok(segments)
+    }
+
+    fn expr(&mut self, depth: usize) -> Result<Expr, QueryError> {
+        let mut expr = self.and(depth)?;
+        while self.eat_str("||") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and(depth)?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self, depth: usize) -> Result<Expr, QueryError> {
+        let mut expr = self.unary(depth)?;
+        while self.eat_str("&&") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary(depth)?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self, depth: usize) -> Result<Expr, QueryError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("filter is nested too deeply".to_string()));
+        }
+        self.skip_ws();
+        let expr = if self.eat('!') {
+            Expr::Not(Box::new(self.unary(depth + 1)?))
+        } else if self.eat('(') {
+            let expr = self.expr(depth + 1)?;
+            self.skip_ws();
+            self.expect(')')?;
+            expr
+        } else {
+            self.comparison(depth)?
+        };
+        self.skip_ws();
+        Ok(expr)
+    }
+
+    fn comparison(&mut self, depth: usize) -> Result<Expr, QueryError> {
+        let left = self.operand(depth)?;
+        self.skip_ws();
+        let op = if self.eat_str("==") {
+            Op::Eq
+        } else if self.eat_str("!=") {
+            Op::Ne
+        } else if self.eat_str("<=") {
+            Op::Le
+        } else if self.eat_str(">=") {
+            Op::Ge
+        } else if self.eat('<') {
+            Op::Lt
+        } else if self.eat('>') {
+            Op::Gt
+        } else {
+            return match left {
+                Operand::Path(path) => Ok(Expr::Exists(path)),
+                Operand::Literal(_) => Err(self.error("expected a comparison".to_string())),
+            };
+        };
+        self.skip_ws();
+        Ok(Expr::Compare(left, op, self.operand(depth)?))
+    }
+
+    fn operand(&mut self, depth: usize) -> Result<Operand, QueryError> {
+        let literal = match self.peek() {
+            Some('"' | '\'') => Literal::String(self.string()?),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number()?,
+            Some('@') => {
+                self.bump();
+                return Ok(Operand::Path(self.segments(false, depth)?));
+            }
+            Some(c) if is_ident_start(c) => {
+                let start = self.pos;
+                match self.ident().as_str() {
+                    "null" => Literal::Null,
+                    "true" => Literal::Bool(true),
+                    "false" => Literal::Bool(false),
+                    _ => {
+                        self.pos = start;
+                        return Ok(Operand::Path(self.segments(true, depth)?));
+                    }
+                }
+            }
+            _ => return Err(self.error("expected a path or a value".to_string())),
+        };
+        Ok(Operand::Literal(literal))
+    }
+
+    fn ident(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            self.bump();
+        }
+        self.text[start..self.pos].to_string()
+    }
+
+    fn number(&mut self) -> Result<Literal, QueryError> {
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '.' || c == '+' || c == '-') {
+            self.bump();
+        }
+        let text = &self.text[start..self.pos];
+        if let Ok(v) = text.parse::<i128>() {
+            return Ok(Literal::Int(v));
+        }
+        match text.parse::<f64>() {
+            Ok(v) if text.bytes().any(|b| b.is_ascii_digit()) => Ok(Literal::Float(v)),
+            _ => {
+                self.pos = start;
+                Err(self.error(format!("invalid number `{}`", text)))
+            }
+        }
+    }
+
+    // a single or double quoted string with the usual backslash escapes
+    fn string(&mut self) -> Result<String, QueryError> {
+        let quote = self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string".to_string())),
+                Some(c) if Some(c) == quote => return Ok(s),
+                Some('\\') => match self.bump() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('0') => s.push('\0'),
+                    Some('u') => s.push(self.unicode_escape()?),
+                    Some(c @ ('\\' | '"' | '\'')) => s.push(c),
+                    _ => return Err(self.error("invalid escape".to_string())),
+                },
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    // `\u{1F600}`, as printed by `{:?}`
+    fn unicode_escape(&mut self) -> Result<char, QueryError> {
+        self.expect('{')?;
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            self.bump();
+        }
+        let code = u32::from_str_radix(&self.text[start..self.pos], 16).ok().and_then(char::from_u32);
+        let c = code.ok_or_else(|| self.error("invalid unicode escape".to_string()))?;
+        self.expect('}')?;
+        Ok(c)
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZonWriter;
+
+    // { meta: { version: 3 }, players: [{ name, score, nick? }...], scores: int[] }
+    fn sample() -> Vec<u8> {
+        let mut writer = ZonWriter::new();
+        let mut players = Vec::new();
+        for (name, score, nick) in [("Hero", 1500, Some("h")), ("Sidekick", 800, None), ("Villain", 2200, None)] {
+            let name_off = writer.write_text(name);
+            let score_off = writer.write_uint(score);
+            let mut fields = vec![("name", name_off), ("score", score_off)];
+            if let Some(nick) = nick {
+                fields.push(("nick", writer.write_text(nick)));
+            }
+            players.push(writer.write_object(&fields));
+        }
+        let players = writer.write_array(&players);
+        let version = writer.write_uint(3);
+        let meta = writer.write_object(&[("version", version)]);
+        let scores = writer.write_int_array(&[5, -2, 40]);
+        let spaced = writer.write_bool(true);
+        let root = writer.write_object(&[("meta", meta), ("players", players), ("scores", scores), ("first name", spaced)]);
+        writer.set_root(root);
+        writer.as_bytes().to_vec()
+    }
+
+    fn texts(values: Vec<ZonValue<'_>>) -> Vec<&str> {
+        values.iter().map(|v| v.as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_paths() {
+        let buffer = sample();
+        let reader = ZonReader::new(&buffer).unwrap();
+
+        assert_eq!(reader.query("meta.version").unwrap()[0].as_u64(), Some(3));
+        assert_eq!(reader.query("$.meta.version").unwrap()[0].as_u64(), Some(3));
+        assert_eq!(texts(reader.query("players[*].name").unwrap()), ["Hero", "Sidekick", "Villain"]);
+        assert_eq!(texts(reader.query("players[-1].name").unwrap()), ["Villain"]);
+        assert_eq!(reader.query("scores[1]").unwrap()[0].as_i64(), Some(-2));
+        assert_eq!(reader.query("[\"first name\"]").unwrap()[0].as_bool(), Some(true));
+        assert_eq!(reader.query("$").unwrap().len(), 1);
+        assert_eq!(reader.query("meta.*").unwrap().len(), 1);
+        assert!(reader.query("players[9].name").unwrap().is_empty());
+        assert!(reader.query("meta.version.major").unwrap().is_empty());
+
+        let query = Query::parse("players[?score > 1000].name").unwrap();
+        let paths: Vec<String> = query.select_paths(reader.root_value().unwrap()).unwrap().into_iter().map(|m| m.0).collect();
+        assert_eq!(paths, ["players[0].name", "players[2].name"]);
+    }
+
+    #[test]
+    fn test_filters() {
+        let buffer = sample();
+        let reader = ZonReader::new(&buffer).unwrap();
+        let names = |query| texts(reader.query(query).unwrap());
+
+        assert_eq!(names("players[?score > 1000].name"), ["Hero", "Villain"]);
+        assert_eq!(names("players[?score >= 800 && score < 2000].name"), ["Hero", "Sidekick"]);
+        assert_eq!(names("players[?name == 'Sidekick' || score == 2.2e3].name"), ["Sidekick", "Villain"]);
+        assert_eq!(names("players[?!(score > 1000)].name"), ["Sidekick"]);
+        assert_eq!(names("players[?nick].name"), ["Hero"]);
+        assert_eq!(names("players[?@.nick != 'h'].name"), [] as [&str; 0]);
+        assert_eq!(names("players[?name > \"S\"].name"), ["Sidekick", "Villain"]);
+        // mismatched types never compare equal
+        assert_eq!(names("players[?score == '800'].name"), [] as [&str; 0]);
+
+        let scores: Vec<i64> = reader.query("scores[?@ < 10]").unwrap().iter().map(|v| v.as_i64().unwrap()).collect();
+        assert_eq!(scores, [5, -2]);
+
+        let first = Query::parse("players[?score < 1000]").unwrap().first(reader.root_value().unwrap()).unwrap();
+        assert_eq!(first.unwrap().as_object().unwrap().get("name").unwrap().unwrap().as_str(), Some("Sidekick"));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let column = |query| match Query::parse(query).unwrap_err() {
+            QueryError::Syntax { column, .. } => column,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(column("players[*"), 10);
+        assert_eq!(column("players."), 9);
+        assert_eq!(column("players[?score >]"), 17);
+        assert_eq!(column("players[?1]"), 11);
+        assert_eq!(column("a b"), 3);
+        assert_eq!(column("['open"), 7);
+        assert!(Query::parse(&format!("a[?{}]", "(".repeat(1000))).is_err());
+        // each filter counts towards the limit, not just the groups inside one
+        let nested = format!("a{}", "[?b".repeat(200_000));
+        match Query::parse(&nested).unwrap_err() {
+            QueryError::Syntax { message, .. } => assert_eq!(message, "filter is nested too deeply"),
+            other => panic!("{:?}", other),
+        }
+    }
+}
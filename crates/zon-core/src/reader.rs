@@ -1,13 +1,14 @@
-use crate::header::{ZonHeader, ZON_MAGIC};
-use std::str;
+use crate::header::{ZonHeader, FLAG_COMPRESSED, FLAG_ENCRYPTED, ZON_MAGIC};
+use core::str;
 
+#[derive(Debug, Clone, Copy)]
 pub struct ZonReader<'a> {
     buffer: &'a [u8],
 }
 
 impl<'a> ZonReader<'a> {
     pub fn new(buffer: &'a [u8]) -> Result<Self, &'static str> {
-        if buffer.len() < std::mem::size_of::<ZonHeader>() {
+        if buffer.len() < core::mem::size_of::<ZonHeader>() {
             return Err("Buffer too small for ZonHeader");
         }
 
@@ -20,9 +21,41 @@ impl<'a> ZonReader<'a> {
             return Err("Invalid Magic Number");
         }
 
+        // offsets in an encrypted or compressed buffer point into the original
+        let flags = u32::from_le_bytes(buffer[12..16].try_into().unwrap());
+        if flags & FLAG_ENCRYPTED != 0 {
+            return Err("Buffer is encrypted, open it with a key");
+        }
+        if flags & FLAG_COMPRESSED != 0 {
+            return Err("Buffer is compressed, decompress it first");
+        }
+
         Ok(Self { buffer })
     }
 
+    // for owners that checked the header when they took the buffer
+    #[cfg(feature = "alloc")]
+    pub(crate) fn new_unchecked(buffer: &'a [u8]) -> Self {
+        Self { buffer }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.buffer
+    }
+
+    /// the root offset stored in the header.
+    pub fn root(&self) -> Result<u32, &'static str> {
+        self.read_u32(8)
+    }
+
     pub fn read_u32(&self, offset: u32) -> Result<u32, &'static str> {
         let start = offset as usize;
         let end = start + 4;
@@ -48,4 +81,52 @@ impl<'a> ZonReader<'a> {
         let str_bytes = &self.buffer[start..end];
         str::from_utf8(str_bytes).map_err(|_| "Invalid UTF-8")
     }
+
+    pub fn read_u64(&self, offset: u32) -> Result<u64, &'static str> {
+        let start = offset as usize;
+        let end = start + 8;
+        if end > self.buffer.len() {
+            return Err("Read out of bounds");
+        }
+        let slice = &self.buffer[start..end];
+        Ok(u64::from_le_bytes(slice.try_into().unwrap()))
+    }
+
+    /// reads a length-prefixed byte blob, laid out like a string.
+    pub fn read_bytes(&self, offset: u32) -> Result<&'a [u8], &'static str> {
+        let len = self.read_u32(offset)?;
+
+        let start = offset as usize + 4;
+        let end = start + len as usize;
+
+        if end > self.buffer.len() {
+            return Err("Bytes read out of bounds");
+        }
+
+        Ok(&self.buffer[start..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Tag;
+
+    // built by hand, so it also runs with neither `std` nor `alloc`
+    #[test]
+    fn test_read_without_alloc() {
+        let mut buffer = [0u8; 80];
+        buffer[0..4].copy_from_slice(&ZON_MAGIC.to_le_bytes());
+        buffer[4..8].copy_from_slice(&1u32.to_le_bytes());
+        buffer[8..12].copy_from_slice(&64u32.to_le_bytes());
+        buffer[64..68].copy_from_slice(&Tag::String.word().to_le_bytes());
+        buffer[68..72].copy_from_slice(&5u32.to_le_bytes());
+        buffer[72..77].copy_from_slice(b"hello");
+
+        let reader = ZonReader::new(&buffer).unwrap();
+        assert_eq!(reader.root_value().unwrap().as_str(), Some("hello"));
+        assert_eq!(ZonReader::new(&buffer[..32]).unwrap_err(), "Buffer too small for ZonHeader");
+        buffer[12] = FLAG_COMPRESSED as u8;
+        assert_eq!(ZonReader::new(&buffer).unwrap_err(), "Buffer is compressed, decompress it first");
+    }
 }
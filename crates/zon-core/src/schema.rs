@@ -0,0 +1,630 @@
+//! optional schemas for self-describing ZON data.
+//!
+//! a schema is declared either with the builder API or in a small text
+//! format (`.zons`):
+//!
+//! ```text
+//! # named types can be referenced anywhere, including recursively
+//! type Player = {
+//!     name: string,
+//!     score: uint,
+//!     nick?: string,      # optional field
+//! }
+//!
+//! root {
+//!     players: [Player],
+//!     meta?: { version: uint, .. },   # `..` allows unknown fields
+//! }
+//! ```
+//!
+//! primitive types: `any`, `null`, `bool`, `int`, `uint`, `float`, `number`, `string`, `bytes`.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::reader::ZonReader;
+use crate::value::{ZonValue, MAX_DEPTH};
+
+/// the expected shape of a value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaType {
+    Any,
+    Null,
+    Bool,
+    /// any integer that fits in an i64.
+    Int,
+    /// any non-negative integer that fits in a u64.
+    UInt,
+    Float,
+    /// any integer or float.
+    Number,
+    String,
+    Binary,
+    Array(Box<SchemaType>),
+    Object(ObjectType),
+    /// reference to a type registered with `Schema::define`.
+    Named(String),
+}
+
+impl SchemaType {
+    pub fn array(item: impl Into<SchemaType>) -> Self {
+        SchemaType::Array(Box::new(item.into()))
+    }
+
+    pub fn object() -> ObjectType {
+        ObjectType::default()
+    }
+
+    pub fn named(name: &str) -> Self {
+        SchemaType::Named(name.to_string())
+    }
+}
+
+impl fmt::Display for SchemaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaType::Any => f.write_str("any"),
+            SchemaType::Null => f.write_str("null"),
+            SchemaType::Bool => f.write_str("bool"),
+            SchemaType::Int => f.write_str("int"),
+            SchemaType::UInt => f.write_str("uint"),
+            SchemaType::Float => f.write_str("float"),
+            SchemaType::Number => f.write_str("number"),
+            SchemaType::String => f.write_str("string"),
+            SchemaType::Binary => f.write_str("bytes"),
+            SchemaType::Array(item) => write!(f, "[{}]", item),
+            SchemaType::Object(_) => f.write_str("object"),
+            SchemaType::Named(name) => f.write_str(name),
+        }
+    }
+}
+
+/// field list of an object type. also the builder for object schemas.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjectType {
+    pub fields: Vec<Field>,
+    /// whether fields not listed in `fields` are allowed.
+    pub open: bool,
+}
+
+impl ObjectType {
+    pub fn field(mut self, name: &str, ty: impl Into<SchemaType>) -> Self {
+        self.fields.push(Field { name: name.to_string(), ty: ty.into(), optional: false });
+        self
+    }
+
+    pub fn optional(mut self, name: &str, ty: impl Into<SchemaType>) -> Self {
+        self.fields.push(Field { name: name.to_string(), ty: ty.into(), optional: true });
+        self
+    }
+
+    pub fn open(mut self) -> Self {
+        self.open = true;
+        self
+    }
+}
+
+impl From<ObjectType> for SchemaType {
+    fn from(object: ObjectType) -> Self {
+        SchemaType::Object(object)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub ty: SchemaType,
+    pub optional: bool,
+}
+
+/// a root type plus the named types it may reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    pub root: SchemaType,
+    pub types: BTreeMap<String, SchemaType>,
+}
+
+impl Schema {
+    pub fn new(root: impl Into<SchemaType>) -> Self {
+        Self { root: root.into(), types: BTreeMap::new() }
+    }
+
+    /// registers a named type that `SchemaType::Named` can refer to.
+    pub fn define(mut self, name: &str, ty: impl Into<SchemaType>) -> Self {
+        self.types.insert(name.to_string(), ty.into());
+        self
+    }
+
+    /// parses the `.zons` text format.
+    pub fn parse(src: &str) -> Result<Self, ParseError> {
+        Parser::new(src).parse_schema()
+    }
+}
+
+/// a single place where the data does not match the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// path to the offending value, e.g. `players[3].name`. empty for the root.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "(root): {}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl core::error::Error for SchemaError {}
+
+/// checks the root value of `reader` against `schema`.
+/// returns every mismatch found, not just the first one.
+pub fn validate(reader: &ZonReader, schema: &Schema) -> Result<(), Vec<SchemaError>> {
+    let mut validator = Validator { schema, path: String::new(), errors: Vec::new() };
+    match reader.root_value() {
+        Ok(value) => validator.check(&value, &schema.root, 0),
+        Err(e) => validator.error(e.to_string()),
+    }
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+struct Validator<'s> {
+    schema: &'s Schema,
+    path: String,
+    errors: Vec<SchemaError>,
+}
+
+impl Validator<'_> {
+    fn error(&mut self, message: String) {
+        self.errors.push(SchemaError { path: self.path.clone(), message });
+    }
+
+    fn mismatch(&mut self, expected: &SchemaType, found: &ZonValue) {
+        self.error(format!("expected {}, found {}", expected, found.type_name()));
+    }
+
+    fn check(&mut self, value: &ZonValue, ty: &SchemaType, depth: usize) {
+        if depth > MAX_DEPTH {
+            self.error("maximum nesting depth exceeded".to_string());
+            return;
+        }
+        let ok = match ty {
+            SchemaType::Any => true,
+            SchemaType::Null => matches!(value, ZonValue::Null),
+            SchemaType::Bool => matches!(value, ZonValue::Bool(_)),
+            SchemaType::Int => value.as_i64().is_some(),
+            SchemaType::UInt => value.as_u64().is_some(),
+            SchemaType::Float => matches!(value, ZonValue::Float(_)),
+            SchemaType::Number => value.as_f64().is_some(),
+            SchemaType::String => matches!(value, ZonValue::String(_)),
+            SchemaType::Binary => matches!(value, ZonValue::Binary(_)),
+            SchemaType::Array(item) => match value {
+                ZonValue::Array(array) => {
+                    for (i, element) in array.iter().enumerate() {
+                        let len = self.path.len();
+                        self.path.push_str(&format!("[{}]", i));
+                        match element {
+                            Ok(element) => self.check(&element, item, depth + 1),
+                            Err(e) => self.error(e.to_string()),
+                        }
+                        self.path.truncate(len);
+                    }
+                    true
+                }
+                _ => false,
+            },
+            SchemaType::Object(object) => match value {
+                ZonValue::Object(_) => {
+                    self.check_object(value, object, depth);
+                    true
+                }
+                _ => false,
+            },
+            SchemaType::Named(name) => match self.schema.types.get(name) {
+                Some(resolved) => {
+                    self.check(value, resolved, depth + 1);
+                    true
+                }
+                None => {
+                    self.error(format!("unknown type `{}`", name));
+                    true
+                }
+            },
+        };
+        if !ok {
+            self.mismatch(ty, value);
+        }
+    }
+
+    fn check_object(&mut self, value: &ZonValue, ty: &ObjectType, depth: usize) {
+        let object = match value.as_object() {
+            Some(object) => object,
+            None => return,
+        };
+
+        for field in &ty.fields {
+            let len = self.path.len();
+            push_key(&mut self.path, &field.name);
+            match object.get(&field.name) {
+                Ok(Some(found)) => self.check(&found, &field.ty, depth + 1),
+                Ok(None) if !field.optional => self.error("missing required field".to_string()),
+                Ok(None) => {}
+                Err(e) => self.error(e.to_string()),
+            }
+            self.path.truncate(len);
+        }
+
+        if ty.open {
+            return;
+        }
+        for i in 0..object.len() {
+            match object.entry(i) {
+                Ok((key, _)) if !ty.fields.iter().any(|f| f.name == key) => {
+                    let len = self.path.len();
+                    push_key(&mut self.path, key);
+                    self.error("unexpected field".to_string());
+                    self.path.truncate(len);
+                }
+                Ok(_) => {}
+                Err(e) => self.error(e.to_string()),
+            }
+        }
+    }
+}
+
+// appends `.key`, or `["key"]` when the key is not a plain identifier.
+pub(crate) fn push_key(path: &mut String, key: &str) {
+    if is_ident(key) {
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(key);
+    } else {
+        path.push_str(&format!("[{:?}]", key));
+    }
+}
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// a syntax error in a `.zons` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Punct(char),
+    DotDot,
+    Eof,
+}
+
+struct Parser<'s> {
+    chars: core::iter::Peekable<core::str::Chars<'s>>,
+    line: usize,
+    column: usize,
+    peeked: Option<(Token, usize, usize)>,
+}
+
+impl<'s> Parser<'s> {
+    fn new(src: &'s str) -> Self {
+        Self { chars: src.chars().peekable(), line: 1, column: 1, peeked: None }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn lex(&mut self) -> Result<(Token, usize, usize), ParseError> {
+        // skip whitespace and comments
+        loop {
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('#') => {
+                    while matches!(self.chars.peek(), Some(c) if *c != '\n') {
+                        self.bump();
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let (line, column) = (self.line, self.column);
+        let token = match self.bump() {
+            None => Token::Eof,
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(&c) = self.chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    ident.push(c);
+                    self.bump();
+                }
+                Token::Ident(ident)
+            }
+            Some('"') => {
+                let mut text = String::new();
+                loop {
+                    match self.bump() {
+                        Some('"') => break,
+                        Some('\\') => match self.bump() {
+                            Some(c @ ('"' | '\\')) => text.push(c),
+                            _ => return Err(self.error_at(line, column, "invalid escape in string")),
+                        },
+                        Some(c) => text.push(c),
+                        None => return Err(self.error_at(line, column, "unterminated string")),
+                    }
+                }
+                Token::Str(text)
+            }
+            Some('.') if self.chars.peek() == Some(&'.') => {
+                self.bump();
+                Token::DotDot
+            }
+            Some(c @ ('{' | '}' | '[' | ']' | ':' | ',' | '?' | '=')) => Token::Punct(c),
+            Some(c) => return Err(self.error_at(line, column, &format!("unexpected character `{}`", c))),
+        };
+        Ok((token, line, column))
+    }
+
+    fn next(&mut self) -> Result<(Token, usize, usize), ParseError> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => self.lex(),
+        }
+    }
+
+    fn peek(&mut self) -> Result<&Token, ParseError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex()?);
+        }
+        Ok(&self.peeked.as_ref().unwrap().0)
+    }
+
+    fn error_at(&self, line: usize, column: usize, message: &str) -> ParseError {
+        ParseError { line, column, message: message.to_string() }
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), ParseError> {
+        match self.next()? {
+            (Token::Punct(c), _, _) if c == punct => Ok(()),
+            (_, line, column) => Err(self.error_at(line, column, &format!("expected `{}`", punct))),
+        }
+    }
+
+    fn parse_schema(mut self) -> Result<Schema, ParseError> {
+        let mut root = None;
+        let mut types = BTreeMap::new();
+        let mut references = Vec::new();
+
+        loop {
+            match self.next()? {
+                (Token::Eof, line, column) => {
+                    let root = root.ok_or_else(|| self.error_at(line, column, "missing `root` declaration"))?;
+                    for (name, line, column) in references {
+                        if !types.contains_key(&name) {
+                            return Err(self.error_at(line, column, &format!("unknown type `{}`", name)));
+                        }
+                    }
+                    return Ok(Schema { root, types });
+                }
+                (Token::Ident(kw), line, column) if kw == "type" => {
+                    let name = match self.next()? {
+                        (Token::Ident(name), _, _) if primitive(&name).is_none() => name,
+                        (_, line, column) => return Err(self.error_at(line, column, "expected type name")),
+                    };
+                    if types.contains_key(&name) {
+                        return Err(self.error_at(line, column, &format!("type `{}` defined twice", name)));
+                    }
+                    self.expect('=')?;
+                    let ty = self.parse_type(&mut references, 0)?;
+                    types.insert(name, ty);
+                }
+                (Token::Ident(kw), line, column) if kw == "root" => {
+                    if root.is_some() {
+                        return Err(self.error_at(line, column, "`root` declared twice"));
+                    }
+                    root = Some(self.parse_type(&mut references, 0)?);
+                }
+                (_, line, column) => return Err(self.error_at(line, column, "expected `type` or `root`")),
+            }
+        }
+    }
+
+    fn parse_type(&mut self, references: &mut Vec<(String, usize, usize)>, depth: usize) -> Result<SchemaType, ParseError> {
+        match self.next()? {
+            (_, line, column) if depth > MAX_DEPTH => Err(self.error_at(line, column, "type is nested too deeply")),
+            (Token::Ident(name), line, column) => Ok(match primitive(&name) {
+                Some(ty) => ty,
+                None => {
+                    references.push((name.clone(), line, column));
+                    SchemaType::Named(name)
+                }
+            }),
+            (Token::Punct('['), _, _) => {
+                let item = self.parse_type(references, depth + 1)?;
+                self.expect(']')?;
+                Ok(SchemaType::array(item))
+            }
+            (Token::Punct('{'), _, _) => self.parse_object(references, depth + 1),
+            (_, line, column) => Err(self.error_at(line, column, "expected a type")),
+        }
+    }
+
+    fn parse_object(&mut self, references: &mut Vec<(String, usize, usize)>, depth: usize) -> Result<SchemaType, ParseError> {
+        let mut object = ObjectType::default();
+        loop {
+            let (token, line, column) = self.next()?;
+            let name = match token {
+                Token::Punct('}') => return Ok(object.into()),
+                Token::Punct(',') => continue,
+                Token::DotDot => {
+                    object.open = true;
+                    continue;
+                }
+                Token::Ident(name) | Token::Str(name) => name,
+                _ => return Err(self.error_at(line, column, "expected field name or `}`")),
+            };
+            if object.fields.iter().any(|f| f.name == name) {
+                return Err(self.error_at(line, column, &format!("field `{}` declared twice", name)));
+            }
+            let optional = self.peek()? == &Token::Punct('?');
+            if optional {
+                self.next()?;
+            }
+            self.expect(':')?;
+            let ty = self.parse_type(references, depth)?;
+            object.fields.push(Field { name, ty, optional });
+        }
+    }
+}
+
+fn primitive(name: &str) -> Option<SchemaType> {
+    let ty = match name {
+        "any" => SchemaType::Any,
+        "null" => SchemaType::Null,
+        "bool" => SchemaType::Bool,
+        "int" => SchemaType::Int,
+        "uint" => SchemaType::UInt,
+        "float" => SchemaType::Float,
+        "number" => SchemaType::Number,
+        "string" => SchemaType::String,
+        "bytes" => SchemaType::Binary,
+        _ => return None,
+    };
+    Some(ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ZonWriter;
+
+    const ROSTER: &str = r#"
+        # roster of players
+        type Player = {
+            name: string,
+            score: uint,
+            nick?: string,
+        }
+
+        root {
+            players: [Player]
+            meta?: { version: uint, .. }
+        }
+    "#;
+
+    #[test]
+    fn test_parse_matches_builder() {
+        let parsed = Schema::parse(ROSTER).unwrap();
+        let built = Schema::new(
+            SchemaType::object()
+                .field("players", SchemaType::array(SchemaType::named("Player")))
+                .optional("meta", SchemaType::object().field("version", SchemaType::UInt).open()),
+        )
+        .define(
+            "Player",
+            SchemaType::object()
+                .field("name", SchemaType::String)
+                .field("score", SchemaType::UInt)
+                .optional("nick", SchemaType::String),
+        );
+        assert_eq!(parsed, built);
+    }
+
+    #[test]
+    fn test_validate_ok() {
+        let schema = Schema::parse(ROSTER).unwrap();
+        let mut writer = ZonWriter::new();
+        let name = writer.write_text("Hero");
+        let score = writer.write_uint(7);
+        let player = writer.write_object(&[("name", name), ("score", score)]);
+        let players = writer.write_array(&[player]);
+        let root = writer.write_object(&[("players", players)]);
+        writer.set_root(root);
+
+        let reader = ZonReader::new(writer.as_bytes()).unwrap();
+        assert_eq!(validate(&reader, &schema), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_reports_every_mismatch() {
+        let schema = Schema::parse(ROSTER).unwrap();
+        let mut writer = ZonWriter::new();
+        let name = writer.write_text("Hero");
+        let score = writer.write_uint(100);
+        let first = writer.write_object(&[("name", name), ("score", score)]);
+        let bad_name = writer.write_int(5);
+        let second = writer.write_object(&[("name", bad_name), ("hp", score)]);
+        let players = writer.write_array(&[first, second]);
+        let root = writer.write_object(&[("players", players)]);
+        writer.set_root(root);
+
+        let reader = ZonReader::new(writer.as_bytes()).unwrap();
+        let errors = validate(&reader, &schema).unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "players[1].name: expected string, found int",
+                "players[1].score: missing required field",
+                "players[1].hp: unexpected field",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Schema::parse("root { name: strin }").unwrap_err();
+        assert_eq!(err.to_string(), "1:14: unknown type `strin`");
+
+        let err = Schema::parse("type A = int").unwrap_err();
+        assert_eq!(err.message, "missing `root` declaration");
+
+        let err = Schema::parse("root {\n  name string\n}").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 8));
+
+        let err = Schema::parse(&format!("root {}", "[".repeat(200_000))).unwrap_err();
+        assert_eq!(err.message, "type is nested too deeply");
+        let err = Schema::parse(&format!("root {}", "{ a: ".repeat(200_000))).unwrap_err();
+        assert_eq!(err.message, "type is nested too deeply");
+    }
+}
@@ -0,0 +1,119 @@
+//! Ed25519 signatures.
+//!
+//! `sign` sets `FLAG_SIGNED` in the header and appends a 64-byte trailer
+//! holding the signature of everything before it, header included. offsets
+//! do not move, so a signed buffer reads like the original. signing works
+//! on plain, compressed and encrypted buffers alike, and comes last: any
+//! change afterwards fails verification, and `ZonWriter::append` refuses
+//! signed buffers.
+//!
+//! keys are raw 32-byte Ed25519 keys: the secret seed for signing and the
+//! public key for verifying.
+
+#[cfg(feature = "signing")]
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+
+use crate::compress::{read_u32, HEADER_SIZE};
+use crate::header::{FLAG_SIGNED, ZON_MAGIC};
+
+pub const SIGNATURE_SIZE: usize = 64;
+
+/// whether `buffer` carries a signature trailer.
+pub fn is_signed(buffer: &[u8]) -> bool {
+    buffer.len() >= HEADER_SIZE + SIGNATURE_SIZE
+        && read_u32(buffer, 0) == ZON_MAGIC
+        && read_u32(buffer, 12) & FLAG_SIGNED != 0
+}
+
+/// `buffer` without its signature trailer, if it has one.
+pub fn strip_signature(buffer: &[u8]) -> &[u8] {
+    match is_signed(buffer) {
+        true => &buffer[..buffer.len() - SIGNATURE_SIZE],
+        false => buffer,
+    }
+}
+
+/// the public key that verifies signatures made with `secret_key`.
+#[cfg(feature = "signing")]
+pub fn public_key(secret_key: &[u8; 32]) -> [u8; 32] {
+    SigningKey::from_bytes(secret_key).verifying_key().to_bytes()
+}
+
+/// signs a copy of `buffer`.
+#[cfg(feature = "signing")]
+pub fn sign(buffer: &[u8], secret_key: &[u8; 32]) -> Result<Vec<u8>, &'static str> {
+    if buffer.len() < HEADER_SIZE || read_u32(buffer, 0) != ZON_MAGIC {
+        return Err("Invalid Magic Number");
+    }
+    if read_u32(buffer, 12) & FLAG_SIGNED != 0 {
+        return Err("Buffer is already signed");
+    }
+    let mut signed = Vec::with_capacity(buffer.len() + SIGNATURE_SIZE);
+    signed.extend_from_slice(buffer);
+    signed[12..16].copy_from_slice(&(read_u32(buffer, 12) | FLAG_SIGNED).to_le_bytes());
+    let signature = SigningKey::from_bytes(secret_key).sign(&signed);
+    signed.extend_from_slice(&signature.to_bytes());
+    Ok(signed)
+}
+
+/// checks that `buffer` was signed with the secret key behind `public_key`
+/// and has not changed since.
+#[cfg(feature = "signing")]
+pub fn verify_signature(buffer: &[u8], public_key: &[u8; 32]) -> Result<(), &'static str> {
+    if !is_signed(buffer) {
+        return Err("Buffer is not signed");
+    }
+    let key = VerifyingKey::from_bytes(public_key).map_err(|_| "Invalid public key")?;
+    let (data, trailer) = buffer.split_at(buffer.len() - SIGNATURE_SIZE);
+    let signature = Signature::from_bytes(trailer.try_into().unwrap());
+    key.verify_strict(data, &signature).map_err(|_| "Signature does not match")
+}
+
+#[cfg(all(test, feature = "signing"))]
+mod tests {
+    use super::*;
+    use crate::{ZonReader, ZonWriter};
+
+    const SECRET: [u8; 32] = [3; 32];
+
+    fn sample() -> Vec<u8> {
+        let mut writer = ZonWriter::new();
+        let region = writer.write_text("eu-west");
+        let root = writer.write_object(&[("region", region)]);
+        writer.set_root(root);
+        writer.as_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let buffer = sample();
+        let signed = sign(&buffer, &SECRET).unwrap();
+        let public = public_key(&SECRET);
+        assert!(is_signed(&signed));
+        assert_eq!(verify_signature(&signed, &public), Ok(()));
+
+        // still readable as is
+        let root = ZonReader::new(&signed).unwrap().root_value().unwrap().as_object().unwrap();
+        assert_eq!(root.get("region").unwrap().unwrap().as_str(), Some("eu-west"));
+
+        assert_eq!(verify_signature(&signed, &public_key(&[4; 32])), Err("Signature does not match"));
+        assert_eq!(verify_signature(&buffer, &public), Err("Buffer is not signed"));
+        assert_eq!(sign(&signed, &SECRET).unwrap_err(), "Buffer is already signed");
+        assert_eq!(crate::compress(&signed, crate::Codec::Lz4).unwrap_err(), "Buffer is signed, sign after compressing or encrypting");
+        for at in [8, 12, HEADER_SIZE + 5, signed.len() - 1] {
+            let mut tampered = signed.clone();
+            tampered[at] ^= 1;
+            assert!(verify_signature(&tampered, &public).is_err(), "byte {}", at);
+        }
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_sign_encrypted() {
+        let key = [5; 32];
+        let sealed = crate::encrypt(&sample(), &key, crate::Cipher::Aes256Gcm).unwrap();
+        let signed = sign(&sealed, &SECRET).unwrap();
+        assert_eq!(verify_signature(&signed, &public_key(&SECRET)), Ok(()));
+        assert_eq!(&ZonReader::open_encrypted(&signed, &key).unwrap()[..], &sample()[..]);
+    }
+}
@@ -0,0 +1,467 @@
+//! writing into a caller-provided buffer.
+//!
+//! `ZonSliceWriter` has the write methods of `ZonWriter`, but fills a fixed
+//! `&mut [u8]`, such as a preallocated ring-buffer slot or shared memory,
+//! and never allocates. a write that does not fit fails with
+//! `"Buffer capacity exceeded"`; nodes written before it stay valid, the
+//! partial one is unreachable, and `set_root` still points wherever it did.
+//!
+//! containers written by `write_object` and `write_value` put their tables
+//! before the nodes they point at, so nothing has to be buffered on the way.
+//! readers do not care about the order.
+
+use crate::header::ZonHeader;
+#[cfg(feature = "alloc")]
+use crate::query::{PlainStep, Query};
+use crate::reader::ZonReader;
+use crate::value::{Tag, ZonArray, ZonValue, MAX_DEPTH};
+
+const HEADER_SIZE: usize = core::mem::size_of::<ZonHeader>();
+
+pub struct ZonSliceWriter<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> ZonSliceWriter<'a> {
+    /// starts a new buffer at the beginning of `buffer` with a default header.
+    pub fn new(buffer: &'a mut [u8]) -> Result<Self, &'static str> {
+        if buffer.len() < HEADER_SIZE {
+            return Err("Buffer capacity exceeded");
+        }
+        let header = ZonHeader::default();
+        // safety: ZonHeader is POD and repr(C).
+        let header = unsafe { core::slice::from_raw_parts(&header as *const ZonHeader as *const u8, HEADER_SIZE) };
+        buffer[..HEADER_SIZE].copy_from_slice(header);
+        Ok(Self { buffer, len: HEADER_SIZE })
+    }
+
+    /// continues after the first `len` bytes of `buffer`, which hold a
+    /// valid buffer, as `ZonWriter::append` does.
+    pub fn append(buffer: &'a mut [u8], len: usize) -> Result<Self, &'static str> {
+        ZonReader::new(buffer.get(..len).ok_or("Buffer capacity exceeded")?)?;
+        let mut writer = Self { buffer, len };
+        writer.pad_to(4)?;
+        Ok(writer)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// the size of the underlying buffer.
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// the bytes written so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+
+    /// appends the 4 bytes of val and returns their offset.
+    pub fn write_u32(&mut self, val: u32) -> Result<u32, &'static str> {
+        let offset = self.len as u32;
+        self.extend(&val.to_le_bytes())?;
+        Ok(offset)
+    }
+
+    /// writes a length-prefixed string padded to 4 bytes, as
+    /// `ZonWriter::write_string` does.
+    pub fn write_string(&mut self, val: &str) -> Result<u32, &'static str> {
+        let offset = self.write_u32(val.len() as u32)?;
+        self.extend(val.as_bytes())?;
+        self.pad_to(4)?;
+        Ok(offset)
+    }
+
+    /// appends zero bytes until the length is a multiple of `align`.
+    pub fn pad_to(&mut self, align: usize) -> Result<(), &'static str> {
+        let end = self.len.next_multiple_of(align);
+        self.buffer.get_mut(self.len..end).ok_or("Buffer capacity exceeded")?.fill(0);
+        self.len = end;
+        Ok(())
+    }
+
+    /// writes a tagged null node.
+    pub fn write_null(&mut self) -> Result<u32, &'static str> {
+        self.write_u32(Tag::Null.word())
+    }
+
+    /// writes a tagged bool node.
+    pub fn write_bool(&mut self, val: bool) -> Result<u32, &'static str> {
+        let offset = self.write_u32(Tag::Bool.word())?;
+        self.write_u32(val as u32)?;
+        Ok(offset)
+    }
+
+    /// writes a tagged signed integer node.
+    pub fn write_int(&mut self, val: i64) -> Result<u32, &'static str> {
+        self.write_wide(Tag::Int, val.to_le_bytes())
+    }
+
+    /// writes a tagged unsigned integer node.
+    pub fn write_uint(&mut self, val: u64) -> Result<u32, &'static str> {
+        self.write_wide(Tag::UInt, val.to_le_bytes())
+    }
+
+    /// writes a tagged f64 node.
+    pub fn write_float(&mut self, val: f64) -> Result<u32, &'static str> {
+        self.write_wide(Tag::Float, val.to_le_bytes())
+    }
+
+    /// writes a tagged string node.
+    pub fn write_text(&mut self, val: &str) -> Result<u32, &'static str> {
+        let offset = self.write_u32(Tag::String.word())?;
+        self.write_string(val)?;
+        Ok(offset)
+    }
+
+    /// writes a tagged binary blob node.
+    pub fn write_binary(&mut self, val: &[u8]) -> Result<u32, &'static str> {
+        let offset = self.write_u32(Tag::Binary.word())?;
+        self.write_u32(val.len() as u32)?;
+        self.extend(val)?;
+        self.pad_to(4)?;
+        Ok(offset)
+    }
+
+    /// writes an array node pointing at already written nodes.
+    pub fn write_array(&mut self, items: &[u32]) -> Result<u32, &'static str> {
+        let offset = self.write_u32(Tag::Array.word())?;
+        self.write_u32(items.len() as u32)?;
+        for &item in items {
+            self.write_u32(item)?;
+        }
+        Ok(offset)
+    }
+
+    /// writes an object node pointing at already written nodes. the keys
+    /// follow the field table.
+    pub fn write_object(&mut self, fields: &[(&str, u32)]) -> Result<u32, &'static str> {
+        let offset = self.reserve(Tag::Object, fields.len(), fields.len() * 8)?;
+        for (i, (key, value)) in fields.iter().enumerate() {
+            let key = self.write_string(key)?;
+            self.patch(offset + 8 + i as u32 * 8, key);
+            self.patch(offset + 12 + i as u32 * 8, *value);
+        }
+        Ok(offset)
+    }
+
+    /// writes an object node from `(key offset, value offset)` pairs.
+    pub fn write_object_offsets(&mut self, fields: &[(u32, u32)]) -> Result<u32, &'static str> {
+        let offset = self.write_u32(Tag::Object.word())?;
+        self.write_u32(fields.len() as u32)?;
+        for &(key, value) in fields {
+            self.write_u32(key)?;
+            self.write_u32(value)?;
+        }
+        Ok(offset)
+    }
+
+    /// writes a typed array of i64 values, 8-byte aligned.
+    pub fn write_int_array(&mut self, values: &[i64]) -> Result<u32, &'static str> {
+        self.write_typed(Tag::IntArray, values.iter().map(|v| Ok(v.to_le_bytes())))
+    }
+
+    /// writes a typed array of f64 values, 8-byte aligned.
+    pub fn write_float_array(&mut self, values: &[f64]) -> Result<u32, &'static str> {
+        self.write_typed(Tag::FloatArray, values.iter().map(|v| Ok(v.to_le_bytes())))
+    }
+
+    /// writes a typed array of bools, one byte each.
+    pub fn write_bool_array(&mut self, values: &[bool]) -> Result<u32, &'static str> {
+        self.write_typed(Tag::BoolArray, values.iter().map(|&v| Ok([v as u8])))
+    }
+
+    /// writes a typed array of strings.
+    pub fn write_string_array<S: AsRef<str>>(&mut self, values: &[S]) -> Result<u32, &'static str> {
+        let offset = self.write_u32(Tag::StringArray.word())?;
+        self.write_u32(values.len() as u32)?;
+        let mut end = 0;
+        self.write_u32(end)?;
+        for v in values {
+            end += v.as_ref().len() as u32;
+            self.write_u32(end)?;
+        }
+        for v in values {
+            self.extend(v.as_ref().as_bytes())?;
+        }
+        self.pad_to(4)?;
+        Ok(offset)
+    }
+
+    /// copies `value` and everything below it. typed arrays stay typed.
+    pub fn write_value(&mut self, value: ZonValue) -> Result<u32, &'static str> {
+        self.copy_value(value, 0)
+    }
+
+    fn copy_value(&mut self, value: ZonValue, depth: usize) -> Result<u32, &'static str> {
+        if depth > MAX_DEPTH {
+            return Err("Maximum nesting depth exceeded");
+        }
+        match value {
+            ZonValue::Null => self.write_null(),
+            ZonValue::Bool(b) => self.write_bool(b),
+            ZonValue::Int(v) => self.write_int(v),
+            ZonValue::UInt(v) => self.write_uint(v),
+            ZonValue::Float(v) => self.write_float(v),
+            ZonValue::String(s) => self.write_text(s),
+            ZonValue::Binary(b) => self.write_binary(b),
+            ZonValue::Array(array) => match array.tag() {
+                Tag::IntArray => self.write_typed(array.tag(), elements(array, |v| v.as_i64().map(i64::to_le_bytes))),
+                Tag::FloatArray => self.write_typed(array.tag(), elements(array, |v| v.as_f64().map(f64::to_le_bytes))),
+                Tag::BoolArray => self.write_typed(array.tag(), elements(array, |v| v.as_bool().map(|b| [b as u8]))),
+                Tag::StringArray => self.copy_string_array(array),
+                _ => {
+                    let offset = self.reserve(Tag::Array, array.len(), array.len() * 4)?;
+                    for (i, item) in array.iter().enumerate() {
+                        let item = self.copy_value(item?, depth + 1)?;
+                        self.patch(offset + 8 + i as u32 * 4, item);
+                    }
+                    Ok(offset)
+                }
+            },
+            ZonValue::Object(object) => {
+                let offset = self.reserve(Tag::Object, object.len(), object.len() * 8)?;
+                for (i, field) in object.iter().enumerate() {
+                    let (key, value) = field?;
+                    let key = self.write_string(key)?;
+                    let value = self.copy_value(value, depth + 1)?;
+                    self.patch(offset + 8 + i as u32 * 8, key);
+                    self.patch(offset + 12 + i as u32 * 8, value);
+                }
+                Ok(offset)
+            }
+        }
+    }
+
+    fn copy_string_array(&mut self, array: ZonArray) -> Result<u32, &'static str> {
+        let offset = self.reserve(Tag::StringArray, array.len(), (array.len() + 1) * 4)?;
+        let mut end = 0;
+        for (i, item) in array.iter().enumerate() {
+            let item = item?.as_str().ok_or("Typed array element type mismatch")?;
+            self.extend(item.as_bytes())?;
+            end += item.len() as u32;
+            self.patch(offset + 12 + i as u32 * 4, end);
+        }
+        self.pad_to(4)?;
+        Ok(offset)
+    }
+
+    /// copy-on-write update, as `ZonWriter::write_update` does.
+    #[cfg(feature = "alloc")]
+    pub fn write_update(&mut self, node: u32, path: &str, value: u32) -> Result<u32, &'static str> {
+        let query = Query::parse(path).map_err(|_| "Invalid path")?;
+        self.update(node, &query.plain_steps().ok_or("Invalid path")?, value)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn update(&mut self, node: u32, path: &[PlainStep], value: u32) -> Result<u32, &'static str> {
+        let Some((segment, rest)) = path.split_first() else {
+            return Ok(value);
+        };
+        if path.len() > MAX_DEPTH {
+            return Err("Maximum nesting depth exceeded");
+        }
+        let reader = ZonReader::new(self.as_bytes())?;
+        match (segment, reader.value(node)?) {
+            (PlainStep::Field(key), ZonValue::Object(object)) => {
+                let (table, len) = (object.offset() + 8, object.len());
+                let mut found = None;
+                for i in 0..len {
+                    if object.entry(i)?.0 == *key {
+                        found = Some(i);
+                    }
+                }
+                let (slot, child, key) = match found {
+                    Some(i) => (i, self.update(self.read_u32(table + i as u32 * 8 + 4), rest, value)?, None),
+                    None if rest.is_empty() => (len, value, Some(self.write_string(key)?)),
+                    None => return Err("Path not found"),
+                };
+                let offset = self.write_u32(Tag::Object.word())?;
+                self.write_u32((len + key.is_some() as usize) as u32)?;
+                for i in 0..len as u32 {
+                    self.write_u32(self.read_u32(table + i * 8))?;
+                    self.write_u32(if i as usize == slot { child } else { self.read_u32(table + i * 8 + 4) })?;
+                }
+                if let Some(key) = key {
+                    self.write_u32(key)?;
+                    self.write_u32(child)?;
+                }
+                Ok(offset)
+            }
+            (PlainStep::Index(index), ZonValue::Array(array)) if array.element_tag().is_none() => {
+                let (table, len) = (array.offset() + 8, array.len());
+                let i = if *index < 0 { len as i64 + index } else { *index };
+                let slot = usize::try_from(i).ok().filter(|&i| i < len).ok_or("Path not found")?;
+                let child = self.update(self.read_u32(table + slot as u32 * 4), rest, value)?;
+                let offset = self.write_u32(Tag::Array.word())?;
+                self.write_u32(len as u32)?;
+                for i in 0..len {
+                    self.write_u32(if i == slot { child } else { self.read_u32(table + i as u32 * 4) })?;
+                }
+                Ok(offset)
+            }
+            (PlainStep::Index(_), ZonValue::Array(_)) => Err("Typed array elements are stored inline"),
+            _ => Err("Path not found"),
+        }
+    }
+
+    /// updates the root offset in the header.
+    pub fn set_root(&mut self, offset: u32) {
+        self.patch(8, offset);
+    }
+
+    fn extend(&mut self, bytes: &[u8]) -> Result<(), &'static str> {
+        let end = self.len + bytes.len();
+        self.buffer.get_mut(self.len..end).ok_or("Buffer capacity exceeded")?.copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+
+    // 8-byte payloads, aligned as in `ZonWriter`
+    fn write_wide(&mut self, tag: Tag, bytes: [u8; 8]) -> Result<u32, &'static str> {
+        self.pad_to(8)?;
+        let offset = self.write_u32(tag.word())?;
+        self.write_u32(0)?;
+        self.extend(&bytes)?;
+        Ok(offset)
+    }
+
+    // a typed array from its elements' bytes
+    fn write_typed<const N: usize>(
+        &mut self,
+        tag: Tag,
+        values: impl ExactSizeIterator<Item = Result<[u8; N], &'static str>>,
+    ) -> Result<u32, &'static str> {
+        if N == 8 {
+            self.pad_to(8)?;
+        }
+        let offset = self.write_u32(tag.word())?;
+        self.write_u32(values.len() as u32)?;
+        for v in values {
+            self.extend(&v?)?;
+        }
+        self.pad_to(4)?;
+        Ok(offset)
+    }
+
+    // a container node with a zeroed table of `size` bytes, filled in
+    // with `patch` as its children are written
+    fn reserve(&mut self, tag: Tag, count: usize, size: usize) -> Result<u32, &'static str> {
+        let offset = self.write_u32(tag.word())?;
+        self.write_u32(count as u32)?;
+        let end = self.len + size;
+        self.buffer.get_mut(self.len..end).ok_or("Buffer capacity exceeded")?.fill(0);
+        self.len = end;
+        Ok(offset)
+    }
+
+    fn patch(&mut self, at: u32, val: u32) {
+        self.buffer[at as usize..at as usize + 4].copy_from_slice(&val.to_le_bytes());
+    }
+
+    #[cfg(feature = "alloc")]
+    fn read_u32(&self, at: u32) -> u32 {
+        u32::from_le_bytes(self.buffer[at as usize..at as usize + 4].try_into().unwrap())
+    }
+}
+
+// the elements of a typed array as bytes
+fn elements<'a, const N: usize>(
+    array: ZonArray<'a>,
+    f: impl Fn(ZonValue<'a>) -> Option<[u8; N]> + 'a,
+) -> impl ExactSizeIterator<Item = Result<[u8; N], &'static str>> + 'a {
+    (0..array.len()).map(move |i| f(array.get(i)?).ok_or("Typed array element type mismatch"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capacity() {
+        let mut small = [0u8; 32];
+        assert_eq!(ZonSliceWriter::new(&mut small).err(), Some("Buffer capacity exceeded"));
+
+        let mut buffer = [0u8; 96];
+        let mut writer = ZonSliceWriter::new(&mut buffer).unwrap();
+        let name = writer.write_text("hello").unwrap();
+        writer.set_root(name);
+        assert_eq!(writer.write_text("a string that no longer fits"), Err("Buffer capacity exceeded"));
+        assert_eq!(writer.write_int_array(&[1, 2, 3]), Err("Buffer capacity exceeded"));
+        let len = writer.len();
+        assert_eq!(writer.capacity(), 96);
+
+        // what was written before the failure still reads
+        let reader = ZonReader::new(&buffer[..len]).unwrap();
+        assert_eq!(reader.root_value().unwrap().as_str(), Some("hello"));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_matches_zon_writer() {
+        use crate::ZonWriter;
+
+        let mut writer = ZonWriter::new();
+        let mut buffer = [0u8; 512];
+        let mut slice = ZonSliceWriter::new(&mut buffer).unwrap();
+        let items = [
+            (writer.write_null(), slice.write_null().unwrap()),
+            (writer.write_bool(true), slice.write_bool(true).unwrap()),
+            (writer.write_int(-7), slice.write_int(-7).unwrap()),
+            (writer.write_float(2.5), slice.write_float(2.5).unwrap()),
+            (writer.write_binary(b"\x01\x02\x03"), slice.write_binary(b"\x01\x02\x03").unwrap()),
+            (writer.write_int_array(&[1, 2]), slice.write_int_array(&[1, 2]).unwrap()),
+            (writer.write_bool_array(&[true, false]), slice.write_bool_array(&[true, false]).unwrap()),
+            (writer.write_string_array(&["a", "bc"]), slice.write_string_array(&["a", "bc"]).unwrap()),
+        ];
+        let root = writer.write_array(&items.map(|(a, _)| a));
+        writer.set_root(root);
+        let root = slice.write_array(&items.map(|(_, b)| b)).unwrap();
+        slice.set_root(root);
+        assert_eq!(slice.as_bytes(), writer.as_bytes());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_write_value_and_update() {
+        use crate::{diff, ZonWriter};
+
+        let mut writer = ZonWriter::new();
+        let names = writer.write_string_array(&["ada", "grace"]);
+        let score = writer.write_uint(10);
+        let player = writer.write_object(&[("name", names), ("score", score)]);
+        let scores = writer.write_float_array(&[1.5, 2.5]);
+        let players = writer.write_array(&[player]);
+        let root = writer.write_object(&[("players", players), ("scores", scores)]);
+        writer.set_root(root);
+        let original = ZonReader::new(writer.as_bytes()).unwrap();
+
+        let mut buffer = [0u8; 1024];
+        let mut slice = ZonSliceWriter::new(&mut buffer).unwrap();
+        let root = slice.write_value(original.root_value().unwrap()).unwrap();
+        slice.set_root(root);
+        let copy = ZonReader::new(slice.as_bytes()).unwrap();
+        assert!(diff(&original, &copy).unwrap().is_empty());
+
+        let score = slice.write_uint(20).unwrap();
+        let updated = slice.write_update(root, "players[0].score", score).unwrap();
+        let level = slice.write_uint(3).unwrap();
+        let updated = slice.write_update(updated, "players[-1].level", level).unwrap();
+        slice.set_root(updated);
+        assert_eq!(slice.write_update(updated, "scores[0]", score), Err("Typed array elements are stored inline"));
+        assert_eq!(slice.write_update(updated, "players[1].score", score), Err("Path not found"));
+
+        let reader = ZonReader::new(slice.as_bytes()).unwrap();
+        let query = |path| crate::Query::parse(path).unwrap().first(reader.root_value().unwrap()).unwrap().unwrap();
+        assert_eq!(query("players[0].score").as_u64(), Some(20));
+        assert_eq!(query("players[0].level").as_u64(), Some(3));
+        assert_eq!(query("players[0].name[1]").as_str(), Some("grace"));
+    }
+}
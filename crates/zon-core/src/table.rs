@@ -0,0 +1,142 @@
+//! columnar tables: an object whose fields are equally long typed arrays,
+//! one per column. see `csv` (behind the `csv` feature) for an importer.
+
+use crate::typed::{FromZon, ZonList};
+use crate::value::{ZonArray, ZonObject, ZonValue};
+
+/// a zero-copy view of a columnar table.
+#[derive(Debug, Clone, Copy)]
+pub struct ZonTable<'a> {
+    object: ZonObject<'a>,
+    rows: usize,
+}
+
+impl<'a> ZonTable<'a> {
+    /// checks that every field of `object` is an array and that all of them
+    /// have the same length.
+    pub fn new(object: ZonObject<'a>) -> Result<Self, &'static str> {
+        let mut rows = None;
+        for field in object.iter() {
+            let array = field?.1.as_array().ok_or("Table column is not an array")?;
+            if *rows.get_or_insert(array.len()) != array.len() {
+                return Err("Table columns differ in length");
+            }
+        }
+        Ok(Self { object, rows: rows.unwrap_or(0) })
+    }
+
+    pub fn as_object(&self) -> ZonObject<'a> {
+        self.object
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// column names in storage order.
+    pub fn columns(&self) -> impl Iterator<Item = Result<&'a str, &'static str>> + 'a {
+        let object = self.object;
+        (0..object.len()).map(move |i| object.entry(i).map(|(name, _)| name))
+    }
+
+    /// the array backing the column `name`.
+    pub fn array(&self, name: &str) -> Result<ZonArray<'a>, &'static str> {
+        let value = self.object.get(name)?.ok_or("Missing column")?;
+        value.as_array().ok_or("Table column is not an array")
+    }
+
+    /// a column read as `T`: numbers as borrowed slices, bools and strings
+    /// as lists, e.g. `table.column::<f64>("price")?` is a `&[f64]`.
+    pub fn column<T: Column<'a>>(&self, name: &str) -> Result<T::Column, &'static str> {
+        T::column(self.array(name)?)
+    }
+}
+
+impl<'a> FromZon<'a> for ZonTable<'a> {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        ZonTable::new(value.as_object().ok_or("Expected object")?)
+    }
+}
+
+/// element types `ZonTable::column` can read a column as.
+pub trait Column<'a> {
+    type Column;
+
+    fn column(array: ZonArray<'a>) -> Result<Self::Column, &'static str>;
+}
+
+impl<'a> Column<'a> for i64 {
+    type Column = &'a [i64];
+
+    fn column(array: ZonArray<'a>) -> Result<Self::Column, &'static str> {
+        array.as_slice()
+    }
+}
+
+impl<'a> Column<'a> for f64 {
+    type Column = &'a [f64];
+
+    fn column(array: ZonArray<'a>) -> Result<Self::Column, &'static str> {
+        array.as_slice()
+    }
+}
+
+impl<'a> Column<'a> for bool {
+    type Column = ZonList<'a, bool>;
+
+    fn column(array: ZonArray<'a>) -> Result<Self::Column, &'static str> {
+        ZonList::from_zon(ZonValue::Array(array))
+    }
+}
+
+impl<'a> Column<'a> for &'a str {
+    type Column = ZonList<'a, &'a str>;
+
+    fn column(array: ZonArray<'a>) -> Result<Self::Column, &'static str> {
+        ZonList::from_zon(ZonValue::Array(array))
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::{ZonReader, ZonWriter};
+
+    #[test]
+    fn test_columns() {
+        let mut writer = ZonWriter::new();
+        let name = writer.write_string_array(&["apple", "", "cherry"]);
+        let price = writer.write_float_array(&[1.5, 0.25, 8.0]);
+        let stock = writer.write_int_array(&[3, -1, 40]);
+        let organic = writer.write_bool_array(&[true, false, true]);
+        let root = writer.write_object(&[("name", name), ("price", price), ("stock", stock), ("organic", organic)]);
+        writer.set_root(root);
+
+        let reader = ZonReader::new(writer.as_bytes()).unwrap();
+        let table = ZonTable::from_zon(reader.root_value().unwrap()).unwrap();
+        assert_eq!(table.rows(), 3);
+        assert_eq!(table.columns().collect::<Result<Vec<_>, _>>().unwrap(), ["name", "price", "stock", "organic"]);
+
+        assert_eq!(table.column::<f64>("price").unwrap(), &[1.5, 0.25, 8.0]);
+        assert_eq!(table.column::<i64>("stock").unwrap().iter().sum::<i64>(), 42);
+        let names: Vec<&str> = table.column::<&str>("name").unwrap().iter().map(Result::unwrap).collect();
+        assert_eq!(names, ["apple", "", "cherry"]);
+        assert!(!table.column::<bool>("organic").unwrap().get(1).unwrap());
+
+        assert_eq!(table.column::<i64>("price").unwrap_err(), "Typed array element type mismatch");
+        assert_eq!(table.column::<f64>("weight").unwrap_err(), "Missing column");
+    }
+
+    #[test]
+    fn test_ragged_table() {
+        let mut writer = ZonWriter::new();
+        let a = writer.write_int_array(&[1, 2]);
+        let b = writer.write_int_array(&[1]);
+        let root = writer.write_object(&[("a", a), ("b", b)]);
+        writer.set_root(root);
+
+        let reader = ZonReader::new(writer.as_bytes()).unwrap();
+        let object = reader.root_value().unwrap().as_object().unwrap();
+        assert_eq!(ZonTable::new(object).unwrap_err(), "Table columns differ in length");
+    }
+}
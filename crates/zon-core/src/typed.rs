@@ -0,0 +1,149 @@
+//! typed accessors over self-describing values.
+//! this is the runtime used by code generated with `zon-codegen`.
+
+use core::marker::PhantomData;
+
+use crate::value::{ZonArray, ZonObject, ZonValue};
+
+/// conversion from a borrowed value into a typed view.
+pub trait FromZon<'a>: Sized {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str>;
+}
+
+impl<'a> FromZon<'a> for ZonValue<'a> {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        Ok(value)
+    }
+}
+
+impl<'a> FromZon<'a> for () {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        match value {
+            ZonValue::Null => Ok(()),
+            _ => Err("Expected null"),
+        }
+    }
+}
+
+impl<'a> FromZon<'a> for bool {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        value.as_bool().ok_or("Expected bool")
+    }
+}
+
+impl<'a> FromZon<'a> for i64 {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        value.as_i64().ok_or("Expected int")
+    }
+}
+
+impl<'a> FromZon<'a> for u64 {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        value.as_u64().ok_or("Expected uint")
+    }
+}
+
+impl<'a> FromZon<'a> for f64 {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        value.as_f64().ok_or("Expected number")
+    }
+}
+
+impl<'a> FromZon<'a> for &'a str {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        value.as_str().ok_or("Expected string")
+    }
+}
+
+impl<'a> FromZon<'a> for &'a [u8] {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        value.as_binary().ok_or("Expected bytes")
+    }
+}
+
+impl<'a> FromZon<'a> for ZonArray<'a> {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        value.as_array().ok_or("Expected array")
+    }
+}
+
+impl<'a> FromZon<'a> for ZonObject<'a> {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        value.as_object().ok_or("Expected object")
+    }
+}
+
+/// an array whose elements are all read as `T`.
+#[derive(Debug, Clone, Copy)]
+pub struct ZonList<'a, T> {
+    array: ZonArray<'a>,
+    _item: PhantomData<T>,
+}
+
+impl<'a, T: FromZon<'a>> ZonList<'a, T> {
+    pub fn len(&self) -> usize {
+        self.array.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.array.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Result<T, &'static str> {
+        T::from_zon(self.array.get(index)?)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Result<T, &'static str>> + 'a
+    where
+        T: 'a,
+    {
+        self.array.iter().map(|item| T::from_zon(item?))
+    }
+}
+
+impl<'a, T: FromZon<'a>> FromZon<'a> for ZonList<'a, T> {
+    fn from_zon(value: ZonValue<'a>) -> Result<Self, &'static str> {
+        Ok(Self { array: ZonArray::from_zon(value)?, _item: PhantomData })
+    }
+}
+
+impl<'a> ZonObject<'a> {
+    /// reads a required field as `T`.
+    pub fn field<T: FromZon<'a>>(&self, key: &str) -> Result<T, &'static str> {
+        T::from_zon(self.get(key)?.ok_or("Missing field")?)
+    }
+
+    /// reads an optional field as `T`.
+    pub fn optional_field<T: FromZon<'a>>(&self, key: &str) -> Result<Option<T>, &'static str> {
+        self.get(key)?.map(T::from_zon).transpose()
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::{ZonReader, ZonWriter};
+
+    #[test]
+    fn test_typed_fields() {
+        let mut writer = ZonWriter::new();
+        let a = writer.write_text("a");
+        let b = writer.write_text("b");
+        let tags = writer.write_array(&[a, b]);
+        let hp = writer.write_int(100);
+        let root = writer.write_object(&[("tags", tags), ("hp", hp)]);
+        writer.set_root(root);
+
+        let reader = ZonReader::new(writer.as_bytes()).unwrap();
+        let root = reader.root_value().unwrap().as_object().unwrap();
+
+        let tags: ZonList<&str> = root.field("tags").unwrap();
+        let tags: Vec<&str> = tags.iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(tags, ["a", "b"]);
+
+        assert_eq!(root.field::<i64>("hp"), Ok(100));
+        assert_eq!(root.field::<&str>("hp"), Err("Expected string"));
+        assert_eq!(root.field::<bool>("alive"), Err("Missing field"));
+        assert_eq!(root.optional_field::<bool>("alive"), Ok(None));
+    }
+}
@@ -0,0 +1,562 @@
+use crate::reader::ZonReader;
+
+/// nesting limit for recursive walks over untrusted buffers.
+/// offsets can point anywhere, so a crafted file may contain cycles.
+pub const MAX_DEPTH: usize = 128;
+
+/// upper bytes of every tag word ("ZN" in a hexdump), so tagged nodes
+/// can be told apart from raw `write_u32`/`write_string` data.
+pub const TAG_MARKER: u32 = 0x4E5A_0000;
+
+/// type tag stored in the low byte of the first word of every self-describing node.
+///
+/// node layouts (all little endian, all 4-byte aligned):
+/// - `Null`:   `[tag]`
+/// - `Bool`:   `[tag][0 | 1]`
+/// - `Int`, `UInt`, `Float`: `[tag][pad][8 byte payload]`, node is 8-byte aligned
+/// - `String`, `Binary`: `[tag][len][bytes][padding]`
+/// - `Array`:  `[tag][count][offset * count]`
+/// - `Object`: `[tag][count][(key offset, value offset) * count]`
+/// - `IntArray`, `FloatArray`: `[tag][count][8 byte value * count]`, node is 8-byte aligned
+/// - `BoolArray`: `[tag][count][1 byte value * count][padding]`
+/// - `StringArray`: `[tag][count][end offset * (count + 1)][bytes][padding]`,
+///   string `i` is `bytes[offset[i]..offset[i + 1]]`
+///
+/// object keys point to raw length-prefixed strings (see `ZonWriter::write_string`).
+/// the typed arrays store their elements inline instead of as nodes, so columns
+/// can be scanned (or borrowed as `&[f64]` / `&[i64]`) without chasing pointers.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tag {
+    Null = 1,
+    Bool = 2,
+    Int = 3,
+    UInt = 4,
+    Float = 5,
+    String = 6,
+    Binary = 7,
+    Array = 8,
+    Object = 9,
+    IntArray = 10,
+    FloatArray = 11,
+    BoolArray = 12,
+    StringArray = 13,
+}
+
+impl Tag {
+    pub fn from_u8(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(Tag::Null),
+            2 => Some(Tag::Bool),
+            3 => Some(Tag::Int),
+            4 => Some(Tag::UInt),
+            5 => Some(Tag::Float),
+            6 => Some(Tag::String),
+            7 => Some(Tag::Binary),
+            8 => Some(Tag::Array),
+            9 => Some(Tag::Object),
+            10 => Some(Tag::IntArray),
+            11 => Some(Tag::FloatArray),
+            12 => Some(Tag::BoolArray),
+            13 => Some(Tag::StringArray),
+            _ => None,
+        }
+    }
+
+    /// the full tag word written at the start of a node.
+    pub fn word(self) -> u32 {
+        TAG_MARKER | self as u32
+    }
+
+    /// lowercase type name, as used by schemas and the inspector.
+    pub fn name(self) -> &'static str {
+        match self {
+            Tag::Null => "null",
+            Tag::Bool => "bool",
+            Tag::Int => "int",
+            Tag::UInt => "uint",
+            Tag::Float => "float",
+            Tag::String => "string",
+            Tag::Binary => "bytes",
+            Tag::Array => "array",
+            Tag::Object => "object",
+            Tag::IntArray => "int[]",
+            Tag::FloatArray => "float[]",
+            Tag::BoolArray => "bool[]",
+            Tag::StringArray => "string[]",
+        }
+    }
+
+    /// the element type of a typed array tag, `None` for everything else.
+    pub fn element(self) -> Option<Tag> {
+        match self {
+            Tag::IntArray => Some(Tag::Int),
+            Tag::FloatArray => Some(Tag::Float),
+            Tag::BoolArray => Some(Tag::Bool),
+            Tag::StringArray => Some(Tag::String),
+            _ => None,
+        }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for i64 {}
+    impl Sealed for f64 {}
+}
+
+/// element types a typed array can be borrowed as, see `ZonArray::as_slice`.
+pub trait Element: Copy + sealed::Sealed {
+    const TAG: Tag;
+}
+
+impl Element for i64 {
+    const TAG: Tag = Tag::IntArray;
+}
+
+impl Element for f64 {
+    const TAG: Tag = Tag::FloatArray;
+}
+
+/// a zero-copy view of a self-describing node.
+/// strings and blobs borrow from the buffer, arrays and objects are lazy.
+#[derive(Debug, Clone, Copy)]
+pub enum ZonValue<'a> {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(&'a str),
+    Binary(&'a [u8]),
+    Array(ZonArray<'a>),
+    Object(ZonObject<'a>),
+}
+
+impl<'a> ZonValue<'a> {
+    pub fn tag(&self) -> Tag {
+        match self {
+            ZonValue::Null => Tag::Null,
+            ZonValue::Bool(_) => Tag::Bool,
+            ZonValue::Int(_) => Tag::Int,
+            ZonValue::UInt(_) => Tag::UInt,
+            ZonValue::Float(_) => Tag::Float,
+            ZonValue::String(_) => Tag::String,
+            ZonValue::Binary(_) => Tag::Binary,
+            ZonValue::Array(array) => array.tag(),
+            ZonValue::Object(_) => Tag::Object,
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        self.tag().name()
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            ZonValue::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// signed view of any integer node that fits in an i64.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            ZonValue::Int(v) => Some(v),
+            ZonValue::UInt(v) => i64::try_from(v).ok(),
+            _ => None,
+        }
+    }
+
+    /// unsigned view of any non-negative integer node.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            ZonValue::Int(v) => u64::try_from(v).ok(),
+            ZonValue::UInt(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// numeric view of any number node. large integers may lose precision.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            ZonValue::Int(v) => Some(v as f64),
+            ZonValue::UInt(v) => Some(v as f64),
+            ZonValue::Float(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&'a str> {
+        match *self {
+            ZonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_binary(&self) -> Option<&'a [u8]> {
+        match *self {
+            ZonValue::Binary(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<ZonArray<'a>> {
+        match *self {
+            ZonValue::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<ZonObject<'a>> {
+        match *self {
+            ZonValue::Object(o) => Some(o),
+            _ => None,
+        }
+    }
+}
+
+/// lazy view of an array node, either a table of node offsets or a typed
+/// array with inline elements. elements are decoded on access.
+#[derive(Debug, Clone, Copy)]
+pub struct ZonArray<'a> {
+    reader: ZonReader<'a>,
+    offset: u32,
+    len: u32,
+    tag: Tag,
+}
+
+impl<'a> ZonArray<'a> {
+    /// offset of the array node itself.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// the node tag: `Array`, or one of the typed array tags.
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    /// the element type of a typed array, `None` for an array of nodes.
+    pub fn element_tag(&self) -> Option<Tag> {
+        self.tag.element()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// offset of the node stored at `index`. typed arrays have no element nodes.
+    pub fn offset_at(&self, index: usize) -> Result<u32, &'static str> {
+        if self.tag != Tag::Array {
+            return Err("Typed array elements are stored inline");
+        }
+        if index >= self.len() {
+            return Err("Array index out of bounds");
+        }
+        self.reader.read_u32(self.offset + 8 + index as u32 * 4)
+    }
+
+    pub fn get(&self, index: usize) -> Result<ZonValue<'a>, &'static str> {
+        if index >= self.len() {
+            return Err("Array index out of bounds");
+        }
+        let at = self.offset + 8;
+        let i = index as u32;
+        let value = match self.tag {
+            Tag::IntArray => ZonValue::Int(self.reader.read_u64(at + i * 8)? as i64),
+            Tag::FloatArray => ZonValue::Float(f64::from_bits(self.reader.read_u64(at + i * 8)?)),
+            Tag::BoolArray => ZonValue::Bool(self.reader.as_bytes()[(at + i) as usize] != 0),
+            Tag::StringArray => {
+                let start = self.reader.read_u32(at + i * 4)?;
+                let end = self.reader.read_u32(at + i * 4 + 4)?;
+                let last = self.reader.read_u32(at + self.len * 4)?;
+                if start > end || end > last {
+                    return Err("Invalid string array offsets");
+                }
+                let data = (at + (self.len + 1) * 4) as usize;
+                let bytes = &self.reader.as_bytes()[data + start as usize..data + end as usize];
+                ZonValue::String(core::str::from_utf8(bytes).map_err(|_| "Invalid UTF-8")?)
+            }
+            _ => return self.reader.value(self.offset_at(index)?),
+        };
+        Ok(value)
+    }
+
+    /// borrows the elements of a typed array in place.
+    ///
+    /// fails if the array holds a different element type, or if the buffer
+    /// itself is not aligned for `T` in memory (the writer aligns the
+    /// elements within the buffer, the allocation is up to the caller).
+    pub fn as_slice<T: Element>(&self) -> Result<&'a [T], &'static str> {
+        if self.tag != T::TAG {
+            return Err("Typed array element type mismatch");
+        }
+        if cfg!(target_endian = "big") {
+            return Err("Typed arrays can only be borrowed on little-endian targets");
+        }
+        let start = self.offset as usize + 8;
+        let bytes = &self.reader.as_bytes()[start..start + self.len() * core::mem::size_of::<T>()];
+        // safety: `Element` is only implemented for i64 and f64, for which every
+        // bit pattern is valid, and align_to only yields correctly aligned items.
+        let (head, items, tail) = unsafe { bytes.align_to::<T>() };
+        if !head.is_empty() || !tail.is_empty() {
+            return Err("Typed array is not aligned in memory");
+        }
+        Ok(items)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Result<ZonValue<'a>, &'static str>> + 'a {
+        let array = *self;
+        (0..array.len()).map(move |i| array.get(i))
+    }
+}
+
+/// lazy view of an object node. fields keep their insertion order.
+#[derive(Debug, Clone, Copy)]
+pub struct ZonObject<'a> {
+    reader: ZonReader<'a>,
+    offset: u32,
+    len: u32,
+}
+
+impl<'a> ZonObject<'a> {
+    /// offset of the object node itself.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// key and value offset of the field stored at `index`.
+    pub fn entry(&self, index: usize) -> Result<(&'a str, u32), &'static str> {
+        if index >= self.len() {
+            return Err("Object index out of bounds");
+        }
+        let slot = self.offset + 8 + index as u32 * 8;
+        let key = self.reader.read_string(self.reader.read_u32(slot)?)?;
+        let value = self.reader.read_u32(slot + 4)?;
+        Ok((key, value))
+    }
+
+    /// looks up a field by key. linear scan, objects are expected to be small.
+    pub fn get(&self, key: &str) -> Result<Option<ZonValue<'a>>, &'static str> {
+        match self.offset_of(key)? {
+            Some(off) => self.reader.value(off).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// offset of the node stored under `key`.
+    pub fn offset_of(&self, key: &str) -> Result<Option<u32>, &'static str> {
+        for i in 0..self.len() {
+            let (k, off) = self.entry(i)?;
+            if k == key {
+                return Ok(Some(off));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Result<(&'a str, ZonValue<'a>), &'static str>> + 'a {
+        let object = *self;
+        (0..object.len()).map(move |i| {
+            let (key, off) = object.entry(i)?;
+            Ok((key, object.reader.value(off)?))
+        })
+    }
+}
+
+impl<'a> ZonReader<'a> {
+    /// reads the tag of the node at `offset`.
+    pub fn read_tag(&self, offset: u32) -> Result<Tag, &'static str> {
+        tag_of(self.read_u32(offset)?)
+    }
+
+    /// decodes the self-describing node at `offset`.
+    pub fn value(&self, offset: u32) -> Result<ZonValue<'a>, &'static str> {
+        let value = match self.read_tag(offset)? {
+            Tag::Null => ZonValue::Null,
+            Tag::Bool => ZonValue::Bool(self.read_u32(offset + 4)? != 0),
+            Tag::Int => ZonValue::Int(self.read_u64(offset + 8)? as i64),
+            Tag::UInt => ZonValue::UInt(self.read_u64(offset + 8)?),
+            Tag::Float => ZonValue::Float(f64::from_bits(self.read_u64(offset + 8)?)),
+            Tag::String => ZonValue::String(self.read_string(offset + 4)?),
+            Tag::Binary => ZonValue::Binary(self.read_bytes(offset + 4)?),
+            Tag::Object => {
+                let len = self.read_u32(offset + 4)?;
+                self.check_node(offset)?;
+                ZonValue::Object(ZonObject { reader: *self, offset, len })
+            }
+            tag => {
+                let len = self.read_u32(offset + 4)?;
+                self.check_node(offset)?;
+                ZonValue::Array(ZonArray { reader: *self, offset, len, tag })
+            }
+        };
+        Ok(value)
+    }
+
+    /// number of bytes the node at `offset` occupies, including padding.
+    /// for arrays and objects this is the element table only, not the children;
+    /// typed arrays include their inline elements.
+    pub fn node_size(&self, offset: u32) -> Result<u32, &'static str> {
+        node_size(offset, |at| self.read_u32(at))
+    }
+
+    /// decodes the node the header root points to.
+    pub fn root_value(&self) -> Result<ZonValue<'a>, &'static str> {
+        self.value(self.root()?)
+    }
+
+    // rejects containers that run past the buffer, so a corrupt count
+    // fails up front instead of on every element access.
+    fn check_node(&self, offset: u32) -> Result<(), &'static str> {
+        let end = offset as u64 + self.node_size(offset)? as u64;
+        if end > self.len() as u64 {
+            return Err("Read out of bounds");
+        }
+        Ok(())
+    }
+}
+
+/// decodes a tag word.
+pub(crate) fn tag_of(word: u32) -> Result<Tag, &'static str> {
+    if word & 0xFFFF_FF00 != TAG_MARKER {
+        return Err("Invalid value tag");
+    }
+    Tag::from_u8(word as u8).ok_or("Invalid value tag")
+}
+
+/// `ZonReader::node_size` over any source of u32 words, for readers that
+/// do not hold the whole buffer.
+pub(crate) fn node_size(offset: u32, read_u32: impl Fn(u32) -> Result<u32, &'static str>) -> Result<u32, &'static str> {
+    let size: u64 = match tag_of(read_u32(offset)?)? {
+        Tag::Null => 4,
+        Tag::Bool => 8,
+        Tag::Int | Tag::UInt | Tag::Float => 16,
+        Tag::String | Tag::Binary => 8 + (read_u32(offset + 4)? as u64).div_ceil(4) * 4,
+        Tag::Array => 8 + read_u32(offset + 4)? as u64 * 4,
+        Tag::Object => 8 + read_u32(offset + 4)? as u64 * 8,
+        Tag::IntArray | Tag::FloatArray => 8 + read_u32(offset + 4)? as u64 * 8,
+        Tag::BoolArray => 8 + (read_u32(offset + 4)? as u64).div_ceil(4) * 4,
+        Tag::StringArray => {
+            let len = read_u32(offset + 4)? as u64;
+            let table = 4 * (len + 1);
+            let last = u32::try_from(offset as u64 + 4 + table).map_err(|_| "Read out of bounds")?;
+            let bytes = read_u32(last)?;
+            8 + table + (bytes as u64).div_ceil(4) * 4
+        }
+    };
+    u32::try_from(size).map_err(|_| "Node size out of range")
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::{ZonReader, ZonWriter};
+    use super::*;
+
+    #[test]
+    fn test_scalar_roundtrip() {
+        let mut writer = ZonWriter::new();
+        let null = writer.write_null();
+        let flag = writer.write_bool(true);
+        let int = writer.write_int(-42);
+        let uint = writer.write_uint(u64::MAX);
+        let float = writer.write_float(1.5);
+        let text = writer.write_text("Hero");
+        let blob = writer.write_binary(&[1, 2, 3]);
+
+        let reader = ZonReader::new(writer.as_bytes()).unwrap();
+        assert!(matches!(reader.value(null).unwrap(), ZonValue::Null));
+        assert_eq!(reader.value(flag).unwrap().as_bool(), Some(true));
+        assert_eq!(reader.value(int).unwrap().as_i64(), Some(-42));
+        assert_eq!(reader.value(uint).unwrap().as_u64(), Some(u64::MAX));
+        assert_eq!(reader.value(float).unwrap().as_f64(), Some(1.5));
+        assert_eq!(reader.value(text).unwrap().as_str(), Some("Hero"));
+        assert_eq!(reader.value(blob).unwrap().as_binary(), Some(&[1u8, 2, 3][..]));
+
+        assert_eq!(reader.node_size(null), Ok(4));
+        assert_eq!(reader.node_size(int), Ok(16));
+        assert_eq!(reader.node_size(text), Ok(12));
+        assert_eq!(reader.node_size(blob), Ok(12));
+
+        // 8-byte payloads are aligned so they can be mapped directly
+        assert_eq!((int + 8) % 8, 0);
+        assert_eq!((float + 8) % 8, 0);
+    }
+
+    #[test]
+    fn test_nested_roundtrip() {
+        let mut writer = ZonWriter::new();
+        let name = writer.write_text("Hero");
+        let hp = writer.write_uint(100);
+        let player = writer.write_object(&[("name", name), ("hp", hp)]);
+        let players = writer.write_array(&[player, player]);
+        let root = writer.write_object(&[("players", players)]);
+        writer.set_root(root);
+
+        let reader = ZonReader::new(writer.as_bytes()).unwrap();
+        let root = reader.root_value().unwrap().as_object().unwrap();
+        let players = root.get("players").unwrap().unwrap().as_array().unwrap();
+        assert_eq!(players.len(), 2);
+
+        let player = players.get(1).unwrap().as_object().unwrap();
+        assert_eq!(player.get("name").unwrap().unwrap().as_str(), Some("Hero"));
+        assert_eq!(player.get("hp").unwrap().unwrap().as_u64(), Some(100));
+        assert!(player.get("mana").unwrap().is_none());
+
+        let keys: Vec<&str> = player.iter().map(|f| f.unwrap().0).collect();
+        assert_eq!(keys, ["name", "hp"]);
+    }
+
+    #[test]
+    fn test_typed_arrays() {
+        let mut writer = ZonWriter::new();
+        let flags = writer.write_bool_array(&[true, false, true]);
+        let ints = writer.write_int_array(&[-1, 2]);
+        let names = writer.write_string_array(&["ab", "", "c"]);
+
+        let reader = ZonReader::new(writer.as_bytes()).unwrap();
+        let ints = reader.value(ints).unwrap().as_array().unwrap();
+        assert_eq!(ints.tag(), Tag::IntArray);
+        assert_eq!(ints.element_tag(), Some(Tag::Int));
+        assert_eq!(ints.as_slice::<i64>().unwrap(), &[-1, 2]);
+        assert_eq!(ints.get(0).unwrap().as_i64(), Some(-1));
+        assert_eq!((ints.offset() + 8) % 8, 0);
+        assert!(ints.offset_at(0).is_err());
+        assert!(ints.get(2).is_err());
+
+        let names = reader.value(names).unwrap().as_array().unwrap();
+        let names: Vec<&str> = names.iter().map(|v| v.unwrap().as_str().unwrap()).collect();
+        assert_eq!(names, ["ab", "", "c"]);
+        assert_eq!(reader.value(flags).unwrap().type_name(), "bool[]");
+        assert_eq!(reader.value(flags).unwrap().as_array().unwrap().get(2).unwrap().as_bool(), Some(true));
+
+        assert_eq!(reader.node_size(flags), Ok(12));
+        assert_eq!(reader.node_size(ints.offset()), Ok(24));
+    }
+
+    #[test]
+    fn test_invalid_nodes() {
+        let mut writer = ZonWriter::new();
+        let raw = writer.write_u32(0xDEAD);
+        let bogus = writer.write_u32(Tag::Array.word());
+        writer.write_u32(1_000_000);
+
+        let reader = ZonReader::new(writer.as_bytes()).unwrap();
+        assert_eq!(reader.value(raw).unwrap_err(), "Invalid value tag");
+        assert_eq!(reader.value(bogus).unwrap_err(), "Read out of bounds");
+        assert!(reader.value(4096).is_err());
+    }
+}
@@ -1,29 +1,98 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{self, Seek, SeekFrom, Write};
+
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
 use crate::header::ZonHeader;
+use crate::query::{PlainStep, Query};
+use crate::reader::ZonReader;
+use crate::value::{Tag, ZonArray, ZonValue, MAX_DEPTH};
 
 pub struct ZonWriter {
     pub(crate) buffer: Vec<u8>,
+    // length of the buffer `append` started from
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    base: usize,
 }
 
 impl ZonWriter {
     pub fn new() -> Self {
+        Self::with_capacity(4096)
+    }
+
+    /// a writer whose buffer holds `capacity` bytes, header included,
+    /// before it reallocates.
+    pub fn with_capacity(capacity: usize) -> Self {
         // start with a default header
         let mut writer = Self {
-            buffer: Vec::with_capacity(4096),
+            buffer: Vec::with_capacity(capacity),
+            base: 0,
         };
-        
-        // write the header immediately
+        writer.write_header();
+        writer
+    }
+
+    /// starts over with a fresh header, keeping the allocated capacity, so
+    /// one writer can serialize message after message without allocating.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.base = 0;
+        self.write_header();
+    }
+
+    /// the same as `reset`.
+    pub fn clear(&mut self) {
+        self.reset();
+    }
+
+    /// the bytes allocated for the buffer.
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// the finished buffer, without copying it.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    /// sets the root and returns the finished buffer, without copying it.
+    pub fn finish(mut self, root: u32) -> Vec<u8> {
+        self.set_root(root);
+        self.buffer
+    }
+
+    fn write_header(&mut self) {
         let header = ZonHeader::default();
         
         // safety: ZonHeader is POD and repr(C).
         let header_slice = unsafe {
-            std::slice::from_raw_parts(
+            core::slice::from_raw_parts(
                 &header as *const ZonHeader as *const u8,
-                std::mem::size_of::<ZonHeader>(),
+                core::mem::size_of::<ZonHeader>(),
             )
         };
         
-        writer.buffer.extend_from_slice(header_slice);
-        writer
+        self.buffer.extend_from_slice(header_slice);
+    }
+
+    /// continues writing after the contents of an existing buffer. nodes
+    /// already in it stay where they are and can be referenced by new ones;
+    /// see `write_update` for copy-on-write edits. the root only changes
+    /// once `set_root` is called. signed buffers are refused, since new
+    /// nodes would land after the signature and break it.
+    pub fn append(buffer: Vec<u8>) -> Result<Self, &'static str> {
+        ZonReader::new(&buffer)?;
+        if crate::sign::is_signed(&buffer) {
+            return Err("Buffer is signed, append before signing");
+        }
+        let base = buffer.len();
+        let mut writer = Self { buffer, base };
+        writer.pad_to(4);
+        Ok(writer)
     }
 
     pub fn len(&self) -> usize {
@@ -70,6 +139,273 @@ impl ZonWriter {
         start_offset
     }
 
+    /// appends zero bytes until the buffer length is a multiple of `align`.
+    pub fn pad_to(&mut self, align: usize) {
+        let padding_needed = (align - (self.buffer.len() % align)) % align;
+        self.buffer.resize(self.buffer.len() + padding_needed, 0);
+    }
+
+    /// writes a tagged null node.
+    pub fn write_null(&mut self) -> u32 {
+        self.write_u32(Tag::Null.word())
+    }
+
+    /// writes a tagged bool node.
+    pub fn write_bool(&mut self, val: bool) -> u32 {
+        let offset = self.write_u32(Tag::Bool.word());
+        self.write_u32(val as u32);
+        offset
+    }
+
+    /// writes a tagged signed integer node.
+    pub fn write_int(&mut self, val: i64) -> u32 {
+        self.write_wide(Tag::Int, val.to_le_bytes())
+    }
+
+    /// writes a tagged unsigned integer node.
+    pub fn write_uint(&mut self, val: u64) -> u32 {
+        self.write_wide(Tag::UInt, val.to_le_bytes())
+    }
+
+    /// writes a tagged f64 node.
+    pub fn write_float(&mut self, val: f64) -> u32 {
+        self.write_wide(Tag::Float, val.to_le_bytes())
+    }
+
+    /// writes a tagged string node.
+    /// unlike `write_string`, the node carries its type and can be decoded with `ZonReader::value`.
+    pub fn write_text(&mut self, val: &str) -> u32 {
+        let offset = self.write_u32(Tag::String.word());
+        self.write_string(val);
+        offset
+    }
+
+    /// writes a tagged binary blob node, laid out like a string.
+    pub fn write_binary(&mut self, val: &[u8]) -> u32 {
+        let offset = self.write_u32(Tag::Binary.word());
+        self.write_u32(val.len() as u32);
+        self.buffer.extend_from_slice(val);
+        self.pad_to(4);
+        offset
+    }
+
+    /// writes an array node pointing at already written nodes.
+    pub fn write_array(&mut self, items: &[u32]) -> u32 {
+        let offset = self.write_u32(Tag::Array.word());
+        self.write_u32(items.len() as u32);
+        for &item in items {
+            self.write_u32(item);
+        }
+        offset
+    }
+
+    /// writes an object node pointing at already written nodes.
+    /// the keys are written first as raw strings, then the field table.
+    pub fn write_object(&mut self, fields: &[(&str, u32)]) -> u32 {
+        let mut key = self.buffer.len() as u32;
+        for (name, _) in fields {
+            self.write_string(name);
+        }
+        let offset = self.write_u32(Tag::Object.word());
+        self.write_u32(fields.len() as u32);
+        // the keys sit back to back, so their offsets are recomputed
+        // instead of collected
+        for (name, value) in fields {
+            self.write_u32(key);
+            self.write_u32(*value);
+            key = (key + 4 + name.len() as u32).next_multiple_of(4);
+        }
+        offset
+    }
+
+    /// writes an object node from `(key offset, value offset)` pairs,
+    /// where the keys were already written with `write_string`.
+    pub fn write_object_offsets(&mut self, fields: &[(u32, u32)]) -> u32 {
+        let offset = self.write_u32(Tag::Object.word());
+        self.write_u32(fields.len() as u32);
+        for &(key, value) in fields {
+            self.write_u32(key);
+            self.write_u32(value);
+        }
+        offset
+    }
+
+    /// writes a typed array of i64 values, stored inline and 8-byte aligned
+    /// so readers can borrow them as `&[i64]`.
+    pub fn write_int_array(&mut self, values: &[i64]) -> u32 {
+        self.pad_to(8);
+        let offset = self.write_u32(Tag::IntArray.word());
+        self.write_u32(values.len() as u32);
+        for v in values {
+            self.buffer.extend_from_slice(&v.to_le_bytes());
+        }
+        offset
+    }
+
+    /// writes a typed array of f64 values, stored inline and 8-byte aligned
+    /// so readers can borrow them as `&[f64]`.
+    pub fn write_float_array(&mut self, values: &[f64]) -> u32 {
+        self.pad_to(8);
+        let offset = self.write_u32(Tag::FloatArray.word());
+        self.write_u32(values.len() as u32);
+        for v in values {
+            self.buffer.extend_from_slice(&v.to_le_bytes());
+        }
+        offset
+    }
+
+    /// writes a typed array of bools, one byte each.
+    pub fn write_bool_array(&mut self, values: &[bool]) -> u32 {
+        let offset = self.write_u32(Tag::BoolArray.word());
+        self.write_u32(values.len() as u32);
+        self.buffer.extend(values.iter().map(|&v| v as u8));
+        self.pad_to(4);
+        offset
+    }
+
+    /// writes a typed array of strings: an end offset table followed by
+    /// all the string bytes back to back.
+    pub fn write_string_array<S: AsRef<str>>(&mut self, values: &[S]) -> u32 {
+        let offset = self.write_u32(Tag::StringArray.word());
+        self.write_u32(values.len() as u32);
+        let mut end = 0;
+        self.write_u32(end);
+        for v in values {
+            end += v.as_ref().len() as u32;
+            self.write_u32(end);
+        }
+        for v in values {
+            self.buffer.extend_from_slice(v.as_ref().as_bytes());
+        }
+        self.pad_to(4);
+        offset
+    }
+
+    /// copies `value` and everything below it, typically from another
+    /// buffer. typed arrays stay typed. returns the offset of the copy.
+    pub fn write_value(&mut self, value: ZonValue) -> Result<u32, &'static str> {
+        self.copy_value(value, 0)
+    }
+
+    fn copy_value(&mut self, value: ZonValue, depth: usize) -> Result<u32, &'static str> {
+        if depth > MAX_DEPTH {
+            return Err("Maximum nesting depth exceeded");
+        }
+        Ok(match value {
+            ZonValue::Null => self.write_null(),
+            ZonValue::Bool(b) => self.write_bool(b),
+            ZonValue::Int(v) => self.write_int(v),
+            ZonValue::UInt(v) => self.write_uint(v),
+            ZonValue::Float(v) => self.write_float(v),
+            ZonValue::String(s) => self.write_text(s),
+            ZonValue::Binary(b) => self.write_binary(b),
+            ZonValue::Array(array) => match array.tag() {
+                Tag::IntArray => self.write_int_array(&elements(array, |v| v.as_i64())?),
+                Tag::FloatArray => self.write_float_array(&elements(array, |v| v.as_f64())?),
+                Tag::BoolArray => self.write_bool_array(&elements(array, |v| v.as_bool())?),
+                Tag::StringArray => self.write_string_array(&elements(array, |v| v.as_str())?),
+                _ => {
+                    let items = array
+                        .iter()
+                        .map(|item| self.copy_value(item?, depth + 1))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    self.write_array(&items)
+                }
+            },
+            ZonValue::Object(object) => {
+                let mut fields = Vec::with_capacity(object.len());
+                for field in object.iter() {
+                    let (key, value) = field?;
+                    fields.push((key, self.copy_value(value, depth + 1)?));
+                }
+                self.write_object(&fields)
+            }
+        })
+    }
+
+    /// copy-on-write update: writes new copies of the containers on the way
+    /// from the container at `node` down to `path` (e.g. `players[0].score`),
+    /// with the value at `path` replaced by the node at `value`. everything
+    /// else is shared with the old version. a missing last key adds the field.
+    /// returns the offset of the new copy of `node`.
+    pub fn write_update(&mut self, node: u32, path: &str, value: u32) -> Result<u32, &'static str> {
+        let query = Query::parse(path).map_err(|_| "Invalid path")?;
+        self.update(node, &query.plain_steps().ok_or("Invalid path")?, value)
+    }
+
+    fn update(&mut self, node: u32, path: &[PlainStep], value: u32) -> Result<u32, &'static str> {
+        let Some((segment, rest)) = path.split_first() else {
+            return Ok(value);
+        };
+        if path.len() > MAX_DEPTH {
+            return Err("Maximum nesting depth exceeded");
+        }
+        let reader = ZonReader::new(&self.buffer)?;
+        match (segment, reader.value(node)?) {
+            (PlainStep::Field(key), ZonValue::Object(object)) => {
+                let mut fields = Vec::with_capacity(object.len() + 1);
+                let mut found = None;
+                for i in 0..object.len() {
+                    let slot = object.offset() + 8 + i as u32 * 8;
+                    if object.entry(i)?.0 == *key {
+                        found = Some(i);
+                    }
+                    fields.push((reader.read_u32(slot)?, reader.read_u32(slot + 4)?));
+                }
+                match found {
+                    Some(i) => fields[i].1 = self.update(fields[i].1, rest, value)?,
+                    None if rest.is_empty() => fields.push((self.write_string(key), value)),
+                    None => return Err("Path not found"),
+                }
+                Ok(self.write_object_offsets(&fields))
+            }
+            (PlainStep::Index(index), ZonValue::Array(array)) => {
+                let mut items = (0..array.len()).map(|i| array.offset_at(i)).collect::<Result<Vec<_>, _>>()?;
+                let i = if *index < 0 { items.len() as i64 + index } else { *index };
+                let item = items.get_mut(usize::try_from(i).map_err(|_| "Path not found")?).ok_or("Path not found")?;
+                *item = self.update(*item, rest, value)?;
+                Ok(self.write_array(&items))
+            }
+            _ => Err("Path not found"),
+        }
+    }
+
+    /// writes what `append` added to `file`, which must hold the buffer
+    /// `append` started from: first the new nodes, then, once they are on
+    /// disk, the new root. a crash in between leaves the old root in place,
+    /// and readers that open the file see either version in full.
+    #[cfg(feature = "std")]
+    pub fn append_to(&self, file: &mut File) -> io::Result<()> {
+        file.seek(SeekFrom::Start(self.base as u64))?;
+        file.write_all(&self.buffer[self.base..])?;
+        file.sync_data()?;
+        file.seek(SeekFrom::Start(8))?;
+        file.write_all(&self.buffer[8..12])?;
+        file.sync_data()
+    }
+
+    /// writes the buffer to `writer`, e.g. a `tokio::fs::File` or a socket,
+    /// without blocking the executor, and flushes it. read it back with
+    /// `ZonBuf::read_from`.
+    #[cfg(feature = "tokio")]
+    pub async fn write_to<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        writer.write_all(&self.buffer).await?;
+        writer.flush().await
+    }
+
+    // 8-byte payloads sit at offset + 8 of an 8-byte aligned node,
+    // so they never straddle a cache line.
+    fn write_wide(&mut self, tag: Tag, bytes: [u8; 8]) -> u32 {
+        self.pad_to(8);
+        let offset = self.write_u32(tag.word());
+        self.write_u32(0);
+        self.buffer.extend_from_slice(&bytes);
+        offset
+    }
+
     /// updates the root offset in the header.
     /// the header is always at the start of the buffer.
     pub fn set_root(&mut self, offset: u32) {
@@ -84,6 +420,11 @@ impl ZonWriter {
     }
 }
 
+// the elements of a typed array as plain values
+fn elements<'a, T>(array: ZonArray<'a>, f: impl Fn(ZonValue<'a>) -> Option<T>) -> Result<Vec<T>, &'static str> {
+    array.iter().map(|item| f(item?).ok_or("Typed array element type mismatch")).collect()
+}
+
 impl Default for ZonWriter {
     fn default() -> Self {
         Self::new()
@@ -162,4 +503,119 @@ mod tests {
             assert_eq!(u32::from_le_bytes(root_slice.try_into().unwrap()), str_offset);
         }
     }
+
+    #[test]
+    fn test_write_value() {
+        let mut source = ZonWriter::new();
+        let scores = source.write_float_array(&[1.5, -2.0]);
+        let tags = source.write_string_array(&["a", "bc"]);
+        let blob = source.write_binary(&[7; 5]);
+        let nested = source.write_array(&[scores, blob]);
+        let root = source.write_object(&[("nested", nested), ("tags", tags)]);
+        source.set_root(root);
+        let source = crate::ZonReader::new(source.as_bytes()).unwrap().root_value().unwrap();
+
+        // an odd offset first, so the float array has to be realigned
+        let mut writer = ZonWriter::new();
+        writer.write_bool(true);
+        let root = writer.write_value(source).unwrap();
+        writer.set_root(root);
+
+        let reader = crate::ZonReader::new(writer.as_bytes()).unwrap();
+        let copy = reader.root_value().unwrap();
+        assert!(crate::diff::diff_values(source, copy).unwrap().is_empty());
+        let nested = copy.as_object().unwrap().get("nested").unwrap().unwrap().as_array().unwrap();
+        assert_eq!(nested.get(0).unwrap().as_array().unwrap().as_slice::<f64>().unwrap(), &[1.5, -2.0]);
+    }
+
+    // { players: [{ name: "Hero", score: 10 }], scores: [1, 2] }
+    fn state() -> (Vec<u8>, u32) {
+        let mut writer = ZonWriter::new();
+        let name = writer.write_text("Hero");
+        let score = writer.write_uint(10);
+        let player = writer.write_object(&[("name", name), ("score", score)]);
+        let players = writer.write_array(&[player]);
+        let scores = writer.write_int_array(&[1, 2]);
+        let root = writer.write_object(&[("players", players), ("scores", scores)]);
+        writer.set_root(root);
+        (writer.as_bytes().to_vec(), root)
+    }
+
+    #[test]
+    fn test_append_copy_on_write() {
+        let (buffer, old_root) = state();
+        let old_len = buffer.len();
+        let mut writer = ZonWriter::append(buffer.clone()).unwrap();
+        let score = writer.write_uint(20);
+        let root = writer.write_update(old_root, "players[0].score", score).unwrap();
+        let level = writer.write_uint(3);
+        let root = writer.write_update(root, "players[-1].level", level).unwrap();
+        writer.set_root(root);
+
+        let bytes = writer.as_bytes();
+        assert_eq!(bytes[12..old_len], buffer[12..]);
+        let reader = ZonReader::new(bytes).unwrap();
+        assert_eq!(reader.query("players[0].score").unwrap()[0].as_u64(), Some(20));
+        assert_eq!(reader.query("players[0].level").unwrap()[0].as_u64(), Some(3));
+        assert_eq!(reader.query("players[0].name").unwrap()[0].as_str(), Some("Hero"));
+
+        // the old version is still there, and unchanged nodes are shared
+        let (old, new) = (reader.value(old_root).unwrap(), reader.root_value().unwrap());
+        let query = crate::Query::parse("players[0].score").unwrap();
+        assert_eq!(query.first(old).unwrap().unwrap().as_u64(), Some(10));
+        let field = |value: ZonValue, key| value.as_object().unwrap().offset_of(key).unwrap();
+        assert_ne!(field(old, "players"), field(new, "players"));
+        assert_eq!(field(old, "scores"), field(new, "scores"));
+
+        assert_eq!(writer.write_update(root, "players[1].score", score), Err("Path not found"));
+        assert_eq!(writer.write_update(root, "players[*].score", score), Err("Invalid path"));
+        assert_eq!(writer.write_update(root, "scores[0]", score), Err("Typed array elements are stored inline"));
+        assert!(ZonWriter::append(vec![0; 64]).is_err());
+
+        // a signed buffer: the flag set and a trailer after the data
+        let mut signed = buffer.clone();
+        signed[12] |= crate::header::FLAG_SIGNED as u8;
+        signed.extend_from_slice(&[0; crate::sign::SIGNATURE_SIZE]);
+        assert_eq!(ZonWriter::append(signed).err(), Some("Buffer is signed, append before signing"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_append_to_file() {
+        let (buffer, old_root) = state();
+        let path = std::env::temp_dir().join(format!("zon-append-test-{}.zon", std::process::id()));
+        std::fs::write(&path, &buffer).unwrap();
+
+        let mut writer = ZonWriter::append(std::fs::read(&path).unwrap()).unwrap();
+        let score = writer.write_uint(99);
+        let root = writer.write_update(old_root, "players[0].score", score).unwrap();
+        writer.set_root(root);
+        let mut file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        writer.append_to(&mut file).unwrap();
+        drop(file);
+
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(contents, writer.as_bytes());
+        let reader = ZonReader::new(&contents).unwrap();
+        assert_eq!(reader.query("players[0].score").unwrap()[0].as_u64(), Some(99));
+    }
+
+    #[test]
+    fn test_reset_and_finish() {
+        let mut writer = ZonWriter::with_capacity(256);
+        let first = writer.write_text("first");
+        writer.set_root(first);
+        let (ptr, capacity) = (writer.as_bytes().as_ptr(), writer.capacity());
+
+        writer.reset();
+        assert_eq!(writer.as_bytes(), ZonWriter::new().as_bytes());
+        assert_eq!((writer.as_bytes().as_ptr(), writer.capacity()), (ptr, capacity));
+
+        let second = writer.write_text("second");
+        let bytes = writer.finish(second);
+        assert_eq!(bytes.as_ptr(), ptr);
+        let reader = ZonReader::new(&bytes).unwrap();
+        assert_eq!(reader.root_value().unwrap().as_str(), Some("second"));
+    }
 }
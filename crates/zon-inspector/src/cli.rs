@@ -0,0 +1,266 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  success
+  1  check failed (schema mismatches, files differ, no query matches, bad signature)
+  2  invalid command line
+  3  I/O error
+  4  invalid input data";
+
+/// inspect, validate and convert ZON files.
+#[derive(Debug, Parser)]
+#[command(name = "zon-inspector", version, after_help = EXIT_CODES)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// file to show as a tree when no command is given (`-` for stdin)
+    pub file: Option<PathBuf>,
+
+    /// output format
+    #[arg(long, short, value_enum, global = true, default_value_t = Format::Text)]
+    pub format: Format,
+
+    /// colour text output
+    #[arg(long, value_enum, global = true, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// key for encrypted files: 32 raw bytes, or 64 hex digits
+    #[arg(long, global = true)]
+    pub key_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// only when writing to a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// show the header and basic facts about a file
+    Info(FileArgs),
+    /// print every value with its path
+    Dump(FileArgs),
+    /// show the value tree with offsets, sizes and pointers
+    Tree(TreeArgs),
+    /// hexdump the raw bytes with layout annotations
+    Hex(HexArgs),
+    /// check a file against a `.zons` schema
+    Validate(ValidateArgs),
+    /// convert between ZON, JSON, MessagePack, CBOR and Arrow
+    Convert(ConvertArgs),
+    /// count nodes and bytes by type
+    Stats(FileArgs),
+    /// list the values that differ between two files
+    Diff(DiffArgs),
+    /// print the values matching a path such as `players[?score > 1000].name`
+    Query(QueryArgs),
+    /// drop unreachable data left behind by appends and updates
+    Compact(CompactArgs),
+    /// sign a file with an Ed25519 key
+    Sign(SignArgs),
+    /// check a file's signature against an Ed25519 public key
+    Verify(VerifyArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct FileArgs {
+    /// input file (`-` for stdin)
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct TreeArgs {
+    /// input file (`-` for stdin)
+    pub file: PathBuf,
+
+    /// stop expanding containers below this depth
+    #[arg(long, short)]
+    pub depth: Option<usize>,
+}
+
+#[derive(Debug, Args)]
+pub struct HexArgs {
+    /// input file (`-` for stdin)
+    pub file: PathBuf,
+
+    /// only show bytes in `START..END` (end exclusive), e.g. `0x40..0x80`
+    #[arg(long, short, value_parser = parse_range)]
+    pub range: Option<(usize, usize)>,
+
+    /// plain 16-bytes-per-row dump without annotations
+    #[arg(long)]
+    pub plain: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ValidateArgs {
+    /// schema file
+    #[arg(long, short)]
+    pub schema: PathBuf,
+
+    /// input file (`-` for stdin)
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct ConvertArgs {
+    /// input file (`-` for stdin)
+    pub input: PathBuf,
+
+    /// output file (`-` for stdout)
+    pub output: PathBuf,
+
+    /// input format. defaults to the input file extension
+    #[arg(long, value_enum)]
+    pub from: Option<DataFormat>,
+
+    /// output format. defaults to the output file extension
+    #[arg(long, value_enum)]
+    pub to: Option<DataFormat>,
+
+    /// compress ZON output. compressed input is always decompressed
+    #[arg(long, value_enum)]
+    pub compress: Option<Compression>,
+
+    /// compress in independently readable chunks of this many bytes (a
+    /// multiple of 64), so readers can decompress only what they touch
+    #[arg(long, requires = "compress")]
+    pub chunk_size: Option<usize>,
+
+    /// encrypt ZON output with the key from `--key-file`, after compressing
+    #[arg(long, value_enum)]
+    pub encrypt: Option<Encryption>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    /// fast, with a moderate ratio
+    Lz4,
+    /// smaller files, slower to write
+    Zstd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DataFormat {
+    Zon,
+    Json,
+    Msgpack,
+    Cbor,
+    /// Arrow IPC, file or stream format. ZON files must hold a columnar table
+    Arrow,
+    /// comma separated values (input only), imported as a columnar table
+    Csv,
+    /// tab separated values (input only), imported as a columnar table
+    Tsv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Encryption {
+    /// ChaCha20-Poly1305, fast without AES hardware
+    Chacha20,
+    /// AES-256-GCM
+    Aes,
+}
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// old file (`-` for stdin)
+    pub old: PathBuf,
+
+    /// new file
+    pub new: PathBuf,
+
+    /// compare the raw bytes instead and report the first differing offset
+    #[arg(long)]
+    pub bytes: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct QueryArgs {
+    /// input file (`-` for stdin)
+    pub file: PathBuf,
+
+    /// query, e.g. `meta.version`, `players[*].name` or `players[?score > 1000]`
+    pub path: String,
+}
+
+#[derive(Debug, Args)]
+pub struct CompactArgs {
+    /// input file (`-` for stdin)
+    pub input: PathBuf,
+
+    /// output file (`-` for stdout). without it, only reports what would be reclaimed
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct SignArgs {
+    /// input file (`-` for stdin), plain, compressed or encrypted
+    pub input: PathBuf,
+
+    /// output file (`-` for stdout)
+    pub output: PathBuf,
+
+    /// Ed25519 secret key: 32 raw bytes, or 64 hex digits
+    #[arg(long)]
+    pub key: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    /// input file (`-` for stdin)
+    pub file: PathBuf,
+
+    /// Ed25519 public key: 32 raw bytes, or 64 hex digits
+    #[arg(long)]
+    pub public_key: PathBuf,
+}
+
+/// parses `START..END`, where either bound may be omitted and
+/// numbers are decimal or `0x` hex.
+fn parse_range(s: &str) -> Result<(usize, usize), String> {
+    let (start, end) = s.split_once("..").ok_or("expected START..END")?;
+    let start = if start.is_empty() { 0 } else { parse_number(start)? };
+    let end = if end.is_empty() { usize::MAX } else { parse_number(end)? };
+    if start > end {
+        return Err(format!("range start 0x{:x} is after its end 0x{:x}", start, end));
+    }
+    Ok((start, end))
+}
+
+fn parse_number(s: &str) -> Result<usize, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| format!("invalid number `{}`", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("0x40..0x80"), Ok((0x40, 0x80)));
+        assert_eq!(parse_range("64..128"), Ok((64, 128)));
+        assert_eq!(parse_range("0x40.."), Ok((0x40, usize::MAX)));
+        assert_eq!(parse_range("..16"), Ok((0, 16)));
+        assert!(parse_range("0x80..0x40").is_err());
+        assert!(parse_range("0x40").is_err());
+        assert!(parse_range("zz..1").is_err());
+    }
+}
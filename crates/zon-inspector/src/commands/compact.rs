@@ -0,0 +1,47 @@
+use std::io::Write;
+
+use zon_lib::ZonReader;
+
+use crate::cli::{CompactArgs, Format};
+use crate::commands::human_size;
+use crate::error::Error;
+use crate::input;
+
+/// copies the reachable values into a fresh file and reports the bytes
+/// reclaimed. without an output file, only reports.
+pub fn run(args: &CompactArgs, key: Option<&[u8; 32]>, format: Format, out: &mut dyn Write) -> Result<(), Error> {
+    let buffer = input::read_zon(&args.input, key)?;
+    let reader = ZonReader::new(&buffer)?;
+    let compacted = zon_lib::compact(&reader)?;
+    let reclaimed = buffer.len().saturating_sub(compacted.len());
+
+    let to_stdout = args.output.as_deref().is_some_and(input::is_stdio);
+    if let Some(output) = &args.output {
+        input::write(output, &compacted)?;
+    }
+
+    let report = match format {
+        Format::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "file": input::display_name(&args.input),
+            "size": buffer.len(),
+            "compacted_size": compacted.len(),
+            "reclaimed": reclaimed,
+        }))
+        .unwrap_or_else(|_| "{}".to_string()),
+        Format::Text => format!(
+            "{}: {} -> {}, reclaimed {} ({:.1}%)",
+            input::display_name(&args.input),
+            human_size(buffer.len()),
+            human_size(compacted.len()),
+            human_size(reclaimed),
+            reclaimed as f64 * 100.0 / buffer.len() as f64,
+        ),
+    };
+    // the compacted bytes own stdout when written there
+    if to_stdout {
+        eprintln!("{}", report);
+        Ok(())
+    } else {
+        writeln!(out, "{}", report).map_err(Error::stdout)
+    }
+}
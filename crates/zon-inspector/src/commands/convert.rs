@@ -0,0 +1,111 @@
+use std::io::Write;
+use std::path::Path;
+
+use zon_lib::{Cipher, Codec, ZonReader};
+
+use crate::cli::{Compression, ConvertArgs, DataFormat, Encryption, Format};
+use crate::error::Error;
+use crate::input;
+
+/// converts between ZON, JSON, MessagePack, CBOR and Arrow IPC, going
+/// through ZON when neither side is ZON. CSV and TSV are imported as
+/// columnar tables. formats come from `--from`/`--to` or the file
+/// extensions; with neither, ZON converts to JSON and anything else to ZON.
+/// compressed or encrypted ZON input is restored; `--compress` compresses ZON
+/// output, in chunks with `--chunk-size`, and `--encrypt` encrypts it.
+pub fn run(args: &ConvertArgs, key: Option<&[u8; 32]>, _format: Format, _out: &mut dyn Write) -> Result<(), Error> {
+    let from = args.from.or_else(|| detect(&args.input)).unwrap_or(DataFormat::Zon);
+    let to = args.to.or_else(|| detect(&args.output)).unwrap_or(match from {
+        DataFormat::Zon => DataFormat::Json,
+        _ => DataFormat::Zon,
+    });
+
+    if matches!(to, DataFormat::Csv | DataFormat::Tsv) {
+        return Err(Error::Invalid("CSV and TSV are only supported as input formats".to_string()));
+    }
+    if (args.compress.is_some() || args.encrypt.is_some()) && to != DataFormat::Zon {
+        return Err(Error::Usage("--compress and --encrypt only apply to ZON output".to_string()));
+    }
+    let encryption_key = match args.encrypt {
+        Some(_) => Some(key.ok_or_else(|| Error::Usage("--encrypt needs --key-file".to_string()))?),
+        None => None,
+    };
+    if args.chunk_size.is_some_and(|size| size == 0 || !size.is_multiple_of(64)) {
+        return Err(Error::Usage("--chunk-size must be a non-zero multiple of 64".to_string()));
+    }
+
+    let path = &args.input;
+    let buffer = match from {
+        DataFormat::Zon => input::read_zon(path, key)?,
+        // parsed straight into the writer, so large arrays never exist as a JSON tree
+        DataFormat::Json => zon_lib::json::from_reader(input::open(path)?).map_err(|e| json_error(path, e))?,
+        DataFormat::Msgpack => {
+            zon_lib::msgpack::encode(&input::read(path)?).map_err(|e| invalid("MessagePack", e))?
+        }
+        DataFormat::Cbor => zon_lib::cbor::encode(&input::read(path)?).map_err(|e| invalid("CBOR", e))?,
+        DataFormat::Arrow => zon_lib::arrow::encode(&input::read(path)?).map_err(|e| invalid("Arrow", e))?,
+        DataFormat::Csv => zon_lib::csv::from_reader(input::open(path)?, b',').map_err(|e| invalid("CSV", e))?,
+        DataFormat::Tsv => zon_lib::csv::from_reader(input::open(path)?, b'\t').map_err(|e| invalid("TSV", e))?,
+    };
+
+    let data = match to {
+        DataFormat::Zon => match args.compress {
+            Some(compression) => {
+                let codec = match compression {
+                    Compression::Lz4 => Codec::Lz4,
+                    Compression::Zstd => Codec::Zstd,
+                };
+                match args.chunk_size {
+                    Some(size) => zon_lib::compress_chunked(&buffer, codec, size)?,
+                    None => zon_lib::compress(&buffer, codec)?,
+                }
+            }
+            None => buffer,
+        },
+        format => {
+            let reader = ZonReader::new(&buffer)?;
+            match format {
+                DataFormat::Msgpack => zon_lib::msgpack::decode(&reader).map_err(|e| invalid("ZON", e))?,
+                DataFormat::Cbor => zon_lib::cbor::decode(&reader).map_err(|e| invalid("ZON", e))?,
+                DataFormat::Arrow => zon_lib::arrow::decode(&reader).map_err(|e| invalid("ZON", e))?,
+                _ => {
+                    let json = zon_lib::json::decode(&reader)?;
+                    let mut text = serde_json::to_string_pretty(&json).unwrap_or_else(|_| "null".to_string());
+                    text.push('\n');
+                    text.into_bytes()
+                }
+            }
+        }
+    };
+    let data = match (args.encrypt, encryption_key) {
+        (Some(Encryption::Chacha20), Some(key)) => zon_lib::encrypt(&data, key, Cipher::ChaCha20Poly1305)?,
+        (Some(Encryption::Aes), Some(key)) => zon_lib::encrypt(&data, key, Cipher::Aes256Gcm)?,
+        _ => data,
+    };
+    input::write(&args.output, &data)
+}
+
+fn detect(path: &Path) -> Option<DataFormat> {
+    match path.extension()?.to_str()? {
+        "zon" => Some(DataFormat::Zon),
+        "json" => Some(DataFormat::Json),
+        "msgpack" | "mpk" => Some(DataFormat::Msgpack),
+        "cbor" => Some(DataFormat::Cbor),
+        "arrow" | "feather" | "ipc" | "arrows" => Some(DataFormat::Arrow),
+        "csv" => Some(DataFormat::Csv),
+        "tsv" | "tab" => Some(DataFormat::Tsv),
+        _ => None,
+    }
+}
+
+fn invalid(format: &str, e: impl std::fmt::Display) -> Error {
+    Error::Invalid(format!("Error parsing {} file: {}", format, e))
+}
+
+fn json_error(path: &Path, e: serde_json::Error) -> Error {
+    if e.is_io() {
+        Error::io(path, e.into())
+    } else {
+        invalid("JSON", e)
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::Write;
+
+use zon_lib::json::to_value;
+use zon_lib::{Change, ZonReader};
+
+use crate::cli::{DiffArgs, Format};
+use crate::commands::{display_path, value_text, write_json};
+use crate::error::Error;
+use crate::input;
+
+/// lists every added, removed and changed value. files that hold the same
+/// data in a different layout are equal. with `--bytes`, compares the raw
+/// bytes instead and reports the first difference.
+pub fn run(args: &DiffArgs, key: Option<&[u8; 32]>, format: Format, out: &mut dyn Write) -> Result<(), Error> {
+    let old = input::read_zon(&args.old, key)?;
+    let new = input::read_zon(&args.new, key)?;
+    let old_reader = ZonReader::new(&old)?;
+    let new_reader = ZonReader::new(&new)?;
+
+    if args.bytes {
+        return diff_bytes(&old, &new, format, out);
+    }
+
+    let changes = zon_lib::diff(&old_reader, &new_reader)?;
+    match format {
+        Format::Json => {
+            let changes = changes
+                .iter()
+                .map(|change| {
+                    let path = display_path(change.path());
+                    Ok(match change {
+                        Change::Added { value, .. } => {
+                            serde_json::json!({ "op": "added", "path": path, "value": to_value(*value)? })
+                        }
+                        Change::Removed { value, .. } => {
+                            serde_json::json!({ "op": "removed", "path": path, "value": to_value(*value)? })
+                        }
+                        Change::Changed { old, new, .. } => {
+                            serde_json::json!({ "op": "changed", "path": path, "old": to_value(*old)?, "new": to_value(*new)? })
+                        }
+                    })
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            write_json(out, &serde_json::json!({ "identical": changes.is_empty(), "changes": changes }))?;
+        }
+        Format::Text if changes.is_empty() => {
+            let text = if old == new { "files are identical" } else { "files hold the same data" };
+            writeln!(out, "{}", text).map_err(Error::stdout)?;
+        }
+        Format::Text => {
+            for change in &changes {
+                let line = match change {
+                    Change::Added { path, value } => format!("+ {} = {}", display_path(path), value_text(value)?),
+                    Change::Removed { path, value } => format!("- {} = {}", display_path(path), value_text(value)?),
+                    Change::Changed { path, old, new } => {
+                        format!("~ {}: {} -> {}", display_path(path), value_text(old)?, value_text(new)?)
+                    }
+                };
+                writeln!(out, "{}", line).map_err(Error::stdout)?;
+            }
+            eprintln!("{} difference(s)", changes.len());
+        }
+    }
+
+    if changes.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::CheckFailed)
+    }
+}
+
+fn diff_bytes(old: &[u8], new: &[u8], format: Format, out: &mut dyn Write) -> Result<(), Error> {
+    let first_difference = old
+        .iter()
+        .zip(new)
+        .position(|(a, b)| a != b)
+        .or_else(|| (old.len() != new.len()).then(|| old.len().min(new.len())));
+
+    match format {
+        Format::Json => write_json(
+            out,
+            &serde_json::json!({
+                "identical": first_difference.is_none(),
+                "first_difference": first_difference,
+                "old_size": old.len(),
+                "new_size": new.len(),
+            }),
+        )?,
+        Format::Text => {
+            let text = match first_difference {
+                None => "files are identical".to_string(),
+                Some(offset) => format!(
+                    "files differ at offset 0x{:08X} ({} vs {} bytes)",
+                    offset,
+                    old.len(),
+                    new.len()
+                ),
+            };
+            writeln!(out, "{}", text).map_err(Error::stdout)?;
+        }
+    }
+
+    match first_difference {
+        None => Ok(()),
+        Some(_) => Err(Error::CheckFailed),
+    }
+}
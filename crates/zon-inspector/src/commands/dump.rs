@@ -0,0 +1,54 @@
+use std::io::Write;
+
+use zon_lib::json::to_value;
+use zon_lib::value::MAX_DEPTH;
+use zon_lib::{ZonReader, ZonValue};
+
+use crate::cli::{FileArgs, Format};
+
+use crate::commands::{display_path, scalar_text, write_json};
+use crate::error::Error;
+use crate::input;
+
+/// prints every leaf as `path = value`, or the whole tree as JSON.
+pub fn run(args: &FileArgs, key: Option<&[u8; 32]>, format: Format, out: &mut dyn Write) -> Result<(), Error> {
+    let buffer = input::read_zon(&args.file, key)?;
+    let reader = ZonReader::new(&buffer)?;
+    let root = reader.root_value()?;
+
+    match format {
+        Format::Json => write_json(out, &to_value(root)?),
+        Format::Text => dump(out, &mut String::new(), root, 0),
+    }
+}
+
+fn dump(out: &mut dyn Write, path: &mut String, value: ZonValue, depth: usize) -> Result<(), Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::Invalid("Maximum nesting depth exceeded".to_string()));
+    }
+    let len = path.len();
+    match value {
+        ZonValue::Array(array) if !array.is_empty() => {
+            for (i, item) in array.iter().enumerate() {
+                path.push_str(&format!("[{}]", i));
+                dump(out, path, item?, depth + 1)?;
+                path.truncate(len);
+            }
+        }
+        ZonValue::Object(object) if !object.is_empty() => {
+            for field in object.iter() {
+                let (key, item) = field?;
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                dump(out, path, item, depth + 1)?;
+                path.truncate(len);
+            }
+        }
+        ZonValue::Array(_) => writeln!(out, "{} = []", display_path(path)).map_err(Error::stdout)?,
+        ZonValue::Object(_) => writeln!(out, "{} = {{}}", display_path(path)).map_err(Error::stdout)?,
+        scalar => writeln!(out, "{} = {}", display_path(path), scalar_text(&scalar)).map_err(Error::stdout)?,
+    }
+    Ok(())
+}
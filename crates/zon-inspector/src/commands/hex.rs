@@ -0,0 +1,364 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use zon_lib::header::ZON_MAGIC;
+use zon_lib::{Tag, ZonArray, ZonReader, ZonValue};
+
+use crate::cli::{Format, HexArgs};
+use crate::commands::{scalar_text, write_json};
+use crate::error::Error;
+use crate::input;
+use crate::style::Style;
+
+const ROW: usize = 16;
+const HEADER_SIZE: usize = 64;
+
+/// a labelled byte range of the file.
+#[derive(Debug, Clone, PartialEq)]
+struct Span {
+    start: usize,
+    len: usize,
+    label: String,
+    kind: Kind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Header,
+    Tag,
+    Length,
+    Payload,
+    Pointer,
+    Padding,
+    Unreferenced,
+    Invalid,
+}
+
+/// hexdump where every header field, tag, length prefix, payload, padding
+/// run and pointer (with its target) gets its own annotated line.
+pub fn run(args: &HexArgs, key: Option<&[u8; 32]>, format: Format, style: Style, out: &mut dyn Write) -> Result<(), Error> {
+    let buffer = input::read_zon(&args.file, key)?;
+    let (start, end) = args.range.unwrap_or((0, usize::MAX));
+    let end = end.min(buffer.len());
+    let start = start.min(end);
+
+    let spans = if args.plain {
+        (start..end)
+            .step_by(ROW)
+            .map(|at| Span { start: at, len: ROW.min(end - at), label: String::new(), kind: Kind::Payload })
+            .collect()
+    } else {
+        let reader = ZonReader::new(&buffer)?;
+        clip(fill_gaps(layout(&reader), &buffer), start, end)
+    };
+
+    match format {
+        Format::Json => {
+            let rows: Vec<serde_json::Value> = spans
+                .iter()
+                .map(|span| {
+                    let bytes = &buffer[span.start..span.start + span.len];
+                    let mut row = serde_json::json!({
+                        "offset": span.start,
+                        "length": span.len,
+                        "hex": hex_bytes(bytes),
+                    });
+                    if args.plain {
+                        row["ascii"] = ascii(bytes).into();
+                    } else {
+                        row["annotation"] = span.label.clone().into();
+                    }
+                    row
+                })
+                .collect();
+            write_json(out, &rows.into())
+        }
+        Format::Text => {
+            for span in &spans {
+                let bytes = &buffer[span.start..span.start + span.len];
+                for (i, row) in bytes.chunks(ROW).enumerate() {
+                    let note = match (i, args.plain) {
+                        (_, true) => format!("|{}|", ascii(row)),
+                        (0, false) => paint(style, span.kind, &span.label),
+                        _ => String::new(),
+                    };
+                    let line = format!("{:08x}  {:<47}  {}", span.start + i * ROW, hex_bytes(row), note);
+                    writeln!(out, "{}", line.trim_end()).map_err(Error::stdout)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn paint(style: Style, kind: Kind, label: &str) -> String {
+    match kind {
+        Kind::Header | Kind::Tag => style.ty(label),
+        Kind::Pointer => style.pointer(label),
+        Kind::Payload => style.value(label),
+        Kind::Length | Kind::Padding | Kind::Unreferenced => style.dim(label),
+        Kind::Invalid => style.error(label),
+    }
+}
+
+/// spans for the header and every node reachable from the root.
+fn layout(reader: &ZonReader) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut push = |start: usize, len: usize, label: String, kind: Kind| {
+        if len > 0 {
+            spans.push(Span { start, len, label, kind });
+        }
+    };
+
+    let root = reader.root().unwrap_or(0);
+    push(0, 4, format!("magic 0x{:08X} \"ZON!\"", ZON_MAGIC), Kind::Header);
+    push(4, 4, format!("version {}", reader.read_u32(4).unwrap_or(0)), Kind::Header);
+    push(8, 4, format!("root -> 0x{:04X} {}", root, target(reader, root)), Kind::Pointer);
+    push(12, 4, format!("flags 0x{:X}", reader.read_u32(12).unwrap_or(0)), Kind::Header);
+    push(16, HEADER_SIZE - 16, "reserved".to_string(), Kind::Padding);
+
+    if reader.read_tag(root).is_err() {
+        // untagged file: the most we can say is whether the root looks like a raw string
+        if root as usize >= HEADER_SIZE && reader.read_string(root).is_ok() {
+            string_spans(reader, root, "", &mut push);
+        }
+        return spans;
+    }
+
+    let mut seen = HashSet::new();
+    let mut stack = vec![root];
+    while let Some(offset) = stack.pop() {
+        if !seen.insert(offset) {
+            continue;
+        }
+        let at = offset as usize;
+        let value = match reader.value(offset) {
+            Ok(value) => value,
+            Err(e) => {
+                push(at, 4.min(reader.len().saturating_sub(at)), format!("<{}>", e), Kind::Invalid);
+                continue;
+            }
+        };
+        let tag = value.tag();
+        push(at, 4, format!("[0x{:04X}] {} node", offset, tag.name()), Kind::Tag);
+        match value {
+            ZonValue::Null => {}
+            ZonValue::Bool(b) => push(at + 4, 4, format!("value {}", b), Kind::Payload),
+            ZonValue::Int(_) | ZonValue::UInt(_) | ZonValue::Float(_) => {
+                push(at + 4, 4, "padding".to_string(), Kind::Padding);
+                push(at + 8, 8, format!("value {}", scalar(&value)), Kind::Payload);
+            }
+            ZonValue::String(_) | ZonValue::Binary(_) => {
+                string_spans(reader, offset + 4, "", &mut push);
+            }
+            ZonValue::Array(array) if array.element_tag().is_some() => {
+                push(at + 4, 4, format!("count {}", array.len()), Kind::Length);
+                typed_spans(reader, array, &mut push);
+            }
+            ZonValue::Array(array) => {
+                push(at + 4, 4, format!("count {}", array.len()), Kind::Length);
+                for i in 0..array.len() {
+                    let Ok(child) = array.offset_at(i) else { break };
+                    let label = format!("[{}] -> 0x{:04X} {}", i, child, target(reader, child));
+                    push(at + 8 + i * 4, 4, label, Kind::Pointer);
+                    stack.push(child);
+                }
+            }
+            ZonValue::Object(object) => {
+                push(at + 4, 4, format!("count {}", object.len()), Kind::Length);
+                for i in 0..object.len() {
+                    let slot = at + 8 + i * 8;
+                    let key_offset = reader.read_u32(slot as u32).unwrap_or(0);
+                    let Ok((key, child)) = object.entry(i) else {
+                        push(slot, 8, "<invalid field>".to_string(), Kind::Invalid);
+                        break;
+                    };
+                    push(slot, 4, format!("key -> 0x{:04X} {:?}", key_offset, key), Kind::Pointer);
+                    let label = format!("{} -> 0x{:04X} {}", key, child, target(reader, child));
+                    push(slot + 4, 4, label, Kind::Pointer);
+                    if seen.insert(key_offset) {
+                        string_spans(reader, key_offset, "key ", &mut push);
+                    }
+                    stack.push(child);
+                }
+            }
+        }
+    }
+    spans
+}
+
+/// inline elements of a typed array: one line per number or string,
+/// a single line for the bool bytes.
+fn typed_spans(reader: &ZonReader, array: ZonArray, push: &mut impl FnMut(usize, usize, String, Kind)) {
+    let data = array.offset() as usize + 8;
+    let len = array.len();
+    let item = |i: usize| array.get(i).map_or_else(|e| format!("<{}>", e), |v| scalar_text(&v));
+    match array.tag() {
+        Tag::BoolArray => {
+            push(data, len, format!("{} values", len), Kind::Payload);
+            push(data + len, (4 - len % 4) % 4, "padding".to_string(), Kind::Padding);
+        }
+        Tag::StringArray => {
+            push(data, (len + 1) * 4, format!("end offsets ({} entries)", len + 1), Kind::Length);
+            let bytes = data + (len + 1) * 4;
+            let mut end = 0;
+            for i in 0..len {
+                let start = reader.read_u32((data + i * 4) as u32).unwrap_or(0) as usize;
+                end = reader.read_u32((data + i * 4 + 4) as u32).unwrap_or(0) as usize;
+                push(bytes + start, end.saturating_sub(start), format!("[{}] {}", i, item(i)), Kind::Payload);
+            }
+            push(bytes + end, (4 - end % 4) % 4, "padding".to_string(), Kind::Padding);
+        }
+        _ => {
+            for i in 0..len {
+                push(data + i * 8, 8, format!("[{}] {}", i, item(i)), Kind::Payload);
+            }
+        }
+    }
+}
+
+/// length prefix, payload and padding of a raw length-prefixed string or blob.
+fn string_spans(reader: &ZonReader, offset: u32, prefix: &str, push: &mut impl FnMut(usize, usize, String, Kind)) {
+    let Ok(len) = reader.read_u32(offset) else { return };
+    let at = offset as usize;
+    let len = len as usize;
+    push(at, 4, format!("{}length {}", prefix, len), Kind::Length);
+    let payload = match reader.read_string(offset) {
+        Ok(s) if s.chars().count() > 32 => format!("{:?}…", s.chars().take(32).collect::<String>()),
+        Ok(s) => format!("{:?}", s),
+        Err(_) if reader.read_bytes(offset).is_ok() => format!("{} bytes", len),
+        Err(e) => {
+            push(at + 4, reader.len().saturating_sub(at + 4).min(len), format!("<{}>", e), Kind::Invalid);
+            return;
+        }
+    };
+    push(at + 4, len, format!("{}{}", prefix, payload), Kind::Payload);
+    push(at + 4 + len, (4 - len % 4) % 4, "padding".to_string(), Kind::Padding);
+}
+
+/// what a pointer points at, e.g. `string` or `<Invalid value tag>`.
+fn target(reader: &ZonReader, offset: u32) -> String {
+    match reader.read_tag(offset) {
+        Ok(Tag::String) => match reader.value(offset) {
+            Ok(ZonValue::String(s)) if s.len() <= 16 => format!("string {:?}", s),
+            _ => "string".to_string(),
+        },
+        Ok(tag) => tag.name().to_string(),
+        Err(e) => format!("<{}>", e),
+    }
+}
+
+fn scalar(value: &ZonValue) -> String {
+    match value {
+        ZonValue::Int(v) => v.to_string(),
+        ZonValue::UInt(v) => v.to_string(),
+        ZonValue::Float(v) => format!("{:?}", v),
+        _ => String::new(),
+    }
+}
+
+/// sorts spans, flags overlaps and labels the bytes no span covers.
+fn fill_gaps(mut spans: Vec<Span>, buffer: &[u8]) -> Vec<Span> {
+    spans.retain(|span| span.start < buffer.len());
+    for span in &mut spans {
+        span.len = span.len.min(buffer.len() - span.start);
+    }
+    spans.sort_by_key(|span| (span.start, span.len));
+
+    let mut out = Vec::with_capacity(spans.len());
+    let mut covered = 0;
+    for mut span in spans {
+        if span.start < covered {
+            span.label.push_str(" (overlaps)");
+            span.kind = Kind::Invalid;
+        } else if span.start > covered {
+            out.push(gap(covered, span.start, buffer));
+        }
+        covered = covered.max(span.start + span.len);
+        out.push(span);
+    }
+    if covered < buffer.len() {
+        out.push(gap(covered, buffer.len(), buffer));
+    }
+    out
+}
+
+fn gap(start: usize, end: usize, buffer: &[u8]) -> Span {
+    let (label, kind) = if buffer[start..end].iter().all(|&b| b == 0) {
+        ("padding", Kind::Padding)
+    } else {
+        ("unreferenced", Kind::Unreferenced)
+    };
+    Span { start, len: end - start, label: label.to_string(), kind }
+}
+
+/// cuts spans down to `start..end`.
+fn clip(spans: Vec<Span>, start: usize, end: usize) -> Vec<Span> {
+    spans
+        .into_iter()
+        .filter(|span| span.start < end && span.start + span.len > start)
+        .map(|mut span| {
+            let from = span.start.max(start);
+            let to = (span.start + span.len).min(end);
+            span.start = from;
+            span.len = to - from;
+            span
+        })
+        .collect()
+}
+
+fn hex_bytes(row: &[u8]) -> String {
+    row.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ")
+}
+
+fn ascii(row: &[u8]) -> String {
+    row.iter()
+        .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zon_lib::ZonWriter;
+
+    #[test]
+    fn test_layout_covers_nodes() {
+        let mut writer = ZonWriter::new();
+        let name = writer.write_text("Hero");
+        let root = writer.write_object(&[("name", name)]);
+        writer.set_root(root);
+        let buffer = writer.as_bytes();
+
+        let reader = ZonReader::new(buffer).unwrap();
+        let spans = fill_gaps(layout(&reader), buffer);
+        let labels: Vec<(usize, &str)> = spans.iter().map(|s| (s.start, s.label.as_str())).collect();
+
+        assert!(labels.contains(&(8, "root -> 0x0054 object")));
+        assert!(labels.contains(&(0x40, "[0x0040] string node")));
+        assert!(labels.contains(&(0x44, "length 4")));
+        assert!(labels.contains(&(0x48, "\"Hero\"")));
+        assert!(labels.contains(&(0x4C, "key length 4")));
+        assert!(labels.contains(&(0x5C, "key -> 0x004C \"name\"")));
+        assert!(labels.contains(&(0x60, "name -> 0x0040 string \"Hero\"")));
+
+        // every byte is covered exactly once
+        let mut at = 0;
+        for span in &spans {
+            assert_eq!(span.start, at);
+            at += span.len;
+        }
+        assert_eq!(at, buffer.len());
+    }
+
+    #[test]
+    fn test_clip() {
+        let spans = vec![
+            Span { start: 0, len: 8, label: "a".to_string(), kind: Kind::Header },
+            Span { start: 8, len: 8, label: "b".to_string(), kind: Kind::Header },
+        ];
+        let clipped = clip(spans, 4, 10);
+        assert_eq!((clipped[0].start, clipped[0].len), (4, 4));
+        assert_eq!((clipped[1].start, clipped[1].len), (8, 2));
+    }
+}
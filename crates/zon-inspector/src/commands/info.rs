@@ -0,0 +1,85 @@
+use std::io::Write;
+
+use zon_lib::header::ZON_MAGIC;
+use zon_lib::{ChunkedReader, ZonReader};
+
+use crate::cli::{FileArgs, Format};
+use crate::commands::{human_size, write_json};
+use crate::error::Error;
+use crate::input;
+
+pub fn run(args: &FileArgs, key: Option<&[u8; 32]>, format: Format, out: &mut dyn Write) -> Result<(), Error> {
+    let file = input::read(&args.file)?;
+    let signed = zon_lib::sign::is_signed(&file);
+    let cipher = zon_lib::encrypt::cipher(&file)?;
+    let decrypted = match cipher {
+        Some(_) => Some(zon_lib::decrypt(&file, input::key(&args.file, key)?)?),
+        None => None,
+    };
+    let raw = decrypted.as_deref().unwrap_or(&file);
+    let codec = zon_lib::compress::codec(raw)?;
+    let chunked = zon_lib::compress::is_chunked(raw).then(|| ChunkedReader::new(raw)).transpose()?;
+    let decompressed = codec.map(|_| zon_lib::decompress(raw)).transpose()?;
+    let buffer = decompressed.as_deref().unwrap_or(raw);
+    let reader = ZonReader::new(buffer)?;
+
+    let version = reader.read_u32(4)?;
+    let root = reader.root()?;
+    // files built from raw write_u32/write_string calls carry no type tags
+    let root_type = reader.read_tag(root).map(|t| t.name()).unwrap_or("raw");
+
+    match format {
+        Format::Json => write_json(
+            out,
+            &serde_json::json!({
+                "file": input::display_name(&args.file),
+                "size": file.len(),
+                "signed": signed,
+                "encryption": cipher.map(|cipher| cipher.name()),
+                "compression": codec.map(|codec| serde_json::json!({
+                    "codec": codec.name(),
+                    "size": buffer.len(),
+                    "ratio": ratio(buffer.len(), raw.len()),
+                    "chunks": chunked.as_ref().map(|chunked| chunked.chunk_count()),
+                    "chunk_size": chunked.as_ref().map(|chunked| chunked.chunk_size()),
+                })),
+                "magic": format!("0x{:08X}", ZON_MAGIC),
+                "version": version,
+                "root_offset": root,
+                "root_type": root_type,
+            }),
+        ),
+        Format::Text => {
+            let mut text = format!("File:     {}\nSize:     {}\n", input::display_name(&args.file), human_size(file.len()));
+            if signed {
+                text.push_str("Signed:   Ed25519\n");
+            }
+            if let Some(cipher) = cipher {
+                text.push_str(&format!("Cipher:   {}\n", cipher.name()));
+            }
+            if let Some(codec) = codec {
+                text.push_str(&format!(
+                    "Codec:    {}, {} decompressed ({:.1}x)\n",
+                    codec.name(),
+                    human_size(buffer.len()),
+                    ratio(buffer.len(), raw.len())
+                ));
+                if let Some(chunked) = &chunked {
+                    text.push_str(&format!("Chunks:   {} of {}\n", chunked.chunk_count(), human_size(chunked.chunk_size())));
+                }
+            }
+            text.push_str(&format!(
+                "Magic:    0x{:08X} (ZON!)\nVersion:  {}\nRoot:     0x{:08X} -> {}\n",
+                ZON_MAGIC,
+                version,
+                root,
+                root_type,
+            ));
+            out.write_all(text.as_bytes()).map_err(Error::stdout)
+        }
+    }
+}
+
+fn ratio(decompressed: usize, compressed: usize) -> f64 {
+    decompressed as f64 / compressed as f64
+}
@@ -0,0 +1,110 @@
+pub mod compact;
+pub mod convert;
+pub mod diff;
+pub mod dump;
+pub mod hex;
+pub mod info;
+pub mod query;
+pub mod sign;
+pub mod stats;
+pub mod tree;
+pub mod validate;
+pub mod verify;
+
+use std::io::Write;
+
+use zon_lib::value::MAX_DEPTH;
+use zon_lib::ZonValue;
+
+use crate::error::Error;
+
+/// calls `f` for `value` and every node below it, with its depth.
+pub fn visit(value: ZonValue, depth: usize, f: &mut dyn FnMut(&ZonValue, usize)) -> Result<(), Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::Invalid("Maximum nesting depth exceeded".to_string()));
+    }
+    f(&value, depth);
+    match value {
+        ZonValue::Array(array) => {
+            for item in array.iter() {
+                visit(item?, depth + 1, f)?;
+            }
+        }
+        ZonValue::Object(object) => {
+            for field in object.iter() {
+                visit(field?.1, depth + 1, f)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// a scalar as it appears in text output. containers show their size.
+pub fn scalar_text(value: &ZonValue) -> String {
+    match value {
+        ZonValue::Null => "null".to_string(),
+        ZonValue::Bool(b) => b.to_string(),
+        ZonValue::Int(v) => v.to_string(),
+        ZonValue::UInt(v) => v.to_string(),
+        ZonValue::Float(v) => format!("{:?}", v),
+        ZonValue::String(s) => format!("{:?}", s),
+        ZonValue::Binary(b) => format!("<{} bytes>", b.len()),
+        ZonValue::Array(a) => format!("[{} items]", a.len()),
+        ZonValue::Object(o) => format!("{{{} fields}}", o.len()),
+    }
+}
+
+/// a value in text output: scalars as in `scalar_text`, containers as compact JSON.
+pub fn value_text(value: &ZonValue) -> Result<String, Error> {
+    match value {
+        ZonValue::Array(_) | ZonValue::Object(_) => Ok(zon_lib::json::to_value(*value)?.to_string()),
+        scalar => Ok(scalar_text(scalar)),
+    }
+}
+
+/// a path for output, with `$` for the root.
+pub fn display_path(path: &str) -> &str {
+    if path.is_empty() {
+        "$"
+    } else {
+        path
+    }
+}
+
+/// `1234` -> `1.2 KB (1234 bytes)`.
+pub fn human_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {} ({} bytes)", size, UNITS[unit], bytes)
+}
+
+/// bytes as lowercase hex digits.
+pub fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn write_json(out: &mut dyn Write, json: &serde_json::Value) -> Result<(), Error> {
+    let text = serde_json::to_string_pretty(json).unwrap_or_else(|_| "{}".to_string());
+    writeln!(out, "{}", text).map_err(Error::stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(48), "48 bytes");
+        assert_eq!(human_size(1229), "1.2 KB (1229 bytes)");
+        assert_eq!(human_size(5 * 1024 * 1024), "5.0 MB (5242880 bytes)");
+    }
+}
@@ -0,0 +1,42 @@
+use std::io::Write;
+
+use zon_lib::json::to_value;
+use zon_lib::{Query, ZonReader};
+
+use crate::cli::{Format, QueryArgs};
+use crate::commands::{display_path, value_text, write_json};
+use crate::error::Error;
+use crate::input;
+
+/// prints every match as `path = value`, containers as compact JSON.
+/// fails the check when nothing matches, like `grep`.
+pub fn run(args: &QueryArgs, key: Option<&[u8; 32]>, format: Format, out: &mut dyn Write) -> Result<(), Error> {
+    let query = Query::parse(&args.path).map_err(|e| Error::Usage(format!("Error parsing query: {}", e)))?;
+    let buffer = input::read_zon(&args.file, key)?;
+    let reader = ZonReader::new(&buffer)?;
+    let matches = query.select_paths(reader.root_value()?)?;
+
+    match format {
+        Format::Json => {
+            let matches = matches
+                .iter()
+                .map(|(path, value)| Ok(serde_json::json!({ "path": display_path(path), "value": to_value(*value)? })))
+                .collect::<Result<Vec<_>, Error>>()?;
+            write_json(out, &serde_json::Value::Array(matches))?;
+        }
+        Format::Text => {
+            for (path, value) in &matches {
+                writeln!(out, "{} = {}", display_path(path), value_text(value)?).map_err(Error::stdout)?;
+            }
+            if matches.is_empty() {
+                eprintln!("{}: no matches", input::display_name(&args.file));
+            }
+        }
+    }
+
+    if matches.is_empty() {
+        Err(Error::CheckFailed)
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use std::io::Write;
+
+use crate::cli::{Format, SignArgs};
+use crate::commands::hex_string;
+use crate::error::Error;
+use crate::input;
+
+/// signs the file as it is on disk, container and all, and reports the
+/// public key that verifies it.
+pub fn run(args: &SignArgs, format: Format, out: &mut dyn Write) -> Result<(), Error> {
+    let buffer = input::read(&args.input)?;
+    let key = input::read_key(&args.key)?;
+    let signed = zon_lib::sign(&buffer, &key)?;
+    input::write(&args.output, &signed)?;
+
+    let public_key = hex_string(&zon_lib::sign::public_key(&key));
+    let report = match format {
+        Format::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "file": input::display_name(&args.input),
+            "size": signed.len(),
+            "public_key": public_key,
+        }))
+        .unwrap_or_else(|_| "{}".to_string()),
+        Format::Text => format!("{}: signed, public key {}", input::display_name(&args.input), public_key),
+    };
+    // the signed bytes own stdout when written there
+    if input::is_stdio(&args.output) {
+        eprintln!("{}", report);
+        Ok(())
+    } else {
+        writeln!(out, "{}", report).map_err(Error::stdout)
+    }
+}
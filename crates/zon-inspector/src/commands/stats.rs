@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use zon_lib::{ZonReader, ZonValue};
+
+use crate::cli::{FileArgs, Format};
+use crate::commands::{human_size, visit, write_json};
+use crate::error::Error;
+use crate::input;
+
+/// node counts per type, nesting depth and payload sizes.
+pub fn run(args: &FileArgs, key: Option<&[u8; 32]>, format: Format, out: &mut dyn Write) -> Result<(), Error> {
+    let buffer = input::read_zon(&args.file, key)?;
+    let reader = ZonReader::new(&buffer)?;
+
+    let mut counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let mut nodes = 0;
+    let mut max_depth = 0;
+    let mut string_bytes = 0;
+    let mut binary_bytes = 0;
+    visit(reader.root_value()?, 0, &mut |value, depth| {
+        nodes += 1;
+        max_depth = max_depth.max(depth);
+        *counts.entry(value.type_name()).or_default() += 1;
+        match value {
+            ZonValue::String(s) => string_bytes += s.len(),
+            ZonValue::Binary(b) => binary_bytes += b.len(),
+            _ => {}
+        }
+    })?;
+
+    match format {
+        Format::Json => write_json(
+            out,
+            &serde_json::json!({
+                "file": input::display_name(&args.file),
+                "size": buffer.len(),
+                "nodes": nodes,
+                "max_depth": max_depth,
+                "types": counts,
+                "string_bytes": string_bytes,
+                "binary_bytes": binary_bytes,
+            }),
+        ),
+        Format::Text => {
+            let mut text = format!(
+                "File:          {}\nSize:          {}\nNodes:         {}\nMax depth:     {}\nString bytes:  {}\nBinary bytes:  {}\nTypes:\n",
+                input::display_name(&args.file),
+                human_size(buffer.len()),
+                nodes,
+                max_depth,
+                string_bytes,
+                binary_bytes,
+            );
+            for (name, count) in &counts {
+                text.push_str(&format!("  {:<8} {}\n", name, count));
+            }
+            out.write_all(text.as_bytes()).map_err(Error::stdout)
+        }
+    }
+}
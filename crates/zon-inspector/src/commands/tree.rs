@@ -0,0 +1,234 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use zon_lib::value::MAX_DEPTH;
+use zon_lib::{ZonArray, ZonReader, ZonValue};
+
+use crate::cli::{Format, TreeArgs};
+use crate::commands::{human_size, scalar_text, write_json};
+use crate::error::Error;
+use crate::input;
+use crate::style::Style;
+
+/// renders the value tree with each node's offset, type and size.
+/// pointers are shown as `-> 0x0040`, shared nodes are printed again,
+/// cycles are cut off.
+pub fn run(args: &TreeArgs, key: Option<&[u8; 32]>, format: Format, style: Style, out: &mut dyn Write) -> Result<(), Error> {
+    let buffer = input::read_zon(&args.file, key)?;
+    let reader = ZonReader::new(&buffer)?;
+    let name = input::display_name(&args.file);
+
+    let root = reader.root()?;
+    if reader.read_tag(root).is_err() {
+        // not self-describing: fall back to guessing what the root points at
+        return raw_root(&reader, &name, format, out);
+    }
+
+    let limit = args.depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH);
+    let mut tree = Tree { reader, style, limit, text: String::new(), ancestors: Vec::new() };
+    let entries = tree.count(root)?;
+
+    match format {
+        Format::Json => {
+            let root = tree.node_json(None, root, 0)?;
+            write_json(
+                out,
+                &serde_json::json!({
+                    "file": name,
+                    "size": buffer.len(),
+                    "entries": entries,
+                    "root": root,
+                }),
+            )
+        }
+        Format::Text => {
+            tree.text.push_str(&format!("📦 ZON File: {}\n", name));
+            tree.text.push_str(&format!("├─ Size: {}\n", human_size(buffer.len())));
+            tree.text.push_str(&format!("├─ Entries: {}\n", entries));
+            tree.render("└─ ", "   ", "Root", root, 0);
+            out.write_all(tree.text.as_bytes()).map_err(Error::stdout)
+        }
+    }
+}
+
+struct Tree<'a> {
+    reader: ZonReader<'a>,
+    style: Style,
+    limit: usize,
+    text: String,
+    // offsets of the containers above the current node, to detect cycles
+    ancestors: Vec<u32>,
+}
+
+impl Tree<'_> {
+    // `line` prefixes this node's own line, `indent` prefixes its children.
+    fn render(&mut self, line: &str, indent: &str, label: &str, offset: u32, depth: usize) {
+        let style = self.style;
+        let head = format!("{}{} {}", line, style.key(label), style.pointer(&format!("-> 0x{:04X}", offset)));
+
+        if self.ancestors.contains(&offset) {
+            self.text.push_str(&format!("{} {}\n", head, style.error("(cycle)")));
+            return;
+        }
+        let value = match self.reader.value(offset) {
+            Ok(value) => value,
+            Err(e) => {
+                self.text.push_str(&format!("{} {}\n", head, style.error(&format!("<{}>", e))));
+                return;
+            }
+        };
+        let size = self.reader.node_size(offset).unwrap_or(0);
+        let summary = match value {
+            ZonValue::Array(array) if array.element_tag().is_some() => {
+                format!("[{}] {}", array.len(), style.value(&preview(array)))
+            }
+            ZonValue::Array(array) => format!("[{}]", array.len()),
+            ZonValue::Object(object) => format!("{{{}}}", object.len()),
+            scalar => style.value(&scalar_text(&scalar)),
+        };
+        self.text.push_str(&format!(
+            "{} {} {} {}",
+            head,
+            style.ty(value.type_name()),
+            summary,
+            style.dim(&format!("({} B)", size))
+        ));
+
+        let children = match self.children(value) {
+            Ok(children) => children,
+            Err(e) => {
+                self.text.push_str(&format!(" {}\n", style.error(&format!("<{}>", e))));
+                return;
+            }
+        };
+        if !children.is_empty() && depth >= self.limit {
+            self.text.push_str(&format!(" {}\n", style.dim("…")));
+            return;
+        }
+        self.text.push('\n');
+
+        self.ancestors.push(offset);
+        for (i, (label, child)) in children.iter().enumerate() {
+            let (branch, next) = if i + 1 == children.len() { ("└─ ", "   ") } else { ("├─ ", "│  ") };
+            let line = format!("{}{}", indent, branch);
+            self.render(&line, &format!("{}{}", indent, next), label, *child, depth + 1);
+        }
+        self.ancestors.pop();
+    }
+
+    fn node_json(&mut self, key: Option<&str>, offset: u32, depth: usize) -> Result<serde_json::Value, Error> {
+        let mut node = serde_json::Map::new();
+        if let Some(key) = key {
+            node.insert("key".to_string(), key.into());
+        }
+        node.insert("offset".to_string(), offset.into());
+        if self.ancestors.contains(&offset) {
+            node.insert("cycle".to_string(), true.into());
+            return Ok(node.into());
+        }
+
+        let value = self.reader.value(offset)?;
+        node.insert("type".to_string(), value.type_name().into());
+        node.insert("size".to_string(), self.reader.node_size(offset)?.into());
+
+        let children = self.children(value)?;
+        match value {
+            ZonValue::Array(array) if array.element_tag().is_some() => {
+                node.insert("values".to_string(), zon_lib::json::to_value(value)?);
+            }
+            ZonValue::Array(_) | ZonValue::Object(_) if depth >= self.limit && !children.is_empty() => {
+                node.insert("truncated".to_string(), true.into());
+            }
+            ZonValue::Array(_) | ZonValue::Object(_) => {
+                let name = if matches!(value, ZonValue::Array(_)) { "items" } else { "fields" };
+                self.ancestors.push(offset);
+                let items = children
+                    .iter()
+                    .map(|(label, child)| {
+                        let key = matches!(value, ZonValue::Object(_)).then_some(label.as_str());
+                        self.node_json(key, *child, depth + 1)
+                    })
+                    .collect::<Result<Vec<_>, _>>();
+                self.ancestors.pop();
+                node.insert(name.to_string(), items?.into());
+            }
+            scalar => {
+                node.insert("value".to_string(), zon_lib::json::to_value(scalar)?);
+            }
+        }
+        Ok(node.into())
+    }
+
+    /// number of distinct nodes reachable from `root`. shared nodes count once.
+    fn count(&self, root: u32) -> Result<usize, Error> {
+        let mut seen = HashSet::new();
+        let mut stack = vec![root];
+        while let Some(offset) = stack.pop() {
+            if seen.insert(offset) {
+                let value = self.reader.value(offset)?;
+                stack.extend(self.children(value)?.into_iter().map(|(_, child)| child));
+            }
+        }
+        Ok(seen.len())
+    }
+
+    /// labels and offsets of the direct children of a container.
+    /// typed arrays store their elements inline, so they have none.
+    fn children(&self, value: ZonValue) -> Result<Vec<(String, u32)>, &'static str> {
+        match value {
+            ZonValue::Array(array) if array.element_tag().is_some() => Ok(Vec::new()),
+            ZonValue::Array(array) => (0..array.len())
+                .map(|i| Ok((format!("[{}]", i), array.offset_at(i)?)))
+                .collect(),
+            ZonValue::Object(object) => (0..object.len())
+                .map(|i| object.entry(i).map(|(key, offset)| (key.to_string(), offset)))
+                .collect(),
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+/// the first few elements of a typed array, e.g. `1.5, 0.25, 8.0, …`.
+fn preview(array: ZonArray) -> String {
+    const SHOWN: usize = 8;
+    let mut items: Vec<String> = array
+        .iter()
+        .take(SHOWN)
+        .map(|item| item.map_or_else(|e| format!("<{}>", e), |item| scalar_text(&item)))
+        .collect();
+    if array.len() > SHOWN {
+        items.push("…".to_string());
+    }
+    items.join(", ")
+}
+
+fn raw_root(reader: &ZonReader, name: &str, format: Format, out: &mut dyn Write) -> Result<(), Error> {
+    let root = reader.root()?;
+    let guess = if root == 0 || root as usize >= reader.len() {
+        serde_json::Value::Null
+    } else if let Ok(s) = reader.read_string(root) {
+        serde_json::json!({ "type": "string", "value": s })
+    } else {
+        serde_json::json!({ "type": "u32", "value": reader.read_u32(root)? })
+    };
+
+    match format {
+        Format::Json => write_json(
+            out,
+            &serde_json::json!({ "file": name, "size": reader.len(), "root": guess }),
+        ),
+        Format::Text => {
+            let root_line = match (guess.get("type"), guess.get("value")) {
+                (Some(ty), Some(value)) => format!("Root ({}, untagged): {}", ty.as_str().unwrap_or("?"), value),
+                _ => "Root: none".to_string(),
+            };
+            let text = format!(
+                "📦 ZON File: {}\n├─ Size: {}\n└─ {}\n",
+                name,
+                human_size(reader.len()),
+                root_line
+            );
+            out.write_all(text.as_bytes()).map_err(Error::stdout)
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::io::Write;
+
+use zon_lib::schema::{self, Schema};
+use zon_lib::ZonReader;
+
+use crate::cli::{Format, ValidateArgs};
+use crate::commands::write_json;
+use crate::error::Error;
+use crate::input;
+
+/// checks a file against a `.zons` schema and lists every mismatch.
+pub fn run(args: &ValidateArgs, key: Option<&[u8; 32]>, format: Format, out: &mut dyn Write) -> Result<(), Error> {
+    let source = input::read(&args.schema)?;
+    let source = String::from_utf8_lossy(&source);
+    let schema = Schema::parse(&source)
+        .map_err(|e| Error::Invalid(format!("Error parsing schema '{}': {}", args.schema.display(), e)))?;
+
+    let buffer = input::read_zon(&args.file, key)?;
+    let reader = ZonReader::new(&buffer)?;
+    let name = input::display_name(&args.file);
+
+    let errors = schema::validate(&reader, &schema).err().unwrap_or_default();
+    match format {
+        Format::Json => {
+            let errors: Vec<serde_json::Value> = errors
+                .iter()
+                .map(|e| serde_json::json!({ "path": e.path, "message": e.message }))
+                .collect();
+            write_json(out, &serde_json::json!({ "file": name, "valid": errors.is_empty(), "errors": errors }))?;
+        }
+        Format::Text if errors.is_empty() => writeln!(out, "{}: valid", name).map_err(Error::stdout)?,
+        Format::Text => {
+            for error in &errors {
+                writeln!(out, "{}", error).map_err(Error::stdout)?;
+            }
+            eprintln!("{}: {} schema mismatch(es)", name, errors.len());
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::CheckFailed)
+    }
+}
@@ -0,0 +1,36 @@
+use std::io::Write;
+
+use crate::cli::{Format, VerifyArgs};
+use crate::commands::write_json;
+use crate::error::Error;
+use crate::input;
+
+/// checks the signature. a missing or mismatched one fails the check.
+pub fn run(args: &VerifyArgs, format: Format, out: &mut dyn Write) -> Result<(), Error> {
+    let buffer = input::read(&args.file)?;
+    let public_key = input::read_key(&args.public_key)?;
+    let result = zon_lib::verify_signature(&buffer, &public_key);
+    if result == Err("Invalid public key") {
+        return Err(Error::Usage(format!("{}: not a valid Ed25519 public key", args.public_key.display())));
+    }
+
+    let name = input::display_name(&args.file);
+    match format {
+        Format::Json => write_json(
+            out,
+            &serde_json::json!({
+                "file": name,
+                "valid": result.is_ok(),
+                "error": result.err(),
+            }),
+        )?,
+        Format::Text => {
+            let status = match result {
+                Ok(()) => "signature OK",
+                Err(message) => message,
+            };
+            writeln!(out, "{}: {}", name, status).map_err(Error::stdout)?;
+        }
+    }
+    result.map_err(|_| Error::CheckFailed)
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// everything a command can fail with. each kind maps to an exit code.
+#[derive(Debug)]
+pub enum Error {
+    Io { path: String, source: io::Error },
+    /// a malformed argument that clap cannot check, such as a query.
+    Usage(String),
+    /// the input is not valid ZON (or not a valid schema, JSON, ...).
+    Invalid(String),
+    /// the command ran but its check did not pass. details were already printed.
+    CheckFailed,
+}
+
+impl Error {
+    pub fn io(path: &Path, source: io::Error) -> Self {
+        Error::Io { path: path.display().to_string(), source }
+    }
+
+    pub fn stdout(source: io::Error) -> Self {
+        Error::Io { path: "<stdout>".to_string(), source }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::CheckFailed => 1,
+            Error::Usage(_) => 2,
+            Error::Io { .. } => 3,
+            Error::Invalid(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "Error accessing '{}': {}", path, source),
+            Error::Usage(message) | Error::Invalid(message) => f.write_str(message),
+            Error::CheckFailed => f.write_str("check failed"),
+        }
+    }
+}
+
+impl From<&'static str> for Error {
+    fn from(message: &'static str) -> Self {
+        Error::Invalid(format!("Error parsing ZON file: {}", message))
+    }
+}
@@ -0,0 +1,104 @@
+use std::fs;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+
+use zon_lib::ZonReader;
+
+use crate::error::Error;
+
+/// reads a 32-byte key file, raw or as 64 hex digits.
+pub fn read_key(path: &Path) -> Result<[u8; 32], Error> {
+    let bytes = fs::read(path).map_err(|e| Error::io(path, e))?;
+    parse_key(&bytes)
+        .ok_or_else(|| Error::Usage(format!("{}: expected a 32-byte key, raw or as 64 hex digits", path.display())))
+}
+
+/// the key from `--key-file`, needed to read the encrypted file at `path`.
+pub fn key<'k>(path: &Path, key: Option<&'k [u8; 32]>) -> Result<&'k [u8; 32], Error> {
+    key.ok_or_else(|| Error::Usage(format!("{} is encrypted, pass --key-file", display_name(path))))
+}
+
+/// reads a whole file, or stdin when the path is `-`.
+pub fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    if is_stdio(path) {
+        let mut buffer = Vec::new();
+        io::stdin().read_to_end(&mut buffer).map_err(|e| Error::io(path, e))?;
+        Ok(buffer)
+    } else {
+        fs::read(path).map_err(|e| Error::io(path, e))
+    }
+}
+
+/// reads a ZON file like `read`, decrypting and decompressing it as needed.
+/// `key` is the one from `--key-file`, if one was given.
+pub fn read_zon(path: &Path, key: Option<&[u8; 32]>) -> Result<Vec<u8>, Error> {
+    let buffer = read(path)?;
+    if zon_lib::encrypt::is_encrypted(&buffer) {
+        Ok(ZonReader::open_encrypted(&buffer, self::key(path, key)?)?.to_vec())
+    } else if zon_lib::compress::is_compressed(&buffer) {
+        Ok(zon_lib::decompress(&buffer)?.to_vec())
+    } else {
+        Ok(buffer)
+    }
+}
+
+/// opens a file for buffered streaming reads, or stdin when the path is `-`.
+pub fn open(path: &Path) -> Result<Box<dyn Read>, Error> {
+    if is_stdio(path) {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        let file = fs::File::open(path).map_err(|e| Error::io(path, e))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// writes a whole file, or stdout when the path is `-`.
+pub fn write(path: &Path, data: &[u8]) -> Result<(), Error> {
+    if is_stdio(path) {
+        io::stdout().write_all(data).map_err(Error::stdout)
+    } else {
+        fs::write(path, data).map_err(|e| Error::io(path, e))
+    }
+}
+
+/// the name to show for an input in output.
+pub fn display_name(path: &Path) -> String {
+    if is_stdio(path) {
+        "<stdin>".to_string()
+    } else {
+        path.display().to_string()
+    }
+}
+
+pub fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+fn parse_key(bytes: &[u8]) -> Option<[u8; 32]> {
+    if let Ok(key) = bytes.try_into() {
+        return Some(key);
+    }
+    let hex = std::str::from_utf8(bytes).ok()?.trim();
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut key = [0; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key(&[9; 32]), Some([9; 32]));
+        let hex = format!("{}\n", "0a".repeat(32));
+        assert_eq!(parse_key(hex.as_bytes()), Some([10; 32]));
+        assert_eq!(parse_key(b"0a0a"), None);
+        assert_eq!(parse_key("zz".repeat(32).as_bytes()), None);
+    }
+}
@@ -1,74 +1,65 @@
-use std::env;
-use std::fs;
+mod cli;
+mod commands;
+mod error;
+mod input;
+mod style;
+
+use std::io::{self, ErrorKind, Write};
 use std::process;
 
-use zon_lib::ZonReader;
+use clap::{CommandFactory, Parser};
+
+use cli::{Cli, Command, TreeArgs};
+use error::Error;
+use style::Style;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    
-    if args.len() < 2 {
-        eprintln!("Usage: zon-inspector <file.zon>");
-        process::exit(1);
-    }
-    
-    let file_path = &args[1];
-    
-    let buffer = match fs::read(file_path) {
-        Ok(data) => data,
-        Err(e) => {
-            eprintln!("Error reading file '{}': {}", file_path, e);
-            process::exit(1);
-        }
-    };
-    
-    let reader = match ZonReader::new(&buffer) {
-        Ok(r) => r,
-        Err(e) => {
-            eprintln!("Error parsing ZON file: {}", e);
-            process::exit(1);
-        }
-    };
-    
-    let json = to_json(&reader, &buffer);
-    println!("{}", json);
-}
+    let cli = Cli::parse();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
 
-/// traverses the ZON structure and converts it to JSON.
-/// 
-/// since ZON is a low-level format without self-describing schema,
-/// we inspect the header and dump raw data.
-fn to_json(reader: &ZonReader, buffer: &[u8]) -> String {
-    // read header fields
-    let magic = reader.read_u32(0).unwrap_or(0);
-    let version = reader.read_u32(4).unwrap_or(0);
-    let root = reader.read_u32(8).unwrap_or(0);
-    
-    // attempt to read root as a string (common case)
-    let root_value = if root > 0 && root < buffer.len() as u32 {
-        match reader.read_string(root) {
-            Ok(s) => serde_json::json!({ "type": "string", "value": s }),
-            Err(_) => {
-                // try reading as u32
-                match reader.read_u32(root) {
-                    Ok(v) => serde_json::json!({ "type": "u32", "value": v }),
-                    Err(_) => serde_json::json!({ "type": "unknown", "offset": root }),
-                }
+    let key = cli.key_file.as_deref().map(input::read_key).transpose();
+    let result = key.and_then(|key| match &cli.command {
+        Some(command) => run(command, &cli, key.as_ref(), &mut out),
+        // `zon-inspector <FILE>` shows the tree
+        None => match &cli.file {
+            Some(file) => {
+                let args = TreeArgs { file: file.clone(), depth: None };
+                commands::tree::run(&args, key.as_ref(), cli.format, Style::new(cli.color), &mut out)
+            }
+            None => {
+                let _ = Cli::command().print_help();
+                process::exit(2);
             }
-        }
-    } else {
-        serde_json::json!(null)
-    };
-    
-    let output = serde_json::json!({
-        "header": {
-            "magic": format!("0x{:08X}", magic),
-            "version": version,
-            "root_offset": root,
         },
-        "root": root_value,
-        "buffer_size": buffer.len(),
     });
-    
-    serde_json::to_string_pretty(&output).unwrap_or_else(|_| "{}".to_string())
+    let result = result.and_then(|()| out.flush().map_err(Error::stdout));
+
+    match result {
+        Ok(()) => {}
+        // the reader went away (e.g. `| head`), nothing left to report
+        Err(Error::Io { source, .. }) if source.kind() == ErrorKind::BrokenPipe => {}
+        Err(Error::CheckFailed) => process::exit(1),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(e.exit_code());
+        }
+    }
+}
+
+fn run(command: &Command, cli: &Cli, key: Option<&[u8; 32]>, out: &mut dyn Write) -> Result<(), Error> {
+    match command {
+        Command::Info(args) => commands::info::run(args, key, cli.format, out),
+        Command::Dump(args) => commands::dump::run(args, key, cli.format, out),
+        Command::Tree(args) => commands::tree::run(args, key, cli.format, Style::new(cli.color), out),
+        Command::Hex(args) => commands::hex::run(args, key, cli.format, Style::new(cli.color), out),
+        Command::Validate(args) => commands::validate::run(args, key, cli.format, out),
+        Command::Convert(args) => commands::convert::run(args, key, cli.format, out),
+        Command::Stats(args) => commands::stats::run(args, key, cli.format, out),
+        Command::Diff(args) => commands::diff::run(args, key, cli.format, out),
+        Command::Query(args) => commands::query::run(args, key, cli.format, out),
+        Command::Compact(args) => commands::compact::run(args, key, cli.format, out),
+        Command::Sign(args) => commands::sign::run(args, cli.format, out),
+        Command::Verify(args) => commands::verify::run(args, cli.format, out),
+    }
 }
@@ -0,0 +1,60 @@
+use std::env;
+use std::io::{self, IsTerminal};
+
+use crate::cli::ColorChoice;
+
+/// ANSI colouring for text output. a disabled style returns text unchanged.
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    enabled: bool,
+}
+
+impl Style {
+    /// `auto` colours only when stdout is a terminal and `NO_COLOR` is unset.
+    pub fn new(choice: ColorChoice) -> Self {
+        let enabled = match choice {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+        };
+        Self { enabled }
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.enabled {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// object keys and array indices.
+    pub fn key(&self, text: &str) -> String {
+        self.paint("1;36", text)
+    }
+
+    /// type names.
+    pub fn ty(&self, text: &str) -> String {
+        self.paint("35", text)
+    }
+
+    /// scalar values.
+    pub fn value(&self, text: &str) -> String {
+        self.paint("32", text)
+    }
+
+    /// offsets and pointer arrows.
+    pub fn pointer(&self, text: &str) -> String {
+        self.paint("33", text)
+    }
+
+    /// secondary details such as sizes.
+    pub fn dim(&self, text: &str) -> String {
+        self.paint("2", text)
+    }
+
+    /// problems found while walking the data.
+    pub fn error(&self, text: &str) -> String {
+        self.paint("1;31", text)
+    }
+}
@@ -1,7 +1,6 @@
 use wasm_bindgen::prelude::*;
-use zon_lib::{ZonWriter as RustZonWriter, ZonReader as RustZonReader};
-use serde_wasm_bindgen;
 use serde::Serialize;
+use zon_lib::{ZonBuf, ZonValue, ZonWriter as RustZonWriter};
 
 #[wasm_bindgen]
 pub struct ZonWriter {
@@ -36,38 +35,49 @@ impl ZonWriter {
         self.inner.len()
     }
 
+    #[wasm_bindgen(js_name = "isEmpty")]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
     #[wasm_bindgen(js_name = "toBytes")]
     pub fn to_bytes(&self) -> Vec<u8> {
         self.inner.as_bytes().to_vec()
     }
 }
 
+impl Default for ZonWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[wasm_bindgen]
 pub struct ZonReader {
-    buffer: Vec<u8>,
+    buffer: ZonBuf,
 }
 
 #[wasm_bindgen]
 impl ZonReader {
     #[wasm_bindgen(constructor)]
     pub fn new(buffer: Vec<u8>) -> Result<ZonReader, JsError> {
-        // Validate magic number etc. by trying to create a reader
-        RustZonReader::new(&buffer).map_err(|e| JsError::new(e))?;
+        // the header is checked once here, not on every read
+        let buffer = ZonBuf::from_vec(buffer).map_err(JsError::new)?;
         Ok(Self { buffer })
     }
 
     #[wasm_bindgen(js_name = "readU32")]
     pub fn read_u32(&self, offset: u32) -> Result<u32, JsError> {
-        let reader = RustZonReader::new(&self.buffer).map_err(|e| JsError::new(e))?;
-        reader.read_u32(offset).map_err(|e| JsError::new(e))
+        let reader = self.buffer.reader();
+        reader.read_u32(offset).map_err(JsError::new)
     }
 
     #[wasm_bindgen(js_name = "readString")]
     pub fn read_string(&self, offset: u32) -> Result<String, JsError> {
-        let reader = RustZonReader::new(&self.buffer).map_err(|e| JsError::new(e))?;
+        let reader = self.buffer.reader();
         reader.read_string(offset)
             .map(|s| s.to_string())
-            .map_err(|e| JsError::new(e))
+            .map_err(JsError::new)
     }
 
     #[wasm_bindgen(getter, js_name = "rootOffset")]
@@ -75,9 +85,137 @@ impl ZonReader {
         self.read_u32(8)
     }
 
+    /// offset of a required object field. throws if the field is missing.
+    pub fn field(&self, offset: u32, key: &str) -> Result<u32, JsError> {
+        self.find_field(offset, key)?
+            .ok_or_else(|| JsError::new(&format!("Missing field `{}`", key)))
+    }
+
+    /// offset of an object field, or `undefined` if it is missing.
+    #[wasm_bindgen(js_name = "findField")]
+    pub fn find_field(&self, offset: u32, key: &str) -> Result<Option<u32>, JsError> {
+        let object = self.value(offset)?.as_object().ok_or_else(|| JsError::new("Expected object"))?;
+        for i in 0..object.len() {
+            let (k, value) = object.entry(i).map_err(JsError::new)?;
+            if k == key {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    #[wasm_bindgen(js_name = "arrayLength")]
+    pub fn array_length(&self, offset: u32) -> Result<u32, JsError> {
+        let array = self.value(offset)?.as_array().ok_or_else(|| JsError::new("Expected array"))?;
+        Ok(array.len() as u32)
+    }
+
+    #[wasm_bindgen(js_name = "arrayOffset")]
+    pub fn array_offset(&self, offset: u32, index: u32) -> Result<u32, JsError> {
+        let array = self.value(offset)?.as_array().ok_or_else(|| JsError::new("Expected array"))?;
+        array.offset_at(index as usize).map_err(JsError::new)
+    }
+
+    #[wasm_bindgen(js_name = "readNull")]
+    pub fn read_null(&self, offset: u32) -> Result<JsValue, JsError> {
+        match self.value(offset)? {
+            ZonValue::Null => Ok(JsValue::NULL),
+            _ => Err(JsError::new("Expected null")),
+        }
+    }
+
+    #[wasm_bindgen(js_name = "readBool")]
+    pub fn read_bool(&self, offset: u32) -> Result<bool, JsError> {
+        self.value(offset)?.as_bool().ok_or_else(|| JsError::new("Expected bool"))
+    }
+
+    /// reads any number node as a JS number. integers beyond 2^53 lose precision.
+    #[wasm_bindgen(js_name = "readNumber")]
+    pub fn read_number(&self, offset: u32) -> Result<f64, JsError> {
+        self.value(offset)?.as_f64().ok_or_else(|| JsError::new("Expected number"))
+    }
+
+    /// reads a tagged string node.
+    #[wasm_bindgen(js_name = "readText")]
+    pub fn read_text(&self, offset: u32) -> Result<String, JsError> {
+        let value = self.value(offset)?;
+        value.as_str().map(|s| s.to_string()).ok_or_else(|| JsError::new("Expected string"))
+    }
+
+    #[wasm_bindgen(js_name = "readBinary")]
+    pub fn read_binary(&self, offset: u32) -> Result<Vec<u8>, JsError> {
+        let value = self.value(offset)?;
+        value.as_binary().map(|b| b.to_vec()).ok_or_else(|| JsError::new("Expected bytes"))
+    }
+
+    /// decodes the node at `offset` and everything below it into plain JS values.
+    #[wasm_bindgen(js_name = "readValue")]
+    pub fn read_value(&self, offset: u32) -> Result<JsValue, JsError> {
+        let json = zon_lib::json::to_value(self.value(offset)?).map_err(JsError::new)?;
+        Ok(json.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+    }
+
+    /// reads element `index` of the array at `offset` as a bool. unlike
+    /// `arrayOffset`, this also works on typed arrays, whose elements are
+    /// stored inline rather than as nodes.
+    #[wasm_bindgen(js_name = "readBoolAt")]
+    pub fn read_bool_at(&self, offset: u32, index: u32) -> Result<bool, JsError> {
+        self.element(offset, index)?.as_bool().ok_or_else(|| JsError::new("Expected bool"))
+    }
+
+    /// reads element `index` of the array at `offset` as a number, see `readBoolAt`.
+    #[wasm_bindgen(js_name = "readNumberAt")]
+    pub fn read_number_at(&self, offset: u32, index: u32) -> Result<f64, JsError> {
+        self.element(offset, index)?.as_f64().ok_or_else(|| JsError::new("Expected number"))
+    }
+
+    /// reads element `index` of the array at `offset` as a string, see `readBoolAt`.
+    #[wasm_bindgen(js_name = "readTextAt")]
+    pub fn read_text_at(&self, offset: u32, index: u32) -> Result<String, JsError> {
+        let value = self.element(offset, index)?;
+        value.as_str().map(|s| s.to_string()).ok_or_else(|| JsError::new("Expected string"))
+    }
+
+    /// decodes element `index` of the array at `offset`, see `readBoolAt`.
+    #[wasm_bindgen(js_name = "readValueAt")]
+    pub fn read_value_at(&self, offset: u32, index: u32) -> Result<JsValue, JsError> {
+        let json = zon_lib::json::to_value(self.element(offset, index)?).map_err(JsError::new)?;
+        Ok(json.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+    }
+
+    /// evaluates a path query such as `players[?score > 1000].name` against the
+    /// root and returns the matching values as an array.
+    pub fn query(&self, path: &str) -> Result<JsValue, JsError> {
+        let reader = self.buffer.reader();
+        let values = reader.query(path)?;
+        let json = values
+            .into_iter()
+            .map(zon_lib::json::to_value)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(JsError::new)?;
+        Ok(json.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+    }
+
     pub fn len(&self) -> usize {
         self.buffer.len()
     }
+
+    #[wasm_bindgen(js_name = "isEmpty")]
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
+
+impl ZonReader {
+    fn value(&self, offset: u32) -> Result<ZonValue<'_>, JsError> {
+        let reader = self.buffer.reader();
+        reader.value(offset).map_err(JsError::new)
+    }
+
+    fn element(&self, offset: u32, index: u32) -> Result<ZonValue<'_>, JsError> {
+        let array = self.value(offset)?.as_array().ok_or_else(|| JsError::new("Expected array"))?;
+        array.get(index as usize).map_err(JsError::new)
+    }
 }
 
 /// serialize a plain JS object to ZON format.